@@ -1,4 +1,4 @@
-use std::ops::Index;
+use std::ops::{Add, Index};
 
 use crate::interval::Interval;
 use crate::ray::Ray;
@@ -105,6 +105,13 @@ impl AABB {
         true
     }
 
+    /// true for boxes of objects that reach infinitely far, like planes
+    pub fn is_unbounded(&self) -> bool {
+        [self.x, self.y, self.z]
+            .iter()
+            .any(|i| i.min == f64::NEG_INFINITY || i.max == f64::INFINITY)
+    }
+
     pub fn longest_axis(&self) -> i32 {
         if self.x.size() < self.y.size() {
             return if self.x.size() < self.z.size() { 0 } else { 2 };
@@ -116,3 +123,14 @@ impl AABB {
         }
     }
 }
+
+impl Add<Vec3> for AABB {
+    type Output = AABB;
+    fn add(self, offset: Vec3) -> AABB {
+        AABB {
+            x: Interval::new(self.x.min + offset.x, self.x.max + offset.x),
+            y: Interval::new(self.y.min + offset.y, self.y.max + offset.y),
+            z: Interval::new(self.z.min + offset.z, self.z.max + offset.z),
+        }
+    }
+}
@@ -0,0 +1,267 @@
+use crate::camera::Camera;
+use crate::hit::Hittable;
+use crate::transform::{AnimatedTransform, Keyframe, Quat};
+use crate::vec3::Vec3;
+use image::{ImageBuffer, RgbImage};
+use std::fs;
+use std::path::Path;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CameraKey {
+    /// seconds
+    pub time: f64,
+    pub lookfrom: Vec3,
+    pub lookat: Vec3,
+    pub vfov: f64,
+}
+
+impl CameraKey {
+    pub fn new(time: f64, lookfrom: Vec3, lookat: Vec3, vfov: f64) -> Self {
+        Self {
+            time,
+            lookfrom,
+            lookat,
+            vfov,
+        }
+    }
+
+    /// the u, v, w axes `Camera::update` builds for this key
+    fn frame(&self, vup: Vec3) -> [Vec3; 3] {
+        let w = (self.lookfrom - self.lookat).normalize();
+        let u = vup.cross(w).normalize();
+        [u, w.cross(u), w]
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PathInterpolation {
+    Linear,
+    /// smooth curve through every key
+    CatmullRom,
+}
+
+/// where the camera is and what it looks at over time
+#[derive(Clone, Debug, PartialEq)]
+pub struct CameraPath {
+    keys: Vec<CameraKey>,
+    pub interpolation: PathInterpolation,
+}
+
+/// Catmull-Rom through (t1, p1) and (t2, p2) with the key times as knots. The tangent at
+/// a key is the velocity between its neighbours, so unevenly spaced keys keep a
+/// continuous speed instead of jumping at every key.
+fn catmull_rom(keys: [(f64, Vec3); 4], time: f64) -> Vec3 {
+    let [(t0, p0), (t1, p1), (t2, p2), (t3, p3)] = keys;
+    let h = t2 - t1;
+    let m1 = (p2 - p0) / (t2 - t0);
+    let m2 = (p3 - p1) / (t3 - t1);
+    let s = (time - t1) / h;
+    let s2 = s * s;
+    let s3 = s2 * s;
+    (2.0 * s3 - 3.0 * s2 + 1.0) * p1
+        + (s3 - 2.0 * s2 + s) * h * m1
+        + (3.0 * s2 - 2.0 * s3) * p2
+        + (s3 - s2) * h * m2
+}
+
+impl CameraPath {
+    pub fn new(mut keys: Vec<CameraKey>, interpolation: PathInterpolation) -> Self {
+        keys.sort_by(|a, b| a.time.total_cmp(&b.time));
+        Self {
+            keys,
+            interpolation,
+        }
+    }
+
+    /// the camera key at `time`, holding the first and last key outside of the path
+    pub fn at(&self, time: f64) -> Option<CameraKey> {
+        let last = self.keys.len().checked_sub(1)?;
+        let next = self.keys.partition_point(|k| k.time <= time);
+        if next == 0 {
+            return Some(self.keys[0]);
+        }
+        if next > last {
+            return Some(self.keys[last]);
+        }
+        let (a, b) = (self.keys[next - 1], self.keys[next]);
+        let t = (time - a.time) / (b.time - a.time);
+        let key = match self.interpolation {
+            PathInterpolation::Linear => CameraKey {
+                time,
+                lookfrom: (1.0 - t) * a.lookfrom + t * b.lookfrom,
+                lookat: (1.0 - t) * a.lookat + t * b.lookat,
+                vfov: (1.0 - t) * a.vfov + t * b.vfov,
+            },
+            PathInterpolation::CatmullRom => {
+                // the end keys are repeated, which gives one sided tangents at both ends
+                let before = self.keys[next.saturating_sub(2)];
+                let after = self.keys[(next + 1).min(last)];
+                let curve = |f: fn(&CameraKey) -> Vec3| {
+                    catmull_rom([before, a, b, after].map(|k| (k.time, f(&k))), time)
+                };
+                CameraKey {
+                    time,
+                    lookfrom: curve(|k| k.lookfrom),
+                    lookat: curve(|k| k.lookat),
+                    vfov: curve(|k| Vec3::ones() * k.vfov).x,
+                }
+            }
+        };
+        Some(key)
+    }
+
+    /// rigid motion that carries the camera of `open` along the path until `close`,
+    /// for motion blur within one frame. The field of view holds its value at `open`.
+    pub fn motion(&self, open: f64, close: f64, vup: Vec3) -> Option<AnimatedTransform> {
+        let start = self.at(open)?;
+        let from = start.frame(vup);
+        let steps = 8;
+        let mut keys = Vec::with_capacity(steps + 1);
+        for i in 0..=steps {
+            let time = open + (close - open) * i as f64 / steps as f64;
+            let key = self.at(time)?;
+            let rotation = Quat::between_frames(from, key.frame(vup));
+            let translation = key.lookfrom - rotation.rotate(start.lookfrom);
+            keys.push(Keyframe::new(time, translation, rotation, Vec3::ones()));
+        }
+        Some(AnimatedTransform::new(keys))
+    }
+}
+
+/// renders numbered frames of one scene. Animated objects move with the ray time,
+/// so every frame only sets the shutter to its own slice of time.
+pub struct Sequence {
+    pub first_frame: usize,
+    /// inclusive
+    pub last_frame: usize,
+    pub fps: f64,
+    /// fraction of the frame time the shutter is open, 0.5 is a 180 degree shutter
+    pub shutter: f64,
+    pub output_dir: String,
+    /// leaves frames that are already on disk alone, to resume a sequence
+    pub skip_existing: bool,
+    pub camera_path: Option<CameraPath>,
+}
+
+impl Sequence {
+    pub fn new(first_frame: usize, last_frame: usize, fps: f64, output_dir: &str) -> Self {
+        Self {
+            first_frame,
+            last_frame,
+            fps,
+            shutter: 0.5,
+            output_dir: output_dir.to_string(),
+            skip_existing: true,
+            camera_path: None,
+        }
+    }
+
+    pub fn frame_path(&self, frame: usize) -> String {
+        format!("{}/frame_{:04}.png", self.output_dir, frame)
+    }
+
+    pub fn render<T: Hittable>(&self, camera: &mut Camera, world: &T) {
+        if fs::create_dir_all(&self.output_dir).is_err() {
+            println!("Cannot create \"{}\".", self.output_dir);
+            return;
+        }
+        for frame in self.first_frame..=self.last_frame {
+            let path = self.frame_path(frame);
+            if self.skip_existing && Path::new(&path).exists() {
+                println!("Skipping \"{}\"", path);
+                continue;
+            }
+            let start = frame as f64 / self.fps;
+            camera.shutter_open = start;
+            camera.shutter_close = start + self.shutter / self.fps;
+            // the camera sits where the shutter opens and moves along the path while it is open
+            if let Some(path) = &self.camera_path {
+                if let Some(key) = path.at(start) {
+                    camera.lookfrom = key.lookfrom;
+                    camera.lookat = key.lookat;
+                    camera.vfov = key.vfov;
+                    camera.motion = path.motion(start, camera.shutter_close, camera.vup);
+                }
+            }
+            let mut img: RgbImage = ImageBuffer::new(camera.width as u32, camera.height as u32);
+            camera.render(world, &mut img);
+            match img.save(&path) {
+                Ok(_) => println!("Ouput frame as \"{}\"", path),
+                Err(_) => println!("Outputting frame {} fails.", frame),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_catmull_rom_through_keys() {
+        let keys: Vec<CameraKey> = (0..4)
+            .map(|i| {
+                let x = i as f64;
+                CameraKey::new(x, Vec3::new(x, x * x, 0.0), Vec3::zero(), 40.0 + x)
+            })
+            .collect();
+        let path = CameraPath::new(keys.clone(), PathInterpolation::CatmullRom);
+        for key in &keys {
+            let at = path.at(key.time).unwrap();
+            assert!((at.lookfrom - key.lookfrom).length() < 1e-12);
+            assert!((at.vfov - key.vfov).abs() < 1e-12);
+        }
+        // between the keys the curve is smooth, not a straight line
+        let mid = path.at(1.5).unwrap();
+        assert!((mid.lookfrom.y - 2.5).abs() > 1e-3);
+    }
+
+    #[test]
+    fn test_camera_motion_follows_path() {
+        let keys = vec![
+            CameraKey::new(0.0, Vec3::new(0.0, 1.0, 5.0), Vec3::zero(), 40.0),
+            CameraKey::new(1.0, Vec3::new(5.0, 1.0, 0.0), Vec3::zero(), 40.0),
+            CameraKey::new(
+                2.0,
+                Vec3::new(0.0, 2.0, -5.0),
+                Vec3::new(0.0, 1.0, 0.0),
+                40.0,
+            ),
+        ];
+        let path = CameraPath::new(keys, PathInterpolation::CatmullRom);
+        let vup = Vec3::new(0.0, 1.0, 0.0);
+        let motion = path.motion(0.25, 1.5, vup).unwrap();
+        let start = path.at(0.25).unwrap();
+        let from = start.frame(vup);
+        // at every key of the motion the camera of `open` lands on the path
+        for time in [0.25, 0.40625, 0.875, 1.5] {
+            let key = path.at(time).unwrap();
+            let xf = motion.at(time);
+            assert!((xf.apply_point(start.lookfrom) - key.lookfrom).length() < 1e-9);
+            let to = key.frame(vup);
+            for k in 0..3 {
+                assert!((xf.apply_vector(from[k]) - to[k]).length() < 1e-9);
+            }
+        }
+    }
+
+    #[test]
+    fn test_catmull_rom_keeps_speed_across_uneven_keys() {
+        // constant velocity with keys at uneven times stays a constant velocity
+        let keys: Vec<CameraKey> = [0.0, 0.5, 3.0, 3.5]
+            .iter()
+            .map(|&t| CameraKey::new(t, Vec3::new(2.0 * t, 0.0, 0.0), Vec3::zero(), 40.0))
+            .collect();
+        let path = CameraPath::new(keys, PathInterpolation::CatmullRom);
+        for i in 0..=35 {
+            let time = 0.1 * i as f64;
+            let at = path.at(time).unwrap();
+            assert!((at.lookfrom.x - 2.0 * time).abs() < 1e-9, "{}", time);
+        }
+        // and the velocity is the same on both sides of a key
+        let dt = 1e-6;
+        let before = (path.at(0.5).unwrap().lookfrom - path.at(0.5 - dt).unwrap().lookfrom) / dt;
+        let after = (path.at(0.5 + dt).unwrap().lookfrom - path.at(0.5).unwrap().lookfrom) / dt;
+        assert!((before - after).length() < 1e-4);
+    }
+}
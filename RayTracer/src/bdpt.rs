@@ -0,0 +1,564 @@
+use crate::camera::{Camera, Projection};
+use crate::hit::{HitRecord, Hittable};
+use crate::interval::Interval;
+use crate::light::Emitters;
+use crate::onb::Onb;
+use crate::ray::Ray;
+use crate::rt::random_f64;
+use crate::vec3::Vec3;
+use std::f64::consts::PI;
+
+// bidirectional path tracing (Veach 1997, in the formulation of pbrt): a camera
+// subpath and a light subpath starting on an emitter are joined in every possible
+// way, s light vertices with t camera vertices, and the strategies are weighted
+// with the power heuristic. Vertex pdfs are per unit area.
+//
+// Only the emissive primitives in `Camera::emitters` are handled bidirectionally.
+// The analytic lights and the environment keep being sampled from the camera
+// vertices like in the path tracer.
+
+/// contribution of a light subpath to pixel (x, y) of the film
+pub type Splat = (usize, usize, Vec3);
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum VertexKind {
+    Camera,
+    Light,
+    Surface,
+}
+
+#[derive(Clone)]
+struct Vertex {
+    kind: VertexKind,
+    point: Vec3,
+    normal: Vec3,
+    /// hit on the surface, the sampled point for light vertices
+    rec: Option<HitRecord>,
+    /// the ray of the subpath that arrived here
+    ray_in: Ray,
+    beta: Vec3,
+    /// specular scattering, which no connection can reproduce
+    delta: bool,
+    pdf_fwd: f64,
+    pdf_rev: f64,
+}
+
+impl Vertex {
+    fn new(kind: VertexKind, point: Vec3, ray_in: Ray, beta: Vec3) -> Self {
+        Self {
+            kind,
+            point,
+            normal: Vec3::zero(),
+            rec: None,
+            ray_in,
+            beta,
+            delta: false,
+            pdf_fwd: 0.0,
+            pdf_rev: 0.0,
+        }
+    }
+
+    /// `pdf` per solid angle around this vertex, turned into a pdf per area at `next`
+    fn convert_density(&self, pdf: f64, next: &Vertex) -> f64 {
+        let to = next.point - self.point;
+        let dist2 = to.squared_length();
+        if dist2 == 0.0 {
+            return 0.0;
+        }
+        let mut pdf = pdf / dist2;
+        if next.kind != VertexKind::Camera {
+            pdf *= (next.normal * to).abs() / dist2.sqrt();
+        }
+        pdf
+    }
+
+    /// the ray arriving here from `from`, in the medium of the subpath
+    fn ray_from(&self, from: Vec3) -> Ray {
+        Ray {
+            orig: from,
+            dir: self.point - from,
+            ..self.ray_in
+        }
+    }
+
+    /// BSDF times |cos| for light coming from `prev` and leaving towards `next`
+    fn eval(&self, prev: Vec3, next: Vec3) -> Vec3 {
+        let ray_in = self.ray_from(prev);
+        let rec = facing(self.rec.as_ref().unwrap(), ray_in.dir);
+        let mat = rec.mat_ptr.as_ref().unwrap();
+        mat.eval(&ray_in, &rec, (next - self.point).normalize())
+    }
+
+    fn emitted(&self) -> Vec3 {
+        match &self.rec {
+            Some(rec) => rec
+                .mat_ptr
+                .as_ref()
+                .unwrap()
+                .emitted(rec.u, rec.v, rec.point),
+            None => Vec3::zero(),
+        }
+    }
+
+    /// density of emitting towards `next`, emitters are two-sided and cosine weighted
+    fn pdf_light(&self, next: &Vertex) -> f64 {
+        let dir = (next.point - self.point).normalize();
+        let pdf_dir = 0.5 * (self.normal * dir).abs() / PI;
+        self.convert_density(pdf_dir, next)
+    }
+}
+
+/// `rec` with the normal and front_face as seen by a ray travelling along `dir_in`
+fn facing(rec: &HitRecord, dir_in: Vec3) -> HitRecord {
+    let outward = if rec.front_face {
+        rec.normal
+    } else {
+        -rec.normal
+    };
+    let mut rec = rec.clone();
+    rec.front_face = dir_in * outward < 0.0;
+    rec.normal = if rec.front_face { outward } else { -outward };
+    rec
+}
+
+pub struct Bdpt<'a> {
+    camera: &'a Camera,
+    emitters: Emitters<'a>,
+    /// whether light subpaths can be projected onto the film, the t = 1 strategy
+    connectable: bool,
+}
+
+impl<'a> Bdpt<'a> {
+    pub fn new(camera: &'a Camera) -> Self {
+        // only a pinhole has a film position for each direction
+        let connectable = camera.projection == Projection::Perspective
+            && camera.defocus_angle <= 0.0
+            && camera.motion.is_none();
+        Self {
+            camera,
+            emitters: Emitters::new(&camera.emitters),
+            connectable,
+        }
+    }
+
+    /// radiance estimate for pixel (i, j) and the splats of the light tracing strategy
+    pub fn sample<T: Hittable>(&self, world: &T, i: usize, j: usize) -> (Vec3, Vec<Splat>) {
+        let mut splats = Vec::new();
+        let mut ray_ = match self.camera.get_ray(i, j) {
+            Some(ray_) => ray_,
+            None => return (Vec3::zero(), splats),
+        };
+        // BSDFs are evaluated in RGB, the subpaths carry no wavelengths
+        ray_.lambda = Vec3::zero();
+        let max_depth = self.camera.max_depth.max(0) as usize;
+
+        let mut color = Vec3::zero();
+        let mut camera_path = vec![Vertex::new(
+            VertexKind::Camera,
+            ray_.orig,
+            ray_,
+            Vec3::ones(),
+        )];
+        let pdf_dir = match self.camera_importance(ray_.dir) {
+            Some((_, pdf_dir, _)) => pdf_dir,
+            None => 0.0,
+        };
+        self.random_walk(
+            world,
+            (ray_, Vec3::ones(), pdf_dir),
+            &mut camera_path,
+            max_depth + 2,
+            Some(&mut color),
+        );
+        let mut light_path = Vec::new();
+        if let Some(start) = self.light_origin(ray_.time, &mut light_path) {
+            self.random_walk(world, start, &mut light_path, max_depth + 1, None);
+        }
+
+        for t in 1..=camera_path.len() {
+            for s in 0..=light_path.len() {
+                let depth = (s + t) as i64 - 2;
+                if (s == 1 && t == 1) || depth < 0 || depth > max_depth as i64 {
+                    continue;
+                }
+                let (c, raster) = self.connect(world, &light_path, &camera_path, s, t);
+                match raster {
+                    Some((x, y)) => splats.push((x, y, c)),
+                    None => color += c,
+                }
+            }
+        }
+        (color, splats)
+    }
+
+    /// camera importance, direction pdf and film position of a ray leaving the pinhole along `dir`
+    fn camera_importance(&self, dir: Vec3) -> Option<(f64, f64, (usize, usize))> {
+        if !self.connectable {
+            return None;
+        }
+        let cam = self.camera;
+        let dir = dir.normalize();
+        let cos = -(dir * cam.w);
+        if cos <= 0.0 {
+            return None;
+        }
+        let corner = cam.pixel00 - 0.5 * (cam.pixel_u + cam.pixel_v);
+        let q = cam.center + (cam.focus_dist / cos) * dir - corner;
+        let x = q * cam.pixel_u / cam.pixel_u.squared_length();
+        let y = q * cam.pixel_v / cam.pixel_v.squared_length();
+        if x < 0.0 || y < 0.0 || x >= cam.width as f64 || y >= cam.height as f64 {
+            return None;
+        }
+        // film area on the plane at distance one
+        let area =
+            cam.pixel_u.length() * cam.width as f64 * cam.pixel_v.length() * cam.height as f64
+                / (cam.focus_dist * cam.focus_dist);
+        let cos2 = cos * cos;
+        Some((
+            1.0 / (area * cos2 * cos2),
+            1.0 / (area * cos2 * cos),
+            (x as usize, y as usize),
+        ))
+    }
+
+    /// a point on one of the emitters as light vertex, None without emitters
+    fn sample_emitter(&self, time: f64) -> Option<Vertex> {
+        let rec = self.emitters.sample(time)?;
+        let pdf_pos = self.emitters.pdf();
+        let ray_in = Ray::new(rec.point, rec.normal, time);
+        let mut vertex = Vertex::new(VertexKind::Light, rec.point, ray_in, Vec3::zero());
+        vertex.normal = rec.normal;
+        vertex.rec = Some(rec);
+        vertex.beta = vertex.emitted() / pdf_pos;
+        vertex.pdf_fwd = pdf_pos;
+        if vertex.beta.near_zero() {
+            return None;
+        }
+        Some(vertex)
+    }
+
+    /// starts the light subpath, returns the first ray with its throughput and pdf
+    fn light_origin(&self, time: f64, path: &mut Vec<Vertex>) -> Option<(Ray, Vec3, f64)> {
+        let vertex = self.sample_emitter(time)?;
+        let side = if random_f64(0.0, 1.0) < 0.5 {
+            1.0
+        } else {
+            -1.0
+        };
+        let local = Vec3::random_cosine_direction();
+        let dir = Onb::new(side * vertex.normal).local(local);
+        let pdf_dir = 0.5 * local.z / PI;
+        if pdf_dir <= 0.0 {
+            return None;
+        }
+        let beta = vertex.beta * (local.z / pdf_dir);
+        let ray_ = Ray::new(vertex.point, dir, time);
+        path.push(vertex);
+        Some((ray_, beta, pdf_dir))
+    }
+
+    /// extends `path` from the ray, throughput and direction pdf in `start` until it
+    /// leaves the scene or has `max_vertices`. `color` collects what the camera subpath gathers from the lights and the environment.
+    fn random_walk<T: Hittable>(
+        &self,
+        world: &T,
+        start: (Ray, Vec3, f64),
+        path: &mut Vec<Vertex>,
+        max_vertices: usize,
+        mut color: Option<&mut Vec3>,
+    ) {
+        let cam = self.camera;
+        let (mut ray_, mut beta, mut pdf_dir) = start;
+        // pdf of the last bounce for the MIS against environment sampling, zero for camera rays
+        let mut bsdf_pdf = 0.0;
+        while path.len() < max_vertices {
+            let mut rec = HitRecord::new(Vec3::zero(), Vec3::zero(), 0.0, false, None);
+            if !world.hit(&ray_, Interval::new(0.001, f64::INFINITY), &mut rec) {
+                if let Some(color) = color.as_deref_mut() {
+                    *color += beta.cor_dot(cam.escaped(&ray_, bsdf_pdf));
+                }
+                break;
+            }
+            rec.set_footprint(&ray_);
+            beta = beta.cor_dot(ray_.media.transmittance(rec.t * ray_.dir.length()));
+            let mut vertex = Vertex::new(VertexKind::Surface, rec.point, ray_, beta);
+            vertex.normal = rec.normal;
+            vertex.pdf_fwd = path.last().unwrap().convert_density(pdf_dir, &vertex);
+            if let Some(color) = color.as_deref_mut() {
+                *color += beta.cor_dot(cam.sample_lights(&ray_, &rec, world));
+            }
+
+            let mat = rec.mat_ptr.clone().unwrap();
+            let mut scattered = Ray::new(Vec3::zero(), Vec3::zero(), 0.0);
+            let mut attenuation = Vec3::zero();
+            if !mat.scatter(&ray_, &rec, &mut attenuation, &mut scattered) {
+                vertex.rec = Some(rec);
+                path.push(vertex);
+                break;
+            }
+            scattered.media = mat.next_media(&ray_, &rec, &scattered);
+            scattered.follow_cone(&ray_, rec.t);
+            scattered.lambda = Vec3::zero();
+            pdf_dir = mat.scattering_pdf(&ray_, &rec, scattered.dir);
+            vertex.delta = pdf_dir == 0.0;
+            let pdf_rev = if vertex.delta {
+                0.0
+            } else {
+                // density of scattering back along the arriving ray, seen from the other end
+                let reversed = Ray {
+                    orig: rec.point + scattered.dir,
+                    dir: -scattered.dir,
+                    ..scattered
+                };
+                mat.scattering_pdf(
+                    &reversed,
+                    &facing(&rec, reversed.dir),
+                    -ray_.dir.normalize(),
+                )
+            };
+            let prev = path.last_mut().unwrap();
+            prev.pdf_rev = vertex.convert_density(pdf_rev, prev);
+            vertex.rec = Some(rec);
+            path.push(vertex);
+
+            beta = beta.cor_dot(attenuation);
+            bsdf_pdf = pdf_dir;
+            ray_ = scattered;
+            if beta.near_zero() {
+                break;
+            }
+        }
+    }
+
+    /// pdf per area at `next` of sampling it from `v`, which was reached from `prev`
+    fn pdf(&self, v: &Vertex, prev: Option<&Vertex>, next: &Vertex) -> f64 {
+        match v.kind {
+            VertexKind::Camera => match self.camera_importance(next.point - v.point) {
+                Some((_, pdf_dir, _)) => v.convert_density(pdf_dir, next),
+                None => 0.0,
+            },
+            VertexKind::Light => v.pdf_light(next),
+            VertexKind::Surface => {
+                let prev = match prev {
+                    Some(prev) => prev,
+                    None => return 0.0,
+                };
+                let ray_in = v.ray_from(prev.point);
+                let rec = facing(v.rec.as_ref().unwrap(), ray_in.dir);
+                let mat = rec.mat_ptr.as_ref().unwrap();
+                let pdf_dir = mat.scattering_pdf(&ray_in, &rec, (next.point - v.point).normalize());
+                v.convert_density(pdf_dir, next)
+            }
+        }
+    }
+
+    /// density of `v` as the start of a light subpath, zero on emissive surfaces that
+    /// are missing from the camera's emitters
+    fn pdf_light_origin(&self, v: &Vertex) -> f64 {
+        match &v.rec {
+            Some(rec) if !v.emitted().near_zero() && self.emitters.contains(rec, v.ray_in.time) => {
+                self.emitters.pdf()
+            }
+            _ => 0.0,
+        }
+    }
+
+    fn visible<T: Hittable>(&self, world: &T, a: &Vertex, b: &Vertex) -> bool {
+        let to = b.point - a.point;
+        let dist = to.length();
+        let ray_ = Ray::new(a.point, to / dist, a.ray_in.time);
+        !self.camera.occluded(world, &ray_, dist)
+    }
+
+    /// the path of `s` light and `t` camera vertices, None for the film position unless t = 1
+    fn connect<T: Hittable>(
+        &self,
+        world: &T,
+        light_path: &[Vertex],
+        camera_path: &[Vertex],
+        s: usize,
+        t: usize,
+    ) -> (Vec3, Option<(usize, usize)>) {
+        let none = (Vec3::zero(), None);
+        let pt = &camera_path[t - 1];
+        let mut sampled = None;
+        let mut raster = None;
+        let color = if s == 0 {
+            // the camera subpath found an emitter on its own
+            if pt.kind != VertexKind::Surface {
+                return none;
+            }
+            pt.beta.cor_dot(pt.emitted())
+        } else if t == 1 {
+            let qs = &light_path[s - 1];
+            if qs.delta || qs.kind != VertexKind::Surface {
+                return none;
+            }
+            let camera = &camera_path[0];
+            let (importance, _, position) = match self.camera_importance(qs.point - camera.point) {
+                Some(we) => we,
+                None => return none,
+            };
+            let to = camera.point - qs.point;
+            let dist2 = to.squared_length();
+            let cos = to.normalize() * self.camera.w;
+            let mut vertex = camera.clone();
+            vertex.beta = Vec3::ones() * (importance * cos / dist2);
+            let color = qs
+                .beta
+                .cor_dot(qs.eval(light_path[s - 2].point, camera.point))
+                .cor_dot(vertex.beta);
+            if color.near_zero() || !self.visible(world, qs, camera) {
+                return none;
+            }
+            raster = Some(position);
+            sampled = Some(vertex);
+            color.cor_dot(qs.ray_in.media.transmittance(dist2.sqrt()))
+        } else if s == 1 {
+            // a fresh point on an emitter instead of the start of the light subpath
+            if pt.delta || pt.kind != VertexKind::Surface {
+                return none;
+            }
+            let light = match self.sample_emitter(pt.ray_in.time) {
+                Some(light) => light,
+                None => return none,
+            };
+            let to = light.point - pt.point;
+            let dist2 = to.squared_length();
+            let cos_light = (light.normal * to).abs() / dist2.sqrt();
+            let color = pt
+                .beta
+                .cor_dot(pt.eval(camera_path[t - 2].point, light.point))
+                .cor_dot(light.beta)
+                * (cos_light / dist2);
+            if color.near_zero() || !self.visible(world, pt, &light) {
+                return none;
+            }
+            sampled = Some(light);
+            color.cor_dot(pt.ray_in.media.transmittance(dist2.sqrt()))
+        } else {
+            let qs = &light_path[s - 1];
+            if qs.delta || pt.delta || pt.kind != VertexKind::Surface {
+                return none;
+            }
+            let dist2 = (qs.point - pt.point).squared_length();
+            let color = qs
+                .beta
+                .cor_dot(qs.eval(light_path[s - 2].point, pt.point))
+                .cor_dot(pt.eval(camera_path[t - 2].point, qs.point))
+                .cor_dot(pt.beta)
+                / dist2;
+            if color.near_zero() || !self.visible(world, pt, qs) {
+                return none;
+            }
+            color.cor_dot(pt.ray_in.media.transmittance(dist2.sqrt()))
+        };
+        if color.near_zero() {
+            return none;
+        }
+        let weight = self.mis_weight(light_path, camera_path, sampled.as_ref(), s, t);
+        (weight * color, raster)
+    }
+
+    /// power heuristic over all the (s, t) splits that could have made the same path
+    fn mis_weight(
+        &self,
+        light_path: &[Vertex],
+        camera_path: &[Vertex],
+        sampled: Option<&Vertex>,
+        s: usize,
+        t: usize,
+    ) -> f64 {
+        if s + t == 2 {
+            return 1.0;
+        }
+        let pt = match (t, sampled) {
+            (1, Some(v)) => v,
+            _ => &camera_path[t - 1],
+        };
+        let qs = match (s, sampled) {
+            (0, _) => None,
+            (1, Some(v)) => Some(v),
+            _ => Some(&light_path[s - 1]),
+        };
+        let pt_minus = if t > 1 {
+            Some(&camera_path[t - 2])
+        } else {
+            None
+        };
+        let qs_minus = if s > 1 {
+            Some(&light_path[s - 2])
+        } else {
+            None
+        };
+
+        // (pdf_fwd, pdf_rev, delta) of both subpaths as changed by this connection
+        let state = |v: &Vertex| (v.pdf_fwd, v.pdf_rev, v.delta);
+        let mut camera: Vec<_> = camera_path[..t].iter().map(state).collect();
+        let mut light: Vec<_> = light_path[..s].iter().map(state).collect();
+        camera[t - 1] = state(pt);
+        camera[t - 1].2 = false;
+        match qs {
+            Some(qs) => {
+                light[s - 1] = state(qs);
+                light[s - 1].2 = false;
+                camera[t - 1].1 = self.pdf(qs, qs_minus, pt);
+                light[s - 1].1 = self.pdf(pt, pt_minus, qs);
+                if let Some(qs_minus) = qs_minus {
+                    light[s - 2].1 = self.pdf(qs, Some(pt), qs_minus);
+                }
+            }
+            None => {
+                camera[t - 1].1 = self.pdf_light_origin(pt);
+                if camera[t - 1].1 == 0.0 {
+                    // an emitter the light subpaths never start from
+                    return 1.0;
+                }
+            }
+        }
+        if let Some(pt_minus) = pt_minus {
+            camera[t - 2].1 = match qs {
+                Some(qs) => self.pdf(pt, Some(qs), pt_minus),
+                None => pt.pdf_light(pt_minus),
+            };
+        }
+
+        let remap = |f: f64| if f != 0.0 { f } else { 1.0 };
+        let mut sum = 0.0;
+        let mut ratio = 1.0;
+        for i in (1..t).rev() {
+            ratio *= remap(camera[i].1) / remap(camera[i].0);
+            if !camera[i].2 && !camera[i - 1].2 && (i > 1 || self.connectable) {
+                sum += ratio * ratio;
+            }
+        }
+        ratio = 1.0;
+        for i in (0..s).rev() {
+            ratio *= remap(light[i].1) / remap(light[i].0);
+            let delta_light = i > 0 && light[i - 1].2;
+            if !light[i].2 && !delta_light {
+                sum += ratio * ratio;
+            }
+        }
+        1.0 / (1.0 + sum)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_camera_importance_center() {
+        let mut camera = Camera::init(200, 2.0);
+        camera.vfov = 90.0;
+        camera.update();
+        let bdpt = Bdpt::new(&camera);
+        let (we, pdf_dir, (x, y)) = bdpt.camera_importance(-camera.w).unwrap();
+        // the film spans [-2, 2] x [-1, 1] at distance one
+        assert!((we - 1.0 / 8.0).abs() < 1e-9);
+        assert!((pdf_dir - 1.0 / 8.0).abs() < 1e-9);
+        assert_eq!((x, y), (100, 50));
+        assert!(bdpt.camera_importance(camera.w).is_none());
+    }
+}
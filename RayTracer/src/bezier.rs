@@ -0,0 +1,273 @@
+use crate::material::Material;
+use crate::mesh::{EdgeTarget, TriangleMesh};
+use crate::vec3::Vec3;
+use std::fs;
+use std::io;
+use std::sync::Arc;
+
+/// the four cubic Bernstein polynomials at t and their derivatives
+fn bernstein(t: f64) -> ([f64; 4], [f64; 4]) {
+    let s = 1.0 - t;
+    (
+        [s * s * s, 3.0 * t * s * s, 3.0 * t * t * s, t * t * t],
+        [
+            -3.0 * s * s,
+            3.0 * s * s - 6.0 * t * s,
+            6.0 * t * s - 3.0 * t * t,
+            3.0 * t * t,
+        ],
+    )
+}
+
+/// bicubic Bézier patch over 4 by 4 control points, row by row with u along the rows
+#[derive(Clone, Copy)]
+pub struct BezierPatch {
+    pub points: [Vec3; 16],
+}
+
+impl BezierPatch {
+    pub fn new(points: [Vec3; 16]) -> Self {
+        Self { points }
+    }
+
+    /// point, dpdu and dpdv at (u, v)
+    pub fn eval(&self, u: f64, v: f64) -> (Vec3, Vec3, Vec3) {
+        let (bu, dbu) = bernstein(u);
+        let (bv, dbv) = bernstein(v);
+        let (mut p, mut dpdu, mut dpdv) = (Vec3::zero(), Vec3::zero(), Vec3::zero());
+        for j in 0..4 {
+            for i in 0..4 {
+                let cp = self.points[4 * j + i];
+                p += bu[i] * bv[j] * cp;
+                dpdu += dbu[i] * bv[j] * cp;
+                dpdv += bu[i] * dbv[j] * cp;
+            }
+        }
+        (p, dpdu, dpdv)
+    }
+
+    /// unit normal at (u, v), looked up a little inside the patch where an edge
+    /// collapses into a point, like at the tip of the teapot lid
+    pub fn normal(&self, u: f64, v: f64) -> Vec3 {
+        let (mut u, mut v) = (u, v);
+        for _ in 0..8 {
+            let (_, dpdu, dpdv) = self.eval(u, v);
+            let n = dpdu.cross(dpdv);
+            if n.length() > 1e-12 {
+                return n.normalize();
+            }
+            u += 0.01 * (0.5 - u);
+            v += 0.01 * (0.5 - v);
+        }
+        Vec3::zero()
+    }
+
+    /// control points of an edge: v = 0, u = 1, v = 1 and u = 0 for k from 0 to 3
+    fn edge(&self, k: usize) -> [Vec3; 4] {
+        let p = &self.points;
+        match k {
+            0 => [p[0], p[1], p[2], p[3]],
+            1 => [p[3], p[7], p[11], p[15]],
+            2 => [p[12], p[13], p[14], p[15]],
+            _ => [p[0], p[4], p[8], p[12]],
+        }
+    }
+
+    /// grid of the patch, with the vertices of each edge on the polyline of that
+    /// edge's own rate so that neighbours sharing the edge meet without cracks
+    fn tessellate(&self, target: &EdgeTarget, mesh: &mut TriangleMesh) {
+        let rates = [0, 1, 2, 3].map(|k| target.segments(&self.edge(k)));
+        let n = (0..4)
+            .flat_map(|i| {
+                let row: Vec<Vec3> = (0..4).map(|k| self.points[4 * i + k]).collect();
+                let column: Vec<Vec3> = (0..4).map(|k| self.points[4 * k + i]).collect();
+                [target.segments(&row), target.segments(&column)]
+            })
+            .chain(rates)
+            .max()
+            .unwrap_or(1);
+        // position along an edge with m segments, s running from 0 to n
+        let on_edge = |m: usize, s: usize, at: &dyn Fn(f64) -> Vec3| -> Vec3 {
+            let x = (s * m) as f64 / n as f64;
+            let k = (x.floor() as usize).min(m - 1);
+            let f = x - k as f64;
+            (1.0 - f) * at(k as f64 / m as f64) + f * at((k + 1) as f64 / m as f64)
+        };
+        let base = mesh.positions.len();
+        let uvs = mesh.uvs.get_or_insert_with(Vec::new);
+        let normals = mesh.normals.get_or_insert_with(Vec::new);
+        for j in 0..=n {
+            for i in 0..=n {
+                let (u, v) = (i as f64 / n as f64, j as f64 / n as f64);
+                let p = if j == 0 {
+                    on_edge(rates[0], i, &|t| self.eval(t, 0.0).0)
+                } else if j == n {
+                    on_edge(rates[2], i, &|t| self.eval(t, 1.0).0)
+                } else if i == 0 {
+                    on_edge(rates[3], j, &|t| self.eval(0.0, t).0)
+                } else if i == n {
+                    on_edge(rates[1], j, &|t| self.eval(1.0, t).0)
+                } else {
+                    self.eval(u, v).0
+                };
+                mesh.positions.push(p);
+                normals.push(self.normal(u, v));
+                uvs.push((u, v));
+            }
+        }
+        for j in 0..n {
+            for i in 0..n {
+                let a = base + j * (n + 1) + i;
+                let (b, c, d) = (a + 1, a + n + 2, a + n + 1);
+                mesh.indices.push([a, b, c]);
+                mesh.indices.push([a, c, d]);
+            }
+        }
+    }
+}
+
+/// patches in the Newell teapot format: the number of patches, a line of 16 one-based
+/// vertex indices for each, the number of vertices and a line of x, y, z for each
+pub fn read_patches(filename: &str) -> io::Result<Vec<BezierPatch>> {
+    let text = fs::read_to_string(filename)?;
+    let invalid = || io::Error::new(io::ErrorKind::InvalidData, "malformed patch file");
+    let mut lines = text.lines().map(str::trim).filter(|line| !line.is_empty());
+    let numbers = |line: Option<&str>| -> io::Result<Vec<f64>> {
+        line.ok_or_else(invalid)?
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|s| !s.is_empty())
+            .map(|s| s.parse::<f64>().map_err(|_| invalid()))
+            .collect()
+    };
+    let count = numbers(lines.next())?
+        .first()
+        .copied()
+        .ok_or_else(invalid)? as usize;
+    let mut indices = Vec::with_capacity(count);
+    for _ in 0..count {
+        let row = numbers(lines.next())?;
+        if row.len() != 16 {
+            return Err(invalid());
+        }
+        indices.push(row);
+    }
+    let vertex_count = numbers(lines.next())?
+        .first()
+        .copied()
+        .ok_or_else(invalid)? as usize;
+    let mut vertices = Vec::with_capacity(vertex_count);
+    for _ in 0..vertex_count {
+        match numbers(lines.next())?[..] {
+            [x, y, z] => vertices.push(Vec3::new(x, y, z)),
+            _ => return Err(invalid()),
+        }
+    }
+    indices
+        .into_iter()
+        .map(|row| {
+            let mut points = [Vec3::zero(); 16];
+            for (point, &index) in points.iter_mut().zip(&row) {
+                let index = (index as usize).checked_sub(1).ok_or_else(invalid)?;
+                *point = *vertices.get(index).ok_or_else(invalid)?;
+            }
+            Ok(BezierPatch::new(points))
+        })
+        .collect()
+}
+
+/// one mesh of all patches, diced until their edges are about as long as the target
+pub fn tessellate(
+    patches: &[BezierPatch],
+    target: &EdgeTarget,
+    mat: Arc<dyn Material>,
+) -> TriangleMesh {
+    let mut mesh = TriangleMesh::new(Vec::new(), Vec::new(), mat);
+    for patch in patches {
+        patch.tessellate(target, &mut mesh);
+    }
+    mesh
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::camera::Camera;
+    use crate::hit::{HitRecord, Hittable};
+    use crate::interval::Interval;
+    use crate::material::Lambertian;
+    use crate::ray::Ray;
+
+    #[test]
+    fn test_read_patches_rejects_bad_indices() {
+        let row: Vec<String> = (1..=16).map(|k| k.to_string()).collect();
+        let vertices = "0 0 0\n".repeat(16);
+        let path = std::env::temp_dir().join(format!("ray_tracer_test_{}.bpt", std::process::id()));
+        let filename = path.to_str().unwrap();
+        let mut results = Vec::new();
+        for first in ["1", "0", "17"] {
+            let indices = format!("{},{}", first, row[1..].join(","));
+            fs::write(&path, format!("1\n{}\n16\n{}", indices, vertices)).unwrap();
+            results.push(read_patches(filename).map(|patches| patches.len()));
+        }
+        fs::remove_file(&path).unwrap();
+        assert_eq!(results[0].as_ref().unwrap(), &1);
+        // indices are one-based, so 0 is as out of range as one past the vertices
+        assert!(results[1].is_err() && results[2].is_err());
+    }
+
+    #[test]
+    fn test_shared_edge_without_cracks() {
+        // two patches side by side bulging towards +z, the left one much closer to
+        // the camera. Along v they follow z = 3 v (1 - v) and are straight along u.
+        let patch = |x0: f64| {
+            let mut points = [Vec3::zero(); 16];
+            for (k, point) in points.iter_mut().enumerate() {
+                let (i, j) = (k % 4, k / 4);
+                let z = if j == 1 || j == 2 { 1.0 } else { 0.0 };
+                *point = Vec3::new(x0 + i as f64, j as f64, z);
+            }
+            BezierPatch::new(points)
+        };
+        let (left, right) = (patch(0.0), patch(3.0));
+        let (p, dpdu, dpdv) = left.eval(0.5, 0.5);
+        assert!((p - Vec3::new(1.5, 1.5, 0.75)).length() < 1e-12);
+        assert!((dpdu.cross(dpdv).normalize() - Vec3::new(0.0, 0.0, 1.0)).length() < 1e-12);
+
+        let mut camera = Camera::init(400, 1.0);
+        camera.lookfrom = Vec3::new(-2.0, 1.5, 1.0);
+        let target = EdgeTarget::new(&camera, 40.0);
+        let mat = Arc::new(Lambertian::new(Vec3::ones()));
+        let mesh = tessellate(&[left, right], &target, mat);
+        // the finer left patch puts its vertices of the shared edge at x = 3 on the
+        // polyline of the right patch
+        let uvs = mesh.uvs.clone().unwrap();
+        let edge = |u: f64| {
+            let mut points: Vec<Vec3> = (0..mesh.positions.len())
+                .filter(|&i| uvs[i].0 == u && (mesh.positions[i].x - 3.0).abs() < 1e-12)
+                .map(|i| mesh.positions[i])
+                .collect();
+            points.sort_by(|a, b| a.y.total_cmp(&b.y));
+            points
+        };
+        let (from_left, from_right) = (edge(1.0), edge(0.0));
+        assert!(from_left.len() > from_right.len());
+        for p in from_left {
+            let k = from_right.partition_point(|q| q.y < p.y - 1e-12);
+            let (a, b) = (from_right[k.saturating_sub(1)], from_right[k]);
+            let f = if b.y > a.y {
+                (p.y - a.y) / (b.y - a.y)
+            } else {
+                0.0
+            };
+            assert!((a.z + f * (b.z - a.z) - p.z).abs() < 1e-12);
+        }
+
+        let bvh = mesh.into_bvh();
+        let mut rec = HitRecord::new(Vec3::zero(), Vec3::zero(), 0.0, false, None);
+        let ray_ = Ray::new(Vec3::new(4.2, 1.5, 5.0), Vec3::new(0.0, 0.0, -1.0), 0.0);
+        assert!(bvh.hit(&ray_, Interval::new(0.001, f64::INFINITY), &mut rec));
+        assert!((rec.t - 4.25).abs() < 1e-9);
+        assert!((rec.u - 0.4).abs() < 1e-9 && (rec.v - 0.5).abs() < 1e-9);
+        assert!((rec.normal - Vec3::new(0.0, 0.0, 1.0)).length() < 1e-9);
+    }
+}
@@ -58,15 +58,30 @@ impl Bvh_Node {
             let mut objects = objects;
             objects[start..end].sort_by(comparator);
             let mid = start + object_span / 2;
-            left = Arc::new(Bvh_Node::new(objects.clone(), start, mid));
-            right = Arc::new(Bvh_Node::new(objects, mid, end));
+            // each child only takes its own half, which keeps large meshes from
+            // copying the whole list at every node
+            left = Arc::new(Bvh_Node::new(objects[start..mid].to_vec(), 0, mid - start));
+            right = Arc::new(Bvh_Node::new(objects[mid..end].to_vec(), 0, end - mid));
         }
 
         Self { left, right, bbox }
     }
 
     pub fn new_by_list(hit_list: &HitList) -> Self {
-        Bvh_Node::new(hit_list.list.clone(), 0, hit_list.list.len())
+        // unbounded objects like planes would stretch every box above them,
+        // so they sit in a list next to the tree of everything else
+        let (unbounded, bounded): (Vec<_>, Vec<_>) = hit_list
+            .list
+            .iter()
+            .cloned()
+            .partition(|object| object.bounding_box().is_unbounded());
+        if unbounded.is_empty() || bounded.is_empty() {
+            return Bvh_Node::new(hit_list.list.clone(), 0, hit_list.list.len());
+        }
+        let left: Arc<dyn Hittable> = Arc::new(Bvh_Node::new(bounded.clone(), 0, bounded.len()));
+        let right: Arc<dyn Hittable> = Arc::new(HitList::new(unbounded));
+        let bbox = AABB::new_by_aabb(&left.bounding_box(), &right.bounding_box());
+        Self { left, right, bbox }
     }
 }
 
@@ -75,11 +90,23 @@ impl Hittable for Bvh_Node {
         self.bbox
     }
 
+    fn finite_bounding_box(&self) -> AABB {
+        if !self.bbox.is_unbounded() {
+            return self.bbox;
+        }
+        AABB::new_by_aabb(
+            &self.left.finite_bounding_box(),
+            &self.right.finite_bounding_box(),
+        )
+    }
+
     fn hit(&self, ray_: &Ray, ray_t: Interval, rec: &mut HitRecord) -> bool {
         let mut ray_t_ref = ray_t.clone();
         if self.bbox.hit(ray_, &mut ray_t_ref) {
             let hit_left = self.left.hit(ray_, ray_t.clone(), rec);
-            let hit_right = self.right.hit(ray_, ray_t.clone(), rec);
+            // the right child only counts if it is closer than the left hit
+            let right_t = Interval::new(ray_t.min, if hit_left { rec.t } else { ray_t.max });
+            let hit_right = self.right.hit(ray_, right_t, rec);
             hit_left || hit_right
         } else {
             false
@@ -1,13 +1,91 @@
+use crate::bdpt::Bdpt;
 use crate::color::write_color;
+use crate::distribution::Distribution1D;
+use crate::environment::Environment;
 use crate::hit::{HitRecord, Hittable};
 use crate::interval::Interval;
+use crate::lens::{Aperture, ThinLens};
+use crate::light::Light;
+use crate::mlt::Pssmlt;
 use crate::random_f64;
 use crate::ray::Ray;
+use crate::rt::power_heuristic;
+use crate::spectrum::{is_terminated, rgb_to_spectrum, sample_wavelengths, spectrum_to_rgb};
+use crate::sppm::Sppm;
+use crate::transform::AnimatedTransform;
 use crate::vec3::Vec3;
 use image::RgbImage;
 use indicatif::ProgressBar;
 use rayon::prelude::*;
+use std::f64::consts::PI;
 use std::io::SeekFrom;
+use std::sync::Arc;
+/// how `get_ray` maps the image plane to rays, all use the lookfrom/lookat/vup basis
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Projection {
+    /// thin lens perspective from `vfov`, `defocus_angle` and `focus_dist`
+    Perspective,
+    /// parallel rays along the view direction, `height` is the visible extent in world units
+    Orthographic { height: f64 },
+    /// equidistant fisheye, `fov` in degrees up to 360 across the shorter image side
+    Fisheye { fov: f64 },
+    /// full sphere latitude-longitude panorama, best with a 2:1 image
+    Equirectangular,
+    /// panorama on a cylinder around vup, `hfov` in degrees across the image width
+    Cylindrical { hfov: f64 },
+}
+
+/// how `render` estimates the light arriving through each pixel
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Integrator {
+    PathTracer,
+    /// bidirectional path tracing, for light that mostly arrives through caustics
+    /// or small openings. Needs the emissive primitives in `emitters`, renders in RGB.
+    Bdpt,
+    /// stochastic progressive photon mapping for caustics, one pass per sample.
+    /// Photons come from `emitters`, `lights` and the environment, renders in RGB.
+    Sppm {
+        photons_per_pass: usize,
+        /// gather radius of the first pass in world units
+        initial_radius: f64,
+    },
+    /// primary sample space Metropolis light transport over the path tracer, for
+    /// light that is hard to find. `samples_per_pixel` is the mean number of mutations
+    /// per pixel, run in `chains` Markov chains started from `bootstrap` paths.
+    Pssmlt {
+        chains: usize,
+        bootstrap: usize,
+        /// chance of a mutation being a fresh independent path
+        large_step_probability: f64,
+        /// size of the small mutations in primary sample space
+        sigma: f64,
+    },
+}
+
+/// how far the shutter is open over the exposure, the weight of each ray time
+#[derive(Clone, Debug, PartialEq)]
+pub enum ShutterCurve {
+    Box,
+    Triangle,
+    /// piecewise constant, the values are spread evenly over the exposure
+    Custom(Vec<f64>),
+}
+
+impl ShutterCurve {
+    fn distribution(&self) -> Distribution1D {
+        let n = 64;
+        let at = |x: f64| match self {
+            ShutterCurve::Box => 1.0,
+            ShutterCurve::Triangle => 1.0 - (2.0 * x - 1.0).abs(),
+            ShutterCurve::Custom(values) => {
+                let i = ((x * values.len() as f64) as usize).min(values.len().max(1) - 1);
+                values.get(i).copied().unwrap_or(1.0).max(0.0)
+            }
+        };
+        Distribution1D::new((0..n).map(|i| at((i as f64 + 0.5) / n as f64)).collect())
+    }
+}
+
 pub struct Camera {
     pub vfov: f64,
     pub width_height_ratio: f64,
@@ -30,6 +108,28 @@ pub struct Camera {
     pub defocus_disk_u: Vec3,
     pub defocus_disk_v: Vec3,
     pub background: Vec3,
+    pub lights: Vec<Arc<dyn Light>>,
+    /// replaces `background` for escaping rays when set
+    pub environment: Option<Arc<dyn Environment>>,
+    /// trace hero wavelengths instead of RGB, needed for dispersion
+    pub spectral: bool,
+    pub projection: Projection,
+    /// when set, `vfov`, `focus_dist` and `defocus_angle` are derived from it
+    pub lens: Option<ThinLens>,
+    pub aperture: Aperture,
+    /// strength of the optical vignetting that clips off-axis bokeh into a cat's eye
+    pub cats_eye: f64,
+    /// exposure interval, the time of every ray falls in between
+    pub shutter_open: f64,
+    pub shutter_close: f64,
+    pub shutter_curve: ShutterCurve,
+    shutter: Distribution1D,
+    /// moves the whole camera over time, applied to each ray at its time
+    pub motion: Option<AnimatedTransform>,
+    pub integrator: Integrator,
+    /// emissive primitives the light subpaths of `Integrator::Bdpt` start from,
+    /// they also have to be part of the world
+    pub emitters: Vec<Arc<dyn Hittable>>,
 }
 
 impl Camera {
@@ -76,10 +176,29 @@ impl Camera {
             defocus_disk_u: defocus_radius * u,
             defocus_disk_v: defocus_radius * v,
             background: Vec3::default(),
+            lights: Vec::new(),
+            environment: None,
+            spectral: false,
+            projection: Projection::Perspective,
+            lens: None,
+            aperture: Aperture::Circle,
+            cats_eye: 0.0,
+            shutter_open: 0.0,
+            shutter_close: 1.0,
+            shutter_curve: ShutterCurve::Box,
+            shutter: ShutterCurve::Box.distribution(),
+            motion: None,
+            integrator: Integrator::PathTracer,
+            emitters: Vec::new(),
         }
     }
 
     pub fn update(&mut self) {
+        if let Some(lens) = self.lens {
+            self.vfov = lens.vfov(self.width_height_ratio);
+            self.focus_dist = lens.focus_dist;
+            self.defocus_angle = lens.defocus_angle();
+        }
         let viewport_height = 2.0 * ((0.5 * self.vfov).to_radians().tan()) * self.focus_dist;
         let viewport_width = viewport_height * self.width_height_ratio;
         self.center = self.lookfrom;
@@ -96,49 +215,248 @@ impl Camera {
         let defocus_radius = self.focus_dist * (0.5_f64 * self.defocus_angle).to_radians().tan();
         self.defocus_disk_u = defocus_radius * self.u;
         self.defocus_disk_v = defocus_radius * self.v;
+        self.shutter = self.shutter_curve.distribution();
     }
 
-    pub fn get_ray(&self, i: usize, j: usize) -> Ray {
+    /// None for pixels the projection does not cover, such as the corners of a fisheye
+    pub fn get_ray(&self, i: usize, j: usize) -> Option<Ray> {
         let offset = Vec3::sample_square();
-        let pixel_sample = self.pixel00
-            + (i as f64 + offset.x) * self.pixel_u
-            + (j as f64 + offset.y) * self.pixel_v;
-        let ray_ori = if self.defocus_angle <= 0.0 {
-            self.center
+        let (ray_ori, ray_dir) = match self.projection {
+            Projection::Perspective => {
+                let pixel_sample = self.pixel00
+                    + (i as f64 + offset.x) * self.pixel_u
+                    + (j as f64 + offset.y) * self.pixel_v;
+                let ray_ori = if self.defocus_angle <= 0.0 {
+                    self.center
+                } else {
+                    self.defocus_disk_sample(i as f64 + offset.x, j as f64 + offset.y)?
+                };
+                (ray_ori, pixel_sample - ray_ori)
+            }
+            _ => {
+                // image coordinates in [0, 1], t grows downwards
+                let s = (i as f64 + 0.5 + offset.x) / self.width as f64;
+                let t = (j as f64 + 0.5 + offset.y) / self.height as f64;
+                self.panoramic_ray(s, t)?
+            }
+        };
+        let ray_time = self.sample_time();
+        let (ray_ori, ray_dir) = match &self.motion {
+            Some(motion) => {
+                let xf = motion.at(ray_time);
+                (xf.apply_point(ray_ori), xf.apply_vector(ray_dir))
+            }
+            None => (ray_ori, ray_dir),
+        };
+        let mut ray_ = if self.spectral {
+            let lambda = sample_wavelengths(random_f64(0.0, 1.0));
+            Ray::new_by_lambda(ray_ori, ray_dir, ray_time, lambda)
         } else {
-            self.defocus_disk_sample()
+            Ray::new(ray_ori, ray_dir, ray_time)
         };
-        let ray_dir = pixel_sample - ray_ori;
-        let ray_time = random_f64(0.0, 1.0);
-        Ray::new(ray_ori, ray_dir, ray_time)
+        (ray_.cone_width, ray_.cone_spread) = self.pixel_cone();
+        Some(ray_)
+    }
+
+    /// width at the origin and spread per unit of distance of the cone through one pixel
+    /// a time over the exposure, drawn by the weight the shutter curve gives it
+    pub fn sample_time(&self) -> f64 {
+        let (x, _, _) = self.shutter.sample_continuous(random_f64(0.0, 1.0));
+        self.shutter_open + (self.shutter_close - self.shutter_open) * x
+    }
+
+    fn pixel_cone(&self) -> (f64, f64) {
+        match self.projection {
+            // pixel_u is one pixel wide at focus_dist
+            Projection::Perspective => (0.0, self.pixel_u.length() / self.focus_dist),
+            Projection::Orthographic { height } => (height / self.height as f64, 0.0),
+            Projection::Fisheye { fov } => {
+                (0.0, fov.to_radians() / self.width.min(self.height) as f64)
+            }
+            Projection::Equirectangular => (0.0, 2.0 * PI / self.width as f64),
+            Projection::Cylindrical { hfov } => (0.0, hfov.to_radians() / self.width as f64),
+        }
+    }
+
+    /// origin and direction for the projections other than the perspective one
+    fn panoramic_ray(&self, s: f64, t: f64) -> Option<(Vec3, Vec3)> {
+        let aspect = self.width as f64 / self.height as f64;
+        match self.projection {
+            Projection::Perspective => None,
+            Projection::Orthographic { height } => {
+                let width = height * aspect;
+                let ray_ori =
+                    self.center + (s - 0.5) * width * self.u - (t - 0.5) * height * self.v;
+                Some((ray_ori, -self.w))
+            }
+            Projection::Fisheye { fov } => {
+                let (x, y) = if aspect >= 1.0 {
+                    ((2.0 * s - 1.0) * aspect, 1.0 - 2.0 * t)
+                } else {
+                    (2.0 * s - 1.0, (1.0 - 2.0 * t) / aspect)
+                };
+                let r = (x * x + y * y).sqrt();
+                if r > 1.0 {
+                    return None;
+                }
+                let theta = r * 0.5 * fov.to_radians();
+                let (sin_phi, cos_phi) = if r > 0.0 { (y / r, x / r) } else { (0.0, 1.0) };
+                let dir =
+                    theta.sin() * (cos_phi * self.u + sin_phi * self.v) - theta.cos() * self.w;
+                Some((self.center, dir))
+            }
+            Projection::Equirectangular => {
+                let phi = (s - 0.5) * 2.0 * PI;
+                let lat = (0.5 - t) * PI;
+                let dir =
+                    lat.cos() * (phi.sin() * self.u - phi.cos() * self.w) + lat.sin() * self.v;
+                Some((self.center, dir))
+            }
+            Projection::Cylindrical { hfov } => {
+                let hfov = hfov.to_radians();
+                let phi = (s - 0.5) * hfov;
+                // square pixels: the cylinder height matches the arc length across the image
+                let y = (0.5 - t) * hfov / aspect;
+                let dir = phi.sin() * self.u - phi.cos() * self.w + y * self.v;
+                Some((self.center, dir))
+            }
+        }
     }
 
     pub fn ray_color<T: Hittable>(&self, ray_: Ray, world: &T, depth: i32) -> Vec3 {
+        self.trace(ray_, world, depth, 0.0)
+    }
+
+    /// `bsdf_pdf` is the density with which the previous bounce picked `ray_`,
+    /// zero for camera rays and specular bounces
+    fn trace<T: Hittable>(&self, ray_: Ray, world: &T, depth: i32, bsdf_pdf: f64) -> Vec3 {
         if depth <= 0 {
             return Vec3::new(0.0, 0.0, 0.0);
         }
         let mut rec = HitRecord::new(Vec3::zero(), Vec3::zero(), 0.0, false, None);
         if !world.hit(&ray_, Interval::new(0.001, f64::INFINITY), &mut rec) {
-            return self.background.clone();
+            return self.escaped(&ray_, bsdf_pdf);
         }
+        rec.set_footprint(&ray_);
         let mut scattered = Ray::new(Vec3::zero(), Vec3::zero(), 0.0);
         let mut attenuation = Vec3::zero();
-        let color_from_emission = rec
-            .mat_ptr
-            .as_ref()
-            .unwrap()
-            .emitted(rec.u, rec.v, rec.point);
-        if rec
-            .mat_ptr
-            .as_ref()
-            .unwrap()
-            .scatter(&ray_, &rec, &mut attenuation, &mut scattered)
-        {
+        let mat = rec.mat_ptr.as_ref().unwrap();
+        // absorption along the segment inside a dielectric
+        let transmittance =
+            self.upsample(ray_.media.transmittance(rec.t * ray_.dir.length()), &ray_);
+        let color_from_emission = self.upsample(mat.emitted(rec.u, rec.v, rec.point), &ray_);
+        let color_from_lights = self.sample_lights(&ray_, &rec, world);
+        if mat.scatter(&ray_, &rec, &mut attenuation, &mut scattered) {
+            let pdf = mat.scattering_pdf(&ray_, &rec, scattered.dir);
+            scattered.media = mat.next_media(&ray_, &rec, &scattered);
+            scattered.follow_cone(&ray_, rec.t);
+            let mut throughput = self.upsample(attenuation, &ray_);
+            if ray_.is_spectral() {
+                if scattered.lambda == Vec3::zero() {
+                    scattered.lambda = ray_.lambda;
+                } else if !is_terminated(ray_.lambda) && is_terminated(scattered.lambda) {
+                    // the hero wavelength carries on alone and takes over the others' weight
+                    throughput = Vec3::new(3.0 * throughput.x, 0.0, 0.0);
+                }
+            }
             let color_from_scatter =
-                attenuation.cor_dot(self.ray_color(scattered, world, depth - 1));
-            return color_from_emission + color_from_scatter;
+                throughput.cor_dot(self.trace(scattered, world, depth - 1, pdf));
+            return transmittance
+                .cor_dot(color_from_emission + color_from_lights + color_from_scatter);
+        }
+        transmittance.cor_dot(color_from_emission + color_from_lights)
+    }
+
+    /// RGB quantities of the scene become values at the ray's wavelengths in spectral mode
+    fn upsample(&self, rgb: Vec3, ray_: &Ray) -> Vec3 {
+        if ray_.is_spectral() {
+            rgb_to_spectrum(rgb, ray_.lambda)
+        } else {
+            rgb
+        }
+    }
+
+    /// radiance of a ray leaving the scene, MIS weighted against environment sampling
+    pub(crate) fn escaped(&self, ray_: &Ray, bsdf_pdf: f64) -> Vec3 {
+        match &self.environment {
+            Some(env) => {
+                let radiance = self.upsample(env.radiance(ray_.dir), ray_);
+                if bsdf_pdf > 0.0 {
+                    radiance * power_heuristic(bsdf_pdf, env.pdf(ray_.dir))
+                } else {
+                    radiance
+                }
+            }
+            None => self.upsample(self.background, ray_),
+        }
+    }
+
+    /// whether something blocks `ray_` before `dist`. Hits on cut out parts of alpha
+    /// masked surfaces let the ray go on as often as `scatter` would.
+    pub(crate) fn occluded<T: Hittable>(&self, world: &T, ray_: &Ray, dist: f64) -> bool {
+        let mut rec = HitRecord::new(Vec3::zero(), Vec3::zero(), 0.0, false, None);
+        let mut t_min = 0.001;
+        while world.hit(ray_, Interval::new(t_min, dist - 0.001), &mut rec) {
+            let coverage = match &rec.mat_ptr {
+                Some(mat) => mat.coverage(&rec),
+                None => 1.0,
+            };
+            if coverage >= 1.0 || random_f64(0.0, 1.0) < coverage {
+                return true;
+            }
+            t_min = rec.t + 0.001;
+        }
+        false
+    }
+
+    /// direct illumination from the analytic lights alone
+    pub(crate) fn sample_analytic_lights<T: Hittable>(
+        &self,
+        ray_: &Ray,
+        rec: &HitRecord,
+        world: &T,
+    ) -> Vec3 {
+        let mat = rec.mat_ptr.as_ref().unwrap();
+        let mut color = Vec3::zero();
+        for light in &self.lights {
+            let sample = match light.sample_li(rec.point) {
+                Some(sample) => sample,
+                None => continue,
+            };
+            let f = mat.eval(ray_, rec, sample.wi);
+            if f.near_zero() {
+                continue;
+            }
+            let shadow_ray = Ray::new(rec.point, sample.wi, ray_.time);
+            if self.occluded(world, &shadow_ray, sample.dist) {
+                continue;
+            }
+            color += self
+                .upsample(f, ray_)
+                .cor_dot(self.upsample(sample.radiance, ray_));
         }
-        color_from_emission
+        color
+    }
+
+    /// direct illumination from the analytic lights and the environment
+    pub fn sample_lights<T: Hittable>(&self, ray_: &Ray, rec: &HitRecord, world: &T) -> Vec3 {
+        let mat = rec.mat_ptr.as_ref().unwrap();
+        let mut color = self.sample_analytic_lights(ray_, rec, world);
+        if let Some(env) = &self.environment {
+            if let Some(sample) = env.sample() {
+                let f = mat.eval(ray_, rec, sample.wi);
+                let shadow_ray = Ray::new(rec.point, sample.wi, ray_.time);
+                if !f.near_zero() && !self.occluded(world, &shadow_ray, f64::INFINITY) {
+                    let bsdf_pdf = mat.scattering_pdf(ray_, rec, sample.wi);
+                    let weight = power_heuristic(sample.pdf, bsdf_pdf) / sample.pdf;
+                    color += weight
+                        * self
+                            .upsample(f, ray_)
+                            .cor_dot(self.upsample(sample.radiance, ray_));
+                }
+            }
+        }
+        color
     }
 
     pub fn render<T: Hittable + Sync>(&mut self, world: &T, img: &mut RgbImage) {
@@ -149,12 +467,47 @@ impl Camera {
             ProgressBar::new((self.height * self.width) as u64)
         };
         let pixel_sample_scale = 1.0 / self.samples_per_pixel as f64;
+        match self.integrator {
+            Integrator::PathTracer => {}
+            Integrator::Bdpt => {
+                self.render_bdpt(world, img, &bar);
+                return;
+            }
+            Integrator::Sppm {
+                photons_per_pass,
+                initial_radius,
+            } => {
+                Sppm::new(self, world, photons_per_pass, initial_radius).render(world, img, &bar);
+                return;
+            }
+            Integrator::Pssmlt {
+                chains,
+                bootstrap,
+                large_step_probability,
+                sigma,
+            } => {
+                Pssmlt::new(self, chains, bootstrap, large_step_probability, sigma)
+                    .render(world, img, &bar);
+                return;
+            }
+        }
 
         for i in 0..self.width {
             for j in 0..self.height {
                 let pixel_color = (0..self.samples_per_pixel)
                     .into_par_iter()
-                    .map(|_| self.ray_color(self.get_ray(i, j), world, self.max_depth))
+                    .map(|_| {
+                        let ray_ = match self.get_ray(i, j) {
+                            Some(ray_) => ray_,
+                            None => return Vec3::zero(),
+                        };
+                        let color = self.ray_color(ray_, world, self.max_depth);
+                        if ray_.is_spectral() {
+                            spectrum_to_rgb(color, ray_.lambda)
+                        } else {
+                            color
+                        }
+                    })
                     .sum::<Vec3>();
                 write_color(pixel_color * pixel_sample_scale, img, i, j);
                 bar.inc(1);
@@ -163,8 +516,181 @@ impl Camera {
         bar.finish();
     }
 
-    pub fn defocus_disk_sample(&self) -> Vec3 {
-        let p = Vec3::random_in_unit_disk();
-        self.center + p.x * self.defocus_disk_u + p.y * self.defocus_disk_v
+    /// light tracing splats onto any pixel, so the image is only written at the end
+    fn render_bdpt<T: Hittable + Sync>(&self, world: &T, img: &mut RgbImage, bar: &ProgressBar) {
+        let bdpt = Bdpt::new(self);
+        let mut film = vec![Vec3::zero(); self.width * self.height];
+        for i in 0..self.width {
+            for j in 0..self.height {
+                let (color, splats) = (0..self.samples_per_pixel)
+                    .into_par_iter()
+                    .map(|_| bdpt.sample(world, i, j))
+                    .reduce(
+                        || (Vec3::zero(), Vec::new()),
+                        |mut a, b| {
+                            a.0 += b.0;
+                            a.1.extend(b.1);
+                            a
+                        },
+                    );
+                film[j * self.width + i] += color;
+                for (x, y, splat) in splats {
+                    film[y * self.width + x] += splat;
+                }
+                bar.inc(1);
+            }
+        }
+        bar.finish();
+        let pixel_sample_scale = 1.0 / self.samples_per_pixel as f64;
+        for i in 0..self.width {
+            for j in 0..self.height {
+                write_color(film[j * self.width + i] * pixel_sample_scale, img, i, j);
+            }
+        }
+    }
+
+    /// point on the lens for a ray through the pixel position (x, y),
+    /// None when the lens barrel blocks it
+    pub fn defocus_disk_sample(&self, x: f64, y: f64) -> Option<Vec3> {
+        let p = self.aperture.sample();
+        if self.cats_eye > 0.0 {
+            // off-axis the pupil is the overlap with the barrel, shifted towards the edge
+            let half_w = 0.5 * self.width as f64;
+            let half_h = 0.5 * self.height as f64;
+            let q =
+                Vec3::new(x - half_w, half_h - y, 0.0) / (half_w * half_w + half_h * half_h).sqrt();
+            if (p - self.cats_eye * q).length() > 1.0 {
+                return None;
+            }
+        }
+        Some(self.center + p.x * self.defocus_disk_u + p.y * self.defocus_disk_v)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hit_list::HitList;
+    use crate::material::{AlphaMask, Lambertian, Material};
+    use crate::quad::Quad;
+    use crate::texture::Texture;
+
+    fn camera(projection: Projection, width: usize, aspect: f64) -> Camera {
+        let mut camera = Camera::init(width, aspect);
+        camera.lookfrom = Vec3::new(1.0, 2.0, 3.0);
+        camera.lookat = Vec3::new(1.0, 2.0, 2.0);
+        camera.vup = Vec3::new(0.0, 1.0, 0.0);
+        camera.projection = projection;
+        camera.update();
+        camera
+    }
+
+    fn assert_dir(ray_: Option<(Vec3, Vec3)>, expected: Vec3) {
+        let (_, dir) = ray_.unwrap();
+        assert!(
+            (dir.normalize() - expected.normalize()).length() < 1e-9,
+            "{:?} vs {:?}",
+            dir,
+            expected
+        );
+    }
+
+    /// solid color with a fixed coverage
+    struct Coverage(f64);
+
+    impl Texture for Coverage {
+        fn value(&self, _u: f64, _v: f64, _p: Vec3) -> Vec3 {
+            Vec3::ones()
+        }
+
+        fn alpha_at(&self, _rec: &HitRecord) -> f64 {
+            self.0
+        }
+    }
+
+    #[test]
+    fn test_shadow_rays_pass_cut_out_cards() {
+        let camera = camera(Projection::Perspective, 100, 1.0);
+        let card = |alpha: f64| {
+            let mat: Arc<dyn Material> = Arc::new(Lambertian::new(Vec3::ones()));
+            let masked = Arc::new(AlphaMask::new(mat, Arc::new(Coverage(alpha))));
+            Arc::new(Quad::new(
+                Vec3::new(-1.0, -1.0, 1.0),
+                Vec3::new(2.0, 0.0, 0.0),
+                Vec3::new(0.0, 2.0, 0.0),
+                masked,
+            ))
+        };
+        let shadow_ray = Ray::new(Vec3::zero(), Vec3::new(0.0, 0.0, 1.0), 0.0);
+        let mut world = HitList::new(Vec::new());
+        world.add(card(0.0));
+        assert!(!camera.occluded(&world, &shadow_ray, 5.0));
+        // a solid card behind the hole still blocks the light
+        let mut behind = HitList::new(Vec::new());
+        behind.add(card(0.0));
+        behind.add(Arc::new(Quad::new(
+            Vec3::new(-1.0, -1.0, 3.0),
+            Vec3::new(2.0, 0.0, 0.0),
+            Vec3::new(0.0, 2.0, 0.0),
+            Arc::new(Lambertian::new(Vec3::ones())),
+        )));
+        assert!(camera.occluded(&behind, &shadow_ray, 5.0));
+        assert!(!camera.occluded(&behind, &shadow_ray, 2.0));
+        world.add(card(1.0));
+        assert!(camera.occluded(&world, &shadow_ray, 5.0));
+        // half covered cards stop about half of the rays
+        let mut half = HitList::new(Vec::new());
+        half.add(card(0.5));
+        let blocked = (0..4000)
+            .filter(|_| camera.occluded(&half, &shadow_ray, 5.0))
+            .count();
+        assert!((blocked as f64 / 4000.0 - 0.5).abs() < 0.05);
+    }
+
+    #[test]
+    fn test_orthographic_rays() {
+        let camera = camera(Projection::Orthographic { height: 2.0 }, 200, 2.0);
+        let forward = Vec3::new(0.0, 0.0, -1.0);
+        let (orig, dir) = camera.panoramic_ray(0.5, 0.5).unwrap();
+        assert!((orig - camera.lookfrom).length() < 1e-9);
+        assert!((dir - forward).length() < 1e-9);
+        // edges shift the origin by half the view, the direction stays
+        let (orig, dir) = camera.panoramic_ray(1.0, 0.0).unwrap();
+        assert!((orig - Vec3::new(3.0, 3.0, 3.0)).length() < 1e-9);
+        assert!((dir - forward).length() < 1e-9);
+    }
+
+    #[test]
+    fn test_fisheye_rays() {
+        let camera = camera(Projection::Fisheye { fov: 180.0 }, 100, 1.0);
+        assert_dir(camera.panoramic_ray(0.5, 0.5), Vec3::new(0.0, 0.0, -1.0));
+        // the rim of the circle looks sideways at half the field of view
+        assert_dir(camera.panoramic_ray(1.0, 0.5), Vec3::new(1.0, 0.0, 0.0));
+        assert_dir(camera.panoramic_ray(0.5, 0.0), Vec3::new(0.0, 1.0, 0.0));
+        assert!(camera.panoramic_ray(1.0, 0.0).is_none());
+    }
+
+    #[test]
+    fn test_equirectangular_rays() {
+        let camera = camera(Projection::Equirectangular, 200, 2.0);
+        assert_dir(camera.panoramic_ray(0.5, 0.5), Vec3::new(0.0, 0.0, -1.0));
+        assert_dir(camera.panoramic_ray(0.75, 0.5), Vec3::new(1.0, 0.0, 0.0));
+        assert_dir(camera.panoramic_ray(1.0, 0.5), Vec3::new(0.0, 0.0, 1.0));
+        assert_dir(camera.panoramic_ray(0.5, 0.0), Vec3::new(0.0, 1.0, 0.0));
+        assert_dir(camera.panoramic_ray(0.5, 1.0), Vec3::new(0.0, -1.0, 0.0));
+    }
+
+    #[test]
+    fn test_cylindrical_rays() {
+        let camera = camera(Projection::Cylindrical { hfov: 90.0 }, 200, 2.0);
+        assert_dir(camera.panoramic_ray(0.5, 0.5), Vec3::new(0.0, 0.0, -1.0));
+        let side = 0.5_f64.sqrt();
+        assert_dir(camera.panoramic_ray(1.0, 0.5), Vec3::new(side, 0.0, -side));
+        // the top edge sits half the arc length across, divided by the aspect
+        let height = 0.25 * PI;
+        assert_dir(
+            camera.panoramic_ray(0.5, 0.0),
+            Vec3::new(0.0, 0.5 * height, -1.0),
+        );
     }
 }
@@ -8,6 +8,18 @@ pub fn linear_to_gamma(x: f64) -> f64 {
     }
     0.0
 }
+/// decodes an sRGB encoded channel in [0, 1]
+pub fn srgb_to_linear(x: f64) -> f64 {
+    if x <= 0.04045 {
+        x / 12.92
+    } else {
+        ((x + 0.055) / 1.055).powf(2.4)
+    }
+}
+pub fn luminance(c: Vec3) -> f64 {
+    0.2126 * c.x + 0.7152 * c.y + 0.0722 * c.z
+}
+
 /// the multi-sample write_color() function
 pub fn write_color(pixel_color: Vec3, img: &mut RgbImage, i: usize, j: usize) {
     let int = Interval::new(0.0, 0.999);
@@ -0,0 +1,233 @@
+use crate::aabb::AABB;
+use crate::hit::{HitRecord, Hittable, Span};
+use crate::interval::Interval;
+use crate::ray::Ray;
+use std::sync::Arc;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CsgOp {
+    Union,
+    Intersection,
+    /// the left shape with the right one carved out
+    Difference,
+}
+
+impl CsgOp {
+    fn inside(&self, left: bool, right: bool) -> bool {
+        match self {
+            CsgOp::Union => left || right,
+            CsgOp::Intersection => left && right,
+            CsgOp::Difference => left && !right,
+        }
+    }
+}
+
+/// boolean combination of two closed shapes. The surface at a hit belongs to one of
+/// the children and keeps its material and uv.
+pub struct Csg {
+    op: CsgOp,
+    left: Arc<dyn Hittable>,
+    right: Arc<dyn Hittable>,
+    bbox: AABB,
+}
+
+impl Csg {
+    pub fn new(op: CsgOp, left: Arc<dyn Hittable>, right: Arc<dyn Hittable>) -> Self {
+        let (a, b) = (left.bounding_box(), right.bounding_box());
+        let bbox = match op {
+            CsgOp::Union => AABB::new_by_aabb(&a, &b),
+            CsgOp::Intersection => AABB::new(
+                Interval::new(a.x.min.max(b.x.min), a.x.max.min(b.x.max)),
+                Interval::new(a.y.min.max(b.y.min), a.y.max.min(b.y.max)),
+                Interval::new(a.z.min.max(b.z.min), a.z.max.min(b.z.max)),
+            ),
+            CsgOp::Difference => a,
+        };
+        Self {
+            op,
+            left,
+            right,
+            bbox,
+        }
+    }
+}
+
+/// where the ray goes in or out of one of the children
+struct Crossing {
+    rec: HitRecord,
+    left: bool,
+    entering: bool,
+}
+
+/// `rec` as a surface of the result, `flip` turns the child's surface inside out
+fn oriented(mut rec: HitRecord, ray_: &Ray, flip: bool) -> HitRecord {
+    let out_normal = if rec.front_face != flip {
+        rec.normal
+    } else {
+        -rec.normal
+    };
+    rec.set_face_normal(ray_, out_normal);
+    rec
+}
+
+impl Hittable for Csg {
+    fn hit(&self, ray_: &Ray, int: Interval, rec: &mut HitRecord) -> bool {
+        for (entry, exit) in self.hit_intervals(ray_) {
+            for crossing in [entry, exit] {
+                if int.surrounds(crossing.t) {
+                    *rec = crossing;
+                    return true;
+                }
+            }
+        }
+        false
+    }
+
+    fn bounding_box(&self) -> AABB {
+        self.bbox
+    }
+
+    fn hit_intervals(&self, ray_: &Ray) -> Vec<Span> {
+        let mut crossings: Vec<Crossing> = Vec::new();
+        for (left, child) in [(true, &self.left), (false, &self.right)] {
+            for (entry, exit) in child.hit_intervals(ray_) {
+                crossings.push(Crossing {
+                    rec: entry,
+                    left,
+                    entering: true,
+                });
+                crossings.push(Crossing {
+                    rec: exit,
+                    left,
+                    entering: false,
+                });
+            }
+        }
+        crossings.sort_by(|a, b| a.rec.t.total_cmp(&b.rec.t));
+
+        // sweep along the ray, the result changes sides where op(left, right) does
+        let (mut in_left, mut in_right) = (false, false);
+        let mut entry: Option<HitRecord> = None;
+        let mut spans = Vec::new();
+        for crossing in crossings {
+            let was_inside = self.op.inside(in_left, in_right);
+            if crossing.left {
+                in_left = crossing.entering;
+            } else {
+                in_right = crossing.entering;
+            }
+            let inside = self.op.inside(in_left, in_right);
+            if inside == was_inside {
+                continue;
+            }
+            // the carved out shape bounds the result with its inside
+            let flip = !crossing.left && self.op == CsgOp::Difference;
+            let rec = oriented(crossing.rec, ray_, flip);
+            if inside {
+                entry = Some(rec);
+            } else if let Some(start) = entry.take() {
+                spans.push((start, rec));
+            }
+        }
+        spans
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::Lambertian;
+    use crate::quad::gen_box;
+    use crate::sphere::Sphere;
+    use crate::vec3::Vec3;
+
+    #[test]
+    fn test_sphere_minus_box() {
+        let mat = Arc::new(Lambertian::new(Vec3::ones()));
+        let sphere = Arc::new(Sphere::new(Vec3::zero(), 1.0, Some(mat.clone())));
+        // carves away everything with z > 0.5
+        let block = gen_box(Vec3::new(-2.0, -2.0, 0.5), Vec3::new(2.0, 2.0, 2.0), mat);
+        let csg = Csg::new(CsgOp::Difference, sphere, block);
+        let mut rec = HitRecord::new(Vec3::zero(), Vec3::zero(), 0.0, false, None);
+        let ray_ = Ray::new(Vec3::new(0.0, 0.0, 5.0), Vec3::new(0.0, 0.0, -1.0), 0.0);
+        assert!(csg.hit(&ray_, Interval::new(0.001, f64::INFINITY), &mut rec));
+        assert!((rec.t - 4.5).abs() < 1e-6);
+        assert!(rec.front_face);
+        assert!((rec.normal - Vec3::new(0.0, 0.0, 1.0)).length() < 1e-9);
+        // a ray that only passes through the carved away part misses
+        let ray_ = Ray::new(Vec3::new(5.0, 0.0, 0.8), Vec3::new(-1.0, 0.0, 0.0), 0.0);
+        assert!(csg.hit_intervals(&ray_).is_empty());
+    }
+
+    /// unit spheres at x = -0.5 and 0.5 with a small one nested in the first
+    fn spheres() -> [Arc<dyn Hittable>; 3] {
+        let mat = Arc::new(Lambertian::new(Vec3::ones()));
+        let sphere = |x: f64, radius: f64| -> Arc<dyn Hittable> {
+            Arc::new(Sphere::new(
+                Vec3::new(x, 0.0, 0.0),
+                radius,
+                Some(mat.clone()),
+            ))
+        };
+        [sphere(-0.5, 1.0), sphere(0.5, 1.0), sphere(-0.5, 0.25)]
+    }
+
+    /// where the spans of `csg` start and end along a ray in +x through y
+    fn spans_along_x(csg: &Csg, y: f64) -> Vec<(f64, f64)> {
+        let ray_ = Ray::new(Vec3::new(-5.0, y, 0.0), Vec3::new(1.0, 0.0, 0.0), 0.0);
+        csg.hit_intervals(&ray_)
+            .iter()
+            .map(|(entry, exit)| {
+                // normals face the ray, outwards at the entry and inwards at the exit
+                assert!(entry.front_face && !exit.front_face);
+                assert!(entry.normal.x < 0.0 && exit.normal.x < 0.0);
+                (entry.point.x, exit.point.x)
+            })
+            .collect()
+    }
+
+    fn assert_spans(spans: Vec<(f64, f64)>, expected: &[(f64, f64)]) {
+        assert_eq!(spans.len(), expected.len());
+        for ((a, b), (c, d)) in spans.iter().zip(expected) {
+            assert!((a - c).abs() < 1e-6 && (b - d).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_union_merges_spans() {
+        let [left, right, nested] = spheres();
+        let union = Csg::new(CsgOp::Union, left.clone(), right);
+        // overlapping spans merge into one from the far side of each sphere
+        assert_spans(spans_along_x(&union, 0.0), &[(-1.5, 1.5)]);
+        // off center the spheres no longer overlap
+        let half = (1.0 - 0.9_f64 * 0.9).sqrt();
+        assert_spans(
+            spans_along_x(&union, 0.9),
+            &[(-0.5 - half, -0.5 + half), (0.5 - half, 0.5 + half)],
+        );
+        // a nested sphere adds nothing
+        let union = Csg::new(CsgOp::Union, left, nested);
+        assert_spans(spans_along_x(&union, 0.0), &[(-1.5, 0.5)]);
+        let mut rec = HitRecord::new(Vec3::zero(), Vec3::zero(), 0.0, false, None);
+        let ray_ = Ray::new(Vec3::new(-0.5, 0.0, 0.0), Vec3::new(1.0, 0.0, 0.0), 0.0);
+        assert!(union.hit(&ray_, Interval::new(0.001, f64::INFINITY), &mut rec));
+        assert!((rec.t - 1.0).abs() < 1e-6 && !rec.front_face);
+    }
+
+    #[test]
+    fn test_intersection_keeps_overlap() {
+        let [left, right, nested] = spheres();
+        let lens = Csg::new(CsgOp::Intersection, left.clone(), right);
+        // enters through the right sphere and leaves through the left one
+        assert_spans(spans_along_x(&lens, 0.0), &[(-0.5, 0.5)]);
+        assert!(spans_along_x(&lens, 0.9).is_empty());
+        let mut rec = HitRecord::new(Vec3::zero(), Vec3::zero(), 0.0, false, None);
+        let ray_ = Ray::new(Vec3::new(-5.0, 0.0, 0.0), Vec3::new(1.0, 0.0, 0.0), 0.0);
+        assert!(lens.hit(&ray_, Interval::new(0.001, f64::INFINITY), &mut rec));
+        assert!((rec.t - 4.5).abs() < 1e-6 && rec.front_face);
+        assert!((rec.normal - Vec3::new(-1.0, 0.0, 0.0)).length() < 1e-9);
+        // a nested sphere is all that is left
+        let core = Csg::new(CsgOp::Intersection, left, nested);
+        assert_spans(spans_along_x(&core, 0.0), &[(-0.75, -0.25)]);
+    }
+}
@@ -0,0 +1,353 @@
+use crate::aabb::AABB;
+use crate::hit::{HitRecord, Hittable};
+use crate::interval::Interval;
+use crate::material::Material;
+use crate::onb::Onb;
+use crate::quad::Quad;
+use crate::ray::Ray;
+use crate::rt::random_f64;
+use crate::vec3::Vec3;
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+/// roots of a t^2 + 2 b t + c in increasing order
+fn solve_quadratic(a: f64, b: f64, c: f64) -> Option<(f64, f64)> {
+    if a.abs() < 1e-12 {
+        if b.abs() < 1e-12 {
+            return None;
+        }
+        let t = -c / (2.0 * b);
+        return Some((t, t));
+    }
+    let disc = b * b - a * c;
+    if disc < 0.0 {
+        return None;
+    }
+    let sqrtd = disc.sqrt();
+    let (t0, t1) = ((-b - sqrtd) / a, (-b + sqrtd) / a);
+    Some((t0.min(t1), t0.max(t1)))
+}
+
+/// angle around the local z axis as u in [0, 1)
+fn azimuth(p: Vec3) -> f64 {
+    let phi = p.y.atan2(p.x);
+    (if phi < 0.0 { phi + 2.0 * PI } else { phi }) / (2.0 * PI)
+}
+
+/// picks the closest of the caps that is hit before `closest`
+fn hit_caps(
+    caps: &[Quad],
+    ray: &Ray,
+    ray_t: Interval,
+    mut closest: Option<HitRecord>,
+) -> Option<HitRecord> {
+    for cap in caps {
+        let max = closest.as_ref().map_or(ray_t.max, |rec| rec.t);
+        let mut rec = HitRecord::new(Vec3::zero(), Vec3::zero(), 0.0, false, None);
+        if cap.hit(ray, Interval::new(ray_t.min, max), &mut rec) {
+            closest = Some(rec);
+        }
+    }
+    closest
+}
+
+/// chooses the lateral surface with the probability of its share of the area,
+/// or else one of the caps, returning None for the lateral surface
+fn sample_cap(caps: &[Quad], lateral_area: f64) -> Option<&Quad> {
+    let caps_area: f64 = caps.iter().map(|cap| cap.area()).sum();
+    let mut u = random_f64(0.0, lateral_area + caps_area) - lateral_area;
+    for cap in caps {
+        if u < cap.area() {
+            return Some(cap);
+        }
+        u -= cap.area();
+    }
+    None
+}
+
+/// finite cylinder around the segment from `base` to `top`, u goes around the axis and
+/// v along it. Capped cylinders are closed by two disks.
+pub struct Cylinder {
+    base: Vec3,
+    frame: Onb,
+    height: f64,
+    radius: f64,
+    caps: Vec<Quad>,
+    mat: Arc<dyn Material>,
+    bbox: AABB,
+}
+
+impl Cylinder {
+    pub fn new(base: Vec3, top: Vec3, radius: f64, capped: bool, mat: Arc<dyn Material>) -> Self {
+        let axis = top - base;
+        let frame = Onb::new(axis);
+        let bottom_disk = Quad::new_disk(base, -frame.w, radius, mat.clone());
+        let top_disk = Quad::new_disk(top, frame.w, radius, mat.clone());
+        // the lateral surface fills the box around its end disks
+        let bbox = AABB::new_by_aabb(&bottom_disk.bounding_box(), &top_disk.bounding_box());
+        let caps = if capped {
+            vec![bottom_disk, top_disk]
+        } else {
+            Vec::new()
+        };
+        Self {
+            base,
+            frame,
+            height: axis.length(),
+            radius,
+            caps,
+            mat,
+            bbox,
+        }
+    }
+
+    /// record of the lateral surface at the local point p
+    fn lateral_record(&self, p: Vec3, t: f64) -> HitRecord {
+        let out_normal = self.frame.local(Vec3::new(p.x, p.y, 0.0) / self.radius);
+        let mut rec = HitRecord::new(
+            self.base + self.frame.local(p),
+            out_normal,
+            t,
+            true,
+            Some(self.mat.clone()),
+        );
+        rec.u = azimuth(p);
+        rec.v = p.z / self.height;
+        rec.dpdu = 2.0 * PI * self.frame.local(Vec3::new(-p.y, p.x, 0.0));
+        rec.dpdv = self.height * self.frame.w;
+        rec
+    }
+}
+
+impl Hittable for Cylinder {
+    fn hit(&self, ray_: &Ray, ray_t: Interval, rec: &mut HitRecord) -> bool {
+        let o = self.frame.to_local(ray_.orig - self.base);
+        let d = self.frame.to_local(ray_.dir);
+        let mut closest = None;
+        if let Some((t0, t1)) = solve_quadratic(
+            d.x * d.x + d.y * d.y,
+            o.x * d.x + o.y * d.y,
+            o.x * o.x + o.y * o.y - self.radius * self.radius,
+        ) {
+            for t in [t0, t1] {
+                let z = o.z + t * d.z;
+                if ray_t.surrounds(t) && (0.0..=self.height).contains(&z) {
+                    let p = o + t * d;
+                    closest = Some(self.lateral_record(p, t));
+                    break;
+                }
+            }
+        }
+        match hit_caps(&self.caps, ray_, ray_t, closest) {
+            Some(hit) => {
+                let out_normal = if hit.front_face {
+                    hit.normal
+                } else {
+                    -hit.normal
+                };
+                *rec = hit;
+                rec.set_face_normal(ray_, out_normal);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn bounding_box(&self) -> AABB {
+        self.bbox
+    }
+
+    fn area(&self) -> f64 {
+        let caps: f64 = self.caps.iter().map(|cap| cap.area()).sum();
+        2.0 * PI * self.radius * self.height + caps
+    }
+
+    fn sample_surface(&self, time: f64) -> Option<HitRecord> {
+        if let Some(cap) = sample_cap(&self.caps, 2.0 * PI * self.radius * self.height) {
+            return cap.sample_surface(time);
+        }
+        let phi = random_f64(0.0, 2.0 * PI);
+        let p = Vec3::new(
+            self.radius * phi.cos(),
+            self.radius * phi.sin(),
+            random_f64(0.0, self.height),
+        );
+        Some(self.lateral_record(p, 0.0))
+    }
+}
+
+/// finite cone from a disk of `radius` around `base` to the `apex`, u goes around
+/// the axis and v along it. A capped cone is closed by the base disk.
+pub struct Cone {
+    base: Vec3,
+    frame: Onb,
+    height: f64,
+    radius: f64,
+    caps: Vec<Quad>,
+    mat: Arc<dyn Material>,
+    bbox: AABB,
+}
+
+impl Cone {
+    pub fn new(base: Vec3, apex: Vec3, radius: f64, capped: bool, mat: Arc<dyn Material>) -> Self {
+        let axis = apex - base;
+        let frame = Onb::new(axis);
+        let base_disk = Quad::new_disk(base, -frame.w, radius, mat.clone());
+        let bbox = AABB::new_by_aabb(&base_disk.bounding_box(), &AABB::new_by_point(apex, apex));
+        let caps = if capped { vec![base_disk] } else { Vec::new() };
+        Self {
+            base,
+            frame,
+            height: axis.length(),
+            radius,
+            caps,
+            mat,
+            bbox,
+        }
+    }
+
+    fn lateral_area(&self) -> f64 {
+        PI * self.radius * (self.radius * self.radius + self.height * self.height).sqrt()
+    }
+
+    /// record of the lateral surface at the local point p
+    fn lateral_record(&self, p: Vec3, t: f64) -> HitRecord {
+        // gradient of x^2 + y^2 - k^2 (h - z)^2 with the slope k = radius / height
+        let k = self.radius / self.height;
+        let gradient = Vec3::new(p.x, p.y, k * k * (self.height - p.z));
+        let out_normal = if gradient.near_zero() {
+            self.frame.w
+        } else {
+            self.frame.local(gradient.normalize())
+        };
+        let mut rec = HitRecord::new(
+            self.base + self.frame.local(p),
+            out_normal,
+            t,
+            true,
+            Some(self.mat.clone()),
+        );
+        rec.u = azimuth(p);
+        rec.v = p.z / self.height;
+        let phi = 2.0 * PI * rec.u;
+        rec.dpdu = 2.0 * PI * self.frame.local(Vec3::new(-p.y, p.x, 0.0));
+        rec.dpdv = self.frame.local(Vec3::new(
+            -self.radius * phi.cos(),
+            -self.radius * phi.sin(),
+            self.height,
+        ));
+        rec
+    }
+}
+
+impl Hittable for Cone {
+    fn hit(&self, ray_: &Ray, ray_t: Interval, rec: &mut HitRecord) -> bool {
+        let o = self.frame.to_local(ray_.orig - self.base);
+        let d = self.frame.to_local(ray_.dir);
+        let k2 = (self.radius / self.height).powi(2);
+        let s = self.height - o.z;
+        let mut closest = None;
+        if let Some((t0, t1)) = solve_quadratic(
+            d.x * d.x + d.y * d.y - k2 * d.z * d.z,
+            o.x * d.x + o.y * d.y + k2 * s * d.z,
+            o.x * o.x + o.y * o.y - k2 * s * s,
+        ) {
+            for t in [t0, t1] {
+                let z = o.z + t * d.z;
+                // the equation also holds on the mirrored cone beyond the apex
+                if ray_t.surrounds(t) && (0.0..=self.height).contains(&z) {
+                    closest = Some(self.lateral_record(o + t * d, t));
+                    break;
+                }
+            }
+        }
+        match hit_caps(&self.caps, ray_, ray_t, closest) {
+            Some(hit) => {
+                let out_normal = if hit.front_face {
+                    hit.normal
+                } else {
+                    -hit.normal
+                };
+                *rec = hit;
+                rec.set_face_normal(ray_, out_normal);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn bounding_box(&self) -> AABB {
+        self.bbox
+    }
+
+    fn area(&self) -> f64 {
+        let caps: f64 = self.caps.iter().map(|cap| cap.area()).sum();
+        self.lateral_area() + caps
+    }
+
+    fn sample_surface(&self, time: f64) -> Option<HitRecord> {
+        if let Some(cap) = sample_cap(&self.caps, self.lateral_area()) {
+            return cap.sample_surface(time);
+        }
+        // the circumference shrinks linearly towards the apex
+        let v = 1.0 - random_f64(0.0, 1.0).sqrt();
+        let phi = random_f64(0.0, 2.0 * PI);
+        let r = (1.0 - v) * self.radius;
+        let p = Vec3::new(r * phi.cos(), r * phi.sin(), v * self.height);
+        Some(self.lateral_record(p, 0.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::Lambertian;
+
+    #[test]
+    fn test_cylinder_hit_side_and_cap() {
+        let mat = Arc::new(Lambertian::new(Vec3::ones()));
+        let cylinder = Cylinder::new(Vec3::zero(), Vec3::new(0.0, 2.0, 0.0), 1.0, true, mat);
+        let mut rec = HitRecord::new(Vec3::zero(), Vec3::zero(), 0.0, false, None);
+        let side = Ray::new(Vec3::new(-5.0, 1.0, 0.0), Vec3::new(1.0, 0.0, 0.0), 0.0);
+        assert!(cylinder.hit(&side, Interval::new(0.001, f64::INFINITY), &mut rec));
+        assert!((rec.t - 4.0).abs() < 1e-9);
+        assert!((rec.normal - Vec3::new(-1.0, 0.0, 0.0)).length() < 1e-9);
+        assert!((rec.v - 0.5).abs() < 1e-9);
+        let top = Ray::new(Vec3::new(0.5, 5.0, 0.0), Vec3::new(0.0, -1.0, 0.0), 0.0);
+        assert!(cylinder.hit(&top, Interval::new(0.001, f64::INFINITY), &mut rec));
+        assert!((rec.t - 3.0).abs() < 1e-9);
+        assert!(rec.front_face);
+        assert!((rec.normal - Vec3::new(0.0, 1.0, 0.0)).length() < 1e-9);
+    }
+
+    #[test]
+    fn test_cone_hit_side_base_and_apex() {
+        let mat = Arc::new(Lambertian::new(Vec3::ones()));
+        let cone = Cone::new(Vec3::zero(), Vec3::new(0.0, 2.0, 0.0), 1.0, true, mat);
+        let all = Interval::new(0.001, f64::INFINITY);
+        let mut rec = HitRecord::new(Vec3::zero(), Vec3::zero(), 0.0, false, None);
+        // halfway up the radius is halved and the normal leans towards the apex
+        let side = Ray::new(Vec3::new(-5.0, 1.0, 0.0), Vec3::new(1.0, 0.0, 0.0), 0.0);
+        assert!(cone.hit(&side, all, &mut rec));
+        assert!((rec.t - 4.5).abs() < 1e-9);
+        assert!((rec.normal - Vec3::new(-2.0, 1.0, 0.0).normalize()).length() < 1e-9);
+        assert!((rec.v - 0.5).abs() < 1e-9);
+        let base = Ray::new(Vec3::new(0.5, -3.0, 0.0), Vec3::new(0.0, 1.0, 0.0), 0.0);
+        assert!(cone.hit(&base, all, &mut rec));
+        assert!((rec.t - 3.0).abs() < 1e-9);
+        assert!(rec.front_face);
+        assert!((rec.normal - Vec3::new(0.0, -1.0, 0.0)).length() < 1e-9);
+        // the mirrored cone beyond the apex is not part of the shape
+        let above = Ray::new(Vec3::new(-5.0, 3.0, 0.0), Vec3::new(1.0, 0.0, 0.0), 0.0);
+        assert!(!cone.hit(&above, all, &mut rec));
+
+        let lateral = PI * 5.0_f64.sqrt();
+        assert!((cone.area() - (lateral + PI)).abs() < 1e-9);
+        for _ in 0..50 {
+            let sample = cone.sample_surface(0.0).unwrap();
+            let ray_ = Ray::new(sample.point + sample.normal, -sample.normal, 0.0);
+            assert!(cone.hit(&ray_, all, &mut rec));
+            assert!((rec.point - sample.point).length() < 1e-6);
+        }
+    }
+}
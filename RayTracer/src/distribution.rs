@@ -0,0 +1,111 @@
+/// piecewise constant 1D distribution over [0, 1)
+#[derive(Clone, Debug)]
+pub struct Distribution1D {
+    pub func: Vec<f64>,
+    pub cdf: Vec<f64>,
+    pub func_int: f64,
+}
+
+impl Distribution1D {
+    pub fn new(func: Vec<f64>) -> Self {
+        let n = func.len();
+        let mut cdf = vec![0.0; n + 1];
+        for i in 1..=n {
+            cdf[i] = cdf[i - 1] + func[i - 1].abs() / n as f64;
+        }
+        let func_int = cdf[n];
+        if func_int == 0.0 {
+            for (i, c) in cdf.iter_mut().enumerate().skip(1) {
+                *c = i as f64 / n as f64;
+            }
+        } else {
+            for c in cdf.iter_mut().skip(1) {
+                *c /= func_int;
+            }
+        }
+        Self {
+            func,
+            cdf,
+            func_int,
+        }
+    }
+
+    pub fn count(&self) -> usize {
+        self.func.len()
+    }
+
+    /// returns the sampled value in [0, 1), its pdf and the index of the segment
+    pub fn sample_continuous(&self, u: f64) -> (f64, f64, usize) {
+        // last index whose cdf is <= u
+        let offset = match self.cdf.partition_point(|&c| c <= u) {
+            0 => 0,
+            i => (i - 1).min(self.count() - 1),
+        };
+        let mut du = u - self.cdf[offset];
+        let width = self.cdf[offset + 1] - self.cdf[offset];
+        if width > 0.0 {
+            du /= width;
+        }
+        let pdf = if self.func_int > 0.0 {
+            self.func[offset].abs() / self.func_int
+        } else {
+            1.0
+        };
+        ((offset as f64 + du) / self.count() as f64, pdf, offset)
+    }
+}
+
+/// piecewise constant 2D distribution over [0, 1)^2, `func` is stored row by row
+#[derive(Clone, Debug)]
+pub struct Distribution2D {
+    conditional: Vec<Distribution1D>,
+    marginal: Distribution1D,
+}
+
+impl Distribution2D {
+    pub fn new(func: &[f64], width: usize, height: usize) -> Self {
+        let conditional: Vec<Distribution1D> = (0..height)
+            .map(|v| Distribution1D::new(func[v * width..(v + 1) * width].to_vec()))
+            .collect();
+        let marginal = Distribution1D::new(conditional.iter().map(|d| d.func_int).collect());
+        Self {
+            conditional,
+            marginal,
+        }
+    }
+
+    /// returns (u, v) and the pdf with respect to the unit square
+    pub fn sample_continuous(&self, u0: f64, u1: f64) -> (f64, f64, f64) {
+        let (v, pdf_v, row) = self.marginal.sample_continuous(u1);
+        let (u, pdf_u, _) = self.conditional[row].sample_continuous(u0);
+        (u, v, pdf_u * pdf_v)
+    }
+
+    pub fn pdf(&self, u: f64, v: f64) -> f64 {
+        let row = ((v * self.marginal.count() as f64) as usize).min(self.marginal.count() - 1);
+        let col = ((u * self.conditional[row].count() as f64) as usize)
+            .min(self.conditional[row].count() - 1);
+        if self.marginal.func_int == 0.0 {
+            return 1.0;
+        }
+        self.conditional[row].func[col] / self.marginal.func_int
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_distribution2d_pdf_matches_sampling() {
+        let func = [1.0, 3.0, 0.0, 4.0];
+        let distribution = Distribution2D::new(&func, 2, 2);
+        for (u0, u1) in [(0.1, 0.2), (0.7, 0.3), (0.5, 0.9), (0.99, 0.99)] {
+            let (u, v, pdf) = distribution.sample_continuous(u0, u1);
+            assert!((distribution.pdf(u, v) - pdf).abs() < 1e-12);
+            assert!(pdf > 0.0);
+        }
+        // the empty cell is never sampled
+        assert_eq!(distribution.pdf(0.25, 0.75), 0.0);
+    }
+}
@@ -0,0 +1,217 @@
+use crate::color::luminance;
+use crate::distribution::Distribution2D;
+use crate::rt::random_f64;
+use crate::vec3::Vec3;
+use image::codecs::hdr::HdrDecoder;
+use image::ImageError;
+use std::f64::consts::PI;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+/// a direction sampled towards the environment
+pub struct EnvironmentSample {
+    /// unit direction pointing away from the scene
+    pub wi: Vec3,
+    pub radiance: Vec3,
+    /// density with respect to solid angle
+    pub pdf: f64,
+}
+
+/// light arriving from infinitely far away, seen by rays that leave the scene
+pub trait Environment: Send + Sync {
+    fn radiance(&self, dir: Vec3) -> Vec3;
+    fn sample(&self) -> Option<EnvironmentSample>;
+    fn pdf(&self, dir: Vec3) -> f64;
+}
+
+/// equirectangular (latitude-longitude) HDR environment map, +y is up
+pub struct EnvironmentLight {
+    width: usize,
+    height: usize,
+    pixels: Vec<Vec3>,
+    distribution: Distribution2D,
+    /// rotation around +y in degrees
+    pub rotation: f64,
+    pub intensity: f64,
+}
+
+impl EnvironmentLight {
+    /// loads a Radiance .hdr file at full range, other formats through `image::open`
+    pub fn new(filename: &str) -> Result<Self, ImageError> {
+        let path = Path::new(filename);
+        let is_hdr = path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("hdr"));
+        if is_hdr {
+            let decoder = HdrDecoder::new(BufReader::new(File::open(path)?))?;
+            let meta = decoder.metadata();
+            let pixels = decoder
+                .read_image_hdr()?
+                .iter()
+                .map(|p| Vec3::new(p[0] as f64, p[1] as f64, p[2] as f64))
+                .collect();
+            return Ok(Self::new_by_pixels(
+                pixels,
+                meta.width as usize,
+                meta.height as usize,
+            ));
+        }
+        let image = image::open(path)?.into_rgb32f();
+        let (width, height) = (image.width() as usize, image.height() as usize);
+        let pixels = image
+            .pixels()
+            .map(|p| Vec3::new(p[0] as f64, p[1] as f64, p[2] as f64))
+            .collect();
+        Ok(Self::new_by_pixels(pixels, width, height))
+    }
+
+    pub fn new_by_pixels(pixels: Vec<Vec3>, width: usize, height: usize) -> Self {
+        // weight by sin(theta) so that the poles are not oversampled
+        let mut func = Vec::with_capacity(width * height);
+        for j in 0..height {
+            let sin_theta = (PI * (j as f64 + 0.5) / height as f64).sin();
+            for i in 0..width {
+                func.push(luminance(pixels[j * width + i]).max(0.0) * sin_theta);
+            }
+        }
+        let distribution = Distribution2D::new(&func, width, height);
+        Self {
+            width,
+            height,
+            pixels,
+            distribution,
+            rotation: 0.0,
+            intensity: 1.0,
+        }
+    }
+
+    fn rotate(&self, dir: Vec3, angle: f64) -> Vec3 {
+        let (sin, cos) = angle.to_radians().sin_cos();
+        Vec3::new(cos * dir.x + sin * dir.z, dir.y, -sin * dir.x + cos * dir.z)
+    }
+
+    /// map coordinates of a world direction
+    fn dir_to_uv(&self, dir: Vec3) -> (f64, f64) {
+        let d = self.rotate(dir.normalize(), -self.rotation);
+        let u = 0.5 + d.x.atan2(-d.z) / (2.0 * PI);
+        let v = d.y.clamp(-1.0, 1.0).acos() / PI;
+        (u.rem_euclid(1.0), v)
+    }
+
+    fn uv_to_dir(&self, u: f64, v: f64) -> Vec3 {
+        let theta = v * PI;
+        let phi = (u - 0.5) * 2.0 * PI;
+        let d = Vec3::new(
+            theta.sin() * phi.sin(),
+            theta.cos(),
+            -theta.sin() * phi.cos(),
+        );
+        self.rotate(d, self.rotation)
+    }
+
+    fn lookup(&self, u: f64, v: f64) -> Vec3 {
+        let i = ((u * self.width as f64) as usize).min(self.width - 1);
+        let j = ((v * self.height as f64) as usize).min(self.height - 1);
+        self.pixels[j * self.width + i]
+    }
+}
+
+impl Environment for EnvironmentLight {
+    fn radiance(&self, dir: Vec3) -> Vec3 {
+        let (u, v) = self.dir_to_uv(dir);
+        self.intensity * self.lookup(u, v)
+    }
+
+    fn sample(&self) -> Option<EnvironmentSample> {
+        let (u, v, map_pdf) = self
+            .distribution
+            .sample_continuous(random_f64(0.0, 1.0), random_f64(0.0, 1.0));
+        if map_pdf == 0.0 {
+            return None;
+        }
+        let sin_theta = (v * PI).sin();
+        if sin_theta == 0.0 {
+            return None;
+        }
+        Some(EnvironmentSample {
+            wi: self.uv_to_dir(u, v),
+            radiance: self.intensity * self.lookup(u, v),
+            pdf: map_pdf / (2.0 * PI * PI * sin_theta),
+        })
+    }
+
+    fn pdf(&self, dir: Vec3) -> f64 {
+        let (u, v) = self.dir_to_uv(dir);
+        let sin_theta = (v * PI).sin();
+        if sin_theta == 0.0 {
+            return 0.0;
+        }
+        self.distribution.pdf(u, v) / (2.0 * PI * PI * sin_theta)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// a dim 8 by 4 map with one bright texel, turned by 30 degrees
+    fn bright_spot() -> EnvironmentLight {
+        let mut pixels = vec![Vec3::new(0.1, 0.1, 0.1); 32];
+        pixels[8 + 5] = Vec3::new(50.0, 40.0, 30.0);
+        let mut light = EnvironmentLight::new_by_pixels(pixels, 8, 4);
+        light.rotation = 30.0;
+        light
+    }
+
+    #[test]
+    fn test_uv_round_trip_with_rotation() {
+        let light = bright_spot();
+        for (u, v) in [(0.1, 0.3), (0.5, 0.5), (0.7, 0.2), (0.95, 0.8)] {
+            let dir = light.uv_to_dir(u, v);
+            assert!((dir.length() - 1.0).abs() < 1e-12);
+            let (u2, v2) = light.dir_to_uv(dir);
+            assert!((u2 - u).abs() < 1e-9 && (v2 - v).abs() < 1e-9);
+        }
+        // turning the map 90 degrees further moves the same direction a quarter along u,
+        // the poles stay put
+        let mut turned = bright_spot();
+        turned.rotation = 120.0;
+        let (u, v) = turned.dir_to_uv(light.uv_to_dir(0.1, 0.3));
+        assert!((u - 0.35).abs() < 1e-9);
+        assert!((v - 0.3).abs() < 1e-9);
+        assert!((light.uv_to_dir(0.3, 0.0) - Vec3::new(0.0, 1.0, 0.0)).length() < 1e-12);
+    }
+
+    #[test]
+    fn test_samples_match_pdf() {
+        let light = bright_spot();
+        let mut bright = 0;
+        for _ in 0..1000 {
+            let sample = light.sample().unwrap();
+            let pdf = light.pdf(sample.wi);
+            assert!(sample.pdf > 0.0 && (sample.pdf - pdf).abs() < 1e-9 * pdf);
+            assert!((sample.radiance - light.radiance(sample.wi)).length() < 1e-9);
+            bright += (sample.radiance.x > 1.0) as usize;
+        }
+        assert!(bright > 850);
+    }
+
+    #[test]
+    fn test_pdf_integrates_to_one() {
+        let light = bright_spot();
+        // midpoints of a grid over the map, each cell inside a single texel
+        let (nu, nv) = (256, 128);
+        let mut integral = 0.0;
+        for j in 0..nv {
+            let v = (j as f64 + 0.5) / nv as f64;
+            // solid angle of the cell
+            let area = 2.0 * PI * PI * (v * PI).sin() / (nu * nv) as f64;
+            for i in 0..nu {
+                let u = (i as f64 + 0.5) / nu as f64;
+                integral += light.pdf(light.uv_to_dir(u, v)) * area;
+            }
+        }
+        assert!((integral - 1.0).abs() < 1e-3, "{}", integral);
+    }
+}
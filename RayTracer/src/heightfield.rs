@@ -0,0 +1,273 @@
+use crate::aabb::AABB;
+use crate::hit::{HitRecord, Hittable};
+use crate::interval::Interval;
+use crate::material::Material;
+use crate::mesh::hit_triangle;
+use crate::perlin::Perlin;
+use crate::ray::Ray;
+use crate::vec3::Vec3;
+use image::ImageError;
+use std::path::Path;
+use std::sync::Arc;
+
+/// terrain over the rectangle from `corner` spanning `size.x` by `size.z`, rising by up
+/// to `size.y` above it. Only the samples are stored, the two triangles of each grid
+/// cell are made up while a ray walks the cells under it.
+pub struct Heightfield {
+    /// row by row along x, in [0, 1]
+    heights: Vec<f32>,
+    nx: usize,
+    nz: usize,
+    corner: Vec3,
+    size: Vec3,
+    mat: Arc<dyn Material>,
+    bbox: AABB,
+}
+
+impl Heightfield {
+    /// `heights` are nx by nz samples in [0, 1], row by row along x
+    pub fn new(
+        heights: Vec<f32>,
+        nx: usize,
+        nz: usize,
+        corner: Vec3,
+        size: Vec3,
+        mat: Arc<dyn Material>,
+    ) -> Self {
+        assert!(nx >= 2 && nz >= 2 && heights.len() == nx * nz);
+        let (lo, hi) = heights
+            .iter()
+            .fold((f32::INFINITY, f32::NEG_INFINITY), |(lo, hi), &h| {
+                (lo.min(h), hi.max(h))
+            });
+        let bbox = AABB::new_by_point(
+            corner + Vec3::new(0.0, lo as f64 * size.y, 0.0),
+            corner + Vec3::new(size.x, hi as f64 * size.y, size.z),
+        );
+        Self {
+            heights,
+            nx,
+            nz,
+            corner,
+            size,
+            mat,
+            bbox,
+        }
+    }
+
+    /// heights from the brightness of a grayscale image, white is the top
+    pub fn from_image(
+        filename: &str,
+        corner: Vec3,
+        size: Vec3,
+        mat: Arc<dyn Material>,
+    ) -> Result<Self, ImageError> {
+        let image = image::open(Path::new(filename))?.to_luma16();
+        let (nx, nz) = (image.width() as usize, image.height() as usize);
+        let heights = image.pixels().map(|p| p[0] as f32 / 65535.0).collect();
+        Ok(Heightfield::new(heights, nx, nz, corner, size, mat))
+    }
+
+    /// heights from `Perlin::turb` over the xz plane, `scale` noise cells per unit,
+    /// stretched to fill [0, 1]
+    #[allow(clippy::too_many_arguments)]
+    pub fn from_perlin(
+        noise: &Perlin,
+        nx: usize,
+        nz: usize,
+        scale: f64,
+        depth: i32,
+        corner: Vec3,
+        size: Vec3,
+        mat: Arc<dyn Material>,
+    ) -> Self {
+        let mut heights: Vec<f32> = (0..nx * nz)
+            .map(|k| {
+                let x = corner.x + size.x * (k % nx) as f64 / (nx - 1) as f64;
+                let z = corner.z + size.z * (k / nx) as f64 / (nz - 1) as f64;
+                noise.turb(scale * Vec3::new(x, 0.0, z), depth) as f32
+            })
+            .collect();
+        let (lo, hi) = heights
+            .iter()
+            .fold((f32::INFINITY, f32::NEG_INFINITY), |(lo, hi), &h| {
+                (lo.min(h), hi.max(h))
+            });
+        if hi > lo {
+            heights.iter_mut().for_each(|h| *h = (*h - lo) / (hi - lo));
+        }
+        Heightfield::new(heights, nx, nz, corner, size, mat)
+    }
+
+    fn cell_size(&self) -> (f64, f64) {
+        (
+            self.size.x / (self.nx - 1) as f64,
+            self.size.z / (self.nz - 1) as f64,
+        )
+    }
+
+    /// height of the sample at (i, j), clamped to the grid
+    fn height(&self, i: isize, j: isize) -> f64 {
+        let i = i.clamp(0, self.nx as isize - 1) as usize;
+        let j = j.clamp(0, self.nz as isize - 1) as usize;
+        self.corner.y + self.size.y * self.heights[j * self.nx + i] as f64
+    }
+
+    fn vertex(&self, i: isize, j: isize) -> Vec3 {
+        let (dx, dz) = self.cell_size();
+        Vec3::new(
+            self.corner.x + i as f64 * dx,
+            self.height(i, j),
+            self.corner.z + j as f64 * dz,
+        )
+    }
+
+    /// slopes dh/dx and dh/dz at a sample by central differences
+    fn slope(&self, i: isize, j: isize) -> (f64, f64) {
+        let (dx, dz) = self.cell_size();
+        let di = ((i + 1).min(self.nx as isize - 1) - (i - 1).max(0)) as f64;
+        let dj = ((j + 1).min(self.nz as isize - 1) - (j - 1).max(0)) as f64;
+        (
+            (self.height(i + 1, j) - self.height(i - 1, j)) / (di * dx),
+            (self.height(i, j + 1) - self.height(i, j - 1)) / (dj * dz),
+        )
+    }
+
+    /// lowest and highest point of the cell with (i, j) as its first corner
+    fn cell_bounds(&self, i: isize, j: isize) -> (f64, f64) {
+        let hs = [
+            self.height(i, j),
+            self.height(i + 1, j),
+            self.height(i, j + 1),
+            self.height(i + 1, j + 1),
+        ];
+        hs.iter()
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), &h| {
+                (lo.min(h), hi.max(h))
+            })
+    }
+
+    /// the closer of the two triangles of a cell
+    fn hit_cell(&self, ray_: &Ray, int: Interval, i: isize, j: isize, rec: &mut HitRecord) -> bool {
+        let corners = [(i, j), (i + 1, j), (i + 1, j + 1), (i, j + 1)];
+        let (mut closest, mut t) = (None, int.max);
+        for tri in [
+            [corners[0], corners[1], corners[2]],
+            [corners[0], corners[2], corners[3]],
+        ] {
+            let [a, b, c] = tri.map(|(i, j)| self.vertex(i, j));
+            if let Some((t_tri, b1, b2)) = hit_triangle(ray_, a, b, c) {
+                if int.min < t_tri && t_tri < t {
+                    t = t_tri;
+                    closest = Some((tri, b1, b2));
+                }
+            }
+        }
+        let (tri, b1, b2) = match closest {
+            Some(hit) => hit,
+            None => return false,
+        };
+        let weights = [1.0 - b1 - b2, b1, b2];
+        let [a, b, c] = tri.map(|(i, j)| self.vertex(i, j));
+        // the slopes interpolated over the triangle give the smooth normal
+        let (mut sx, mut sz) = (0.0, 0.0);
+        for (&(i, j), w) in tri.iter().zip(weights) {
+            let (dhdx, dhdz) = self.slope(i, j);
+            sx += w * dhdx;
+            sz += w * dhdz;
+        }
+        rec.t = t;
+        rec.point = ray_.at(t);
+        rec.u = ((rec.point.x - self.corner.x) / self.size.x).clamp(0.0, 1.0);
+        rec.v = ((rec.point.z - self.corner.z) / self.size.z).clamp(0.0, 1.0);
+        rec.dpdu = self.size.x * Vec3::new(1.0, sx, 0.0);
+        rec.dpdv = self.size.z * Vec3::new(0.0, sz, 1.0);
+        rec.mat_ptr = Some(self.mat.clone());
+        rec.set_face_normal(ray_, (c - a).cross(b - a).normalize());
+        let smooth = Vec3::new(-sx, 1.0, -sz).normalize();
+        rec.set_shading_normal(if rec.front_face { smooth } else { -smooth });
+        true
+    }
+}
+
+impl Hittable for Heightfield {
+    fn hit(&self, ray_: &Ray, int: Interval, rec: &mut HitRecord) -> bool {
+        let mut ray_t = int;
+        if !self.bbox.hit(ray_, &mut ray_t) {
+            return false;
+        }
+        // 2D DDA over the cells under the ray, front to back
+        let (dx, dz) = self.cell_size();
+        let (cells_x, cells_z) = (self.nx as isize - 1, self.nz as isize - 1);
+        let start = ray_.at(ray_t.min) - self.corner;
+        let mut i = ((start.x / dx).floor() as isize).clamp(0, cells_x - 1);
+        let mut j = ((start.z / dz).floor() as isize).clamp(0, cells_z - 1);
+        let axis = |origin: f64, dir: f64, cell: isize, size: f64| -> (isize, f64, f64) {
+            if dir > 0.0 {
+                (1, ((cell + 1) as f64 * size - origin) / dir, size / dir)
+            } else if dir < 0.0 {
+                (-1, (cell as f64 * size - origin) / dir, -size / dir)
+            } else {
+                (0, f64::INFINITY, f64::INFINITY)
+            }
+        };
+        let orig = ray_.orig - self.corner;
+        let (step_i, mut next_x, delta_x) = axis(orig.x, ray_.dir.x, i, dx);
+        let (step_j, mut next_z, delta_z) = axis(orig.z, ray_.dir.z, j, dz);
+        let mut t = ray_t.min;
+        loop {
+            let t_exit = next_x.min(next_z).min(ray_t.max);
+            // the ray can only meet the cell where it passes its height range
+            let (y0, y1) = (ray_.at(t).y, ray_.at(t_exit).y);
+            let (lo, hi) = self.cell_bounds(i, j);
+            if y0.min(y1) <= hi && y0.max(y1) >= lo && self.hit_cell(ray_, int, i, j, rec) {
+                return true;
+            }
+            if t_exit >= ray_t.max {
+                return false;
+            }
+            if next_x < next_z {
+                i += step_i;
+                t = next_x;
+                next_x += delta_x;
+            } else {
+                j += step_j;
+                t = next_z;
+                next_z += delta_z;
+            }
+            if i < 0 || i >= cells_x || j < 0 || j >= cells_z {
+                return false;
+            }
+        }
+    }
+
+    fn bounding_box(&self) -> AABB {
+        self.bbox
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::Lambertian;
+
+    #[test]
+    fn test_ramp_hit_and_normal() {
+        // height rises linearly along x from 0 to 1 over 4 units
+        let (nx, nz) = (9, 5);
+        let heights = (0..nx * nz)
+            .map(|k| (k % nx) as f32 / (nx - 1) as f32)
+            .collect();
+        let mat = Arc::new(Lambertian::new(Vec3::ones()));
+        let field = Heightfield::new(heights, nx, nz, Vec3::zero(), Vec3::new(4.0, 1.0, 4.0), mat);
+        let mut rec = HitRecord::new(Vec3::zero(), Vec3::zero(), 0.0, false, None);
+        let ray_ = Ray::new(Vec3::new(3.1, 5.0, 1.7), Vec3::new(0.0, -1.0, 0.0), 0.0);
+        assert!(field.hit(&ray_, Interval::new(0.001, f64::INFINITY), &mut rec));
+        assert!((rec.point.y - 3.1 / 4.0).abs() < 1e-9);
+        assert!((rec.normal - Vec3::new(-0.25, 1.0, 0.0).normalize()).length() < 1e-9);
+        // a ray skimming over the ramp from the low end finds the far high side
+        let ray_ = Ray::new(Vec3::new(-1.0, 0.9, 2.0), Vec3::new(1.0, 0.0, 0.0), 0.0);
+        assert!(field.hit(&ray_, Interval::new(0.001, f64::INFINITY), &mut rec));
+        assert!((rec.point.x - 3.6).abs() < 1e-9);
+    }
+}
@@ -3,6 +3,7 @@ use std::sync::Arc;
 use crate::aabb::AABB;
 use crate::interval::Interval;
 use crate::material::Material;
+use crate::onb::Onb;
 use crate::ray::Ray;
 use crate::vec3::Vec3;
 #[derive(Clone)]
@@ -14,6 +15,11 @@ pub struct HitRecord {
     pub mat_ptr: Option<Arc<dyn Material>>,
     pub u: f64,
     pub v: f64,
+    pub dpdu: Vec3,
+    pub dpdv: Vec3,
+    /// semi-axes in uv of the ellipse the ray cone covers around the hit, zero when
+    /// the ray carries no cone
+    pub footprint: [(f64, f64); 2],
 }
 
 impl HitRecord {
@@ -32,6 +38,9 @@ impl HitRecord {
             mat_ptr: mat,
             u: 0.0,
             v: 0.0,
+            dpdu: Vec3::zero(),
+            dpdv: Vec3::zero(),
+            footprint: [(0.0, 0.0); 2],
         }
     }
 
@@ -43,13 +52,114 @@ impl HitRecord {
             -out_normal
         };
     }
+
+    /// tangent frame around the (face-forward) normal, with u following dpdu.
+    /// The returned sign flips the bitangent so that it follows dpdv.
+    pub fn tangent_frame(&self) -> (Onb, f64) {
+        let onb = Onb::new_by_tangent(self.normal, self.dpdu);
+        let sign = if onb.v * self.dpdv < 0.0 { -1.0 } else { 1.0 };
+        (onb, sign)
+    }
+
+    /// footprint of the cone of `ray_` at this hit: a circle across the ray, stretched
+    /// along the surface by the slant of the ray and taken into uv through dpdu and dpdv
+    pub fn set_footprint(&mut self, ray_: &Ray) {
+        self.footprint = [(0.0, 0.0); 2];
+        let radius = 0.5 * ray_.cone_width_at(self.t);
+        if radius <= 0.0 || ray_.dir.near_zero() {
+            return;
+        }
+        let dir = ray_.dir.normalize();
+        let cos = dir * self.normal;
+        // the long axis follows the ray across the surface
+        let across = dir - cos * self.normal;
+        let a = if !across.near_zero() {
+            across.normalize()
+        } else {
+            Onb::new(self.normal).u
+        };
+        let b = self.normal.cross(a);
+        let (uu, uv, vv) = (
+            self.dpdu * self.dpdu,
+            self.dpdu * self.dpdv,
+            self.dpdv * self.dpdv,
+        );
+        let det = uu * vv - uv * uv;
+        if det <= 1e-12 * uu * vv || det == 0.0 {
+            return;
+        }
+        // least squares fit of w by du dpdu + dv dpdv
+        let to_uv = |w: Vec3| {
+            let (pu, pv) = (self.dpdu * w, self.dpdv * w);
+            ((vv * pu - uv * pv) / det, (uu * pv - uv * pu) / det)
+        };
+        self.footprint = [to_uv(radius / cos.abs().max(1e-3) * a), to_uv(radius * b)];
+    }
+
+    /// replaces the shading normal, ignoring normals that point below the geometric surface
+    pub fn set_shading_normal(&mut self, n: Vec3) {
+        if n * self.normal > 0.0 {
+            self.normal = n.normalize();
+        }
+    }
 }
 
-pub trait Hittable: Sync {
+pub trait Hittable: Send + Sync {
     fn hit(&self, ray_: &Ray, int: Interval, rec: &mut HitRecord) -> bool;
     fn bounding_box(&self) -> AABB;
+
+    /// box of the parts that have a finite one, unbounded objects like planes are left out
+    fn finite_bounding_box(&self) -> AABB {
+        let bbox = self.bounding_box();
+        if bbox.is_unbounded() {
+            AABB::new(Interval::empty(), Interval::empty(), Interval::empty())
+        } else {
+            bbox
+        }
+    }
+
+    /// surface area, for picking points on emitters
+    fn area(&self) -> f64 {
+        0.0
+    }
+
+    /// uniformly distributed point on the surface at `time` with the outward normal,
+    /// None for shapes that cannot be sampled
+    fn sample_surface(&self, _time: f64) -> Option<HitRecord> {
+        None
+    }
+
+    /// the spans of the whole line through the ray that lie inside of the shape,
+    /// as entry and exit records in increasing t. Only closed shapes have an inside,
+    /// the default walks the surface crossings found by `hit`.
+    fn hit_intervals(&self, ray_: &Ray) -> Vec<Span> {
+        let mut spans = Vec::new();
+        let mut entry: Option<HitRecord> = None;
+        let mut rec = HitRecord::new(Vec3::zero(), Vec3::zero(), 0.0, false, None);
+        let mut ray_t = Interval::universe();
+        for _ in 0..MAX_CROSSINGS {
+            if !self.hit(ray_, ray_t, &mut rec) {
+                break;
+            }
+            ray_t.min = rec.t + 1e-7 * (1.0 + rec.t.abs());
+            // front faces are where the ray goes in
+            match (rec.front_face, entry.take()) {
+                (true, None) => entry = Some(rec.clone()),
+                (false, Some(start)) => spans.push((start, rec.clone())),
+                // a crossing that does not change sides, from touching an edge twice
+                (_, start) => entry = start,
+            }
+        }
+        spans
+    }
 }
 
+/// entry and exit of a ray through a solid
+pub type Span = (HitRecord, HitRecord);
+
+/// surface crossings `hit_intervals` looks for along one ray
+const MAX_CROSSINGS: usize = 64;
+
 pub struct Translate {
     object: Arc<dyn Hittable>,
     offset: Vec3,
@@ -58,10 +168,33 @@ pub struct Translate {
 
 impl Translate {
     pub fn new(object: Arc<dyn Hittable>, offset: Vec3) -> Self {
+        let bbox = object.bounding_box() + offset;
         Self {
             object,
             offset,
-            bbox: object.bounding_box() + offset,
+            bbox,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tangent_frame() {
+        let mut rec = HitRecord::new(Vec3::zero(), Vec3::new(0.0, 0.0, 1.0), 1.0, true, None);
+        // a tangent that leans off the surface is projected back onto it
+        rec.dpdu = Vec3::new(2.0, 0.0, 0.5);
+        rec.dpdv = Vec3::new(0.0, 3.0, 0.0);
+        let (frame, sign) = rec.tangent_frame();
+        assert!((frame.u - Vec3::new(1.0, 0.0, 0.0)).length() < 1e-9);
+        assert!((frame.v - Vec3::new(0.0, 1.0, 0.0)).length() < 1e-9);
+        assert!((frame.w - rec.normal).length() < 1e-9);
+        assert_eq!(sign, 1.0);
+
+        // mirrored uvs flip the bitangent
+        rec.dpdv = Vec3::new(0.0, -3.0, 0.0);
+        assert_eq!(rec.tangent_frame().1, -1.0);
+    }
+}
@@ -13,10 +13,11 @@ pub struct HitList {
 
 impl HitList {
     pub fn new(list: Vec<Arc<dyn Hittable>>) -> Self {
-        Self {
-            list,
-            bbox: AABB::default(),
-        }
+        let empty = AABB::new(Interval::empty(), Interval::empty(), Interval::empty());
+        let bbox = list.iter().fold(empty, |bbox, item| {
+            AABB::new_by_aabb(&bbox, &item.bounding_box())
+        });
+        Self { list, bbox }
     }
     pub fn add(&mut self, item: Arc<dyn Hittable>) {
         let item_ref = Arc::clone(&item);
@@ -44,15 +45,7 @@ impl Hittable for HitList {
             if item.hit(ray_, Interval::new(int.min, closest_so_far), &mut temp_rec) {
                 hit_anything = true;
                 closest_so_far = temp_rec.clone().t;
-                *rec = HitRecord {
-                    point: temp_rec.point,
-                    normal: temp_rec.normal,
-                    t: temp_rec.t,
-                    front_face: temp_rec.front_face,
-                    mat_ptr: temp_rec.mat_ptr.clone(),
-                    u: temp_rec.u,
-                    v: temp_rec.v,
-                };
+                *rec = temp_rec.clone();
             }
         }
         hit_anything
@@ -61,6 +54,13 @@ impl Hittable for HitList {
     fn bounding_box(&self) -> AABB {
         self.bbox.clone()
     }
+
+    fn finite_bounding_box(&self) -> AABB {
+        let empty = AABB::new(Interval::empty(), Interval::empty(), Interval::empty());
+        self.list.iter().fold(empty, |bbox, item| {
+            AABB::new_by_aabb(&bbox, &item.finite_bounding_box())
+        })
+    }
 }
 
 unsafe impl Sync for HitList {}
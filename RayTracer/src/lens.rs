@@ -0,0 +1,149 @@
+use crate::distribution::Distribution2D;
+use crate::rt::random_f64;
+use crate::vec3::Vec3;
+use image::ImageError;
+use std::f64::consts::PI;
+use std::path::Path;
+use std::sync::Arc;
+
+/// camera described the way photographers do, lengths on the sensor side in mm
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ThinLens {
+    pub focal_length: f64,
+    pub f_number: f64,
+    /// width of the sensor, 36 for full frame
+    pub sensor_width: f64,
+    /// distance to the plane in focus, in world units
+    pub focus_dist: f64,
+    /// world units per meter
+    pub world_scale: f64,
+}
+
+impl ThinLens {
+    pub fn new(focal_length: f64, f_number: f64, sensor_width: f64, focus_dist: f64) -> Self {
+        Self {
+            focal_length,
+            f_number,
+            sensor_width,
+            focus_dist,
+            world_scale: 1.0,
+        }
+    }
+
+    /// vertical field of view in degrees, the image spans the sensor width
+    pub fn vfov(&self, width_height_ratio: f64) -> f64 {
+        let sensor_height = self.sensor_width / width_height_ratio;
+        2.0 * (sensor_height / (2.0 * self.focal_length))
+            .atan()
+            .to_degrees()
+    }
+
+    /// radius of the entrance pupil in world units
+    pub fn aperture_radius(&self) -> f64 {
+        0.5 * self.focal_length / self.f_number / 1000.0 * self.world_scale
+    }
+
+    /// the `defocus_angle` of `Camera` that gives the same aperture
+    pub fn defocus_angle(&self) -> f64 {
+        2.0 * (self.aperture_radius() / self.focus_dist)
+            .atan()
+            .to_degrees()
+    }
+}
+
+/// grayscale transmission mask of the aperture, for custom bokeh shapes
+pub struct ApertureImage {
+    width: usize,
+    height: usize,
+    distribution: Distribution2D,
+}
+
+impl ApertureImage {
+    pub fn new(filename: &str) -> Result<Self, ImageError> {
+        let image = image::open(Path::new(filename))?.to_luma32f();
+        let (width, height) = (image.width() as usize, image.height() as usize);
+        let values = image.pixels().map(|p| p[0] as f64).collect();
+        Ok(Self::new_by_values(values, width, height))
+    }
+
+    /// `values` row by row from the top, zero is opaque
+    pub fn new_by_values(values: Vec<f64>, width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            distribution: Distribution2D::new(&values, width, height),
+        }
+    }
+
+    /// point in [-1, 1]^2, distributed like the transmission
+    fn sample(&self) -> Vec3 {
+        let (u, v, _) = self
+            .distribution
+            .sample_continuous(random_f64(0.0, 1.0), random_f64(0.0, 1.0));
+        // keep the mask's aspect ratio inside the unit square
+        let size = self.width.max(self.height) as f64;
+        let (sx, sy) = (self.width as f64 / size, self.height as f64 / size);
+        Vec3::new((2.0 * u - 1.0) * sx, (1.0 - 2.0 * v) * sy, 0.0)
+    }
+}
+
+/// shape of the lens opening, which is the shape of out of focus highlights
+#[derive(Clone)]
+pub enum Aperture {
+    Circle,
+    /// regular polygon of `blades` straight blades, `rotation` in degrees
+    Polygon {
+        blades: usize,
+        rotation: f64,
+    },
+    Image(Arc<ApertureImage>),
+}
+
+impl Aperture {
+    /// point on the aperture in [-1, 1]^2, inside the unit disk for the polygons
+    pub fn sample(&self) -> Vec3 {
+        match self {
+            Aperture::Circle => Vec3::random_in_unit_disk(),
+            Aperture::Polygon { blades, rotation } => {
+                let blades = (*blades).max(3);
+                // uniform in one of the triangles between the center and two corners
+                let k = ((random_f64(0.0, 1.0) * blades as f64) as usize).min(blades - 1);
+                let corner = |i: usize| {
+                    let angle = rotation.to_radians() + 2.0 * PI * i as f64 / blades as f64;
+                    Vec3::new(angle.cos(), angle.sin(), 0.0)
+                };
+                let (mut a, mut b) = (random_f64(0.0, 1.0), random_f64(0.0, 1.0));
+                if a + b > 1.0 {
+                    a = 1.0 - a;
+                    b = 1.0 - b;
+                }
+                a * corner(k) + b * corner(k + 1)
+            }
+            Aperture::Image(image) => image.sample(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_thin_lens_full_frame() {
+        // a 50mm lens on a 36x24 sensor sees about 27 degrees vertically
+        let lens = ThinLens::new(50.0, 2.0, 36.0, 5.0);
+        assert!((lens.vfov(1.5) - 26.99).abs() < 0.01);
+        assert!((lens.aperture_radius() - 0.0125).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_polygon_aperture_inside_unit_disk() {
+        let aperture = Aperture::Polygon {
+            blades: 5,
+            rotation: 10.0,
+        };
+        for _ in 0..1000 {
+            assert!(aperture.sample().length() <= 1.0 + 1e-9);
+        }
+    }
+}
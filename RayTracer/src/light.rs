@@ -0,0 +1,349 @@
+use crate::distribution::Distribution1D;
+use crate::hit::{HitRecord, Hittable};
+use crate::interval::Interval;
+use crate::onb::Onb;
+use crate::ray::Ray;
+use crate::rt::random_f64;
+use crate::vec3::Vec3;
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+/// incident illumination from one light towards a shading point
+pub struct LightSample {
+    /// unit direction from the shading point towards the light
+    pub wi: Vec3,
+    /// distance to the light, infinite for distant lights
+    pub dist: f64,
+    /// radiance arriving along `wi`, already divided by the sampling pdf
+    pub radiance: Vec3,
+}
+
+/// lights that are not part of the geometry and are reached through shadow rays
+pub trait Light: Send + Sync {
+    fn sample_li(&self, p: Vec3) -> Option<LightSample>;
+
+    /// ray leaving the light with its power divided by the sampling pdf, for photon
+    /// mapping. Distant lights shoot into the sphere at `center` of radius `radius`.
+    fn sample_photon(&self, center: Vec3, radius: f64) -> Option<(Ray, Vec3)>;
+}
+
+/// photon of a distant source arriving along `dir`, through a disk that covers the scene
+pub fn distant_photon(dir: Vec3, center: Vec3, radius: f64) -> Ray {
+    let onb = Onb::new(dir);
+    let disk = Vec3::random_in_unit_disk();
+    let orig = center - radius * dir + radius * (disk.x * onb.u + disk.y * onb.v);
+    Ray::new(orig, dir, 0.0)
+}
+
+/// the emissive primitives of a scene, for integrators that start paths on them.
+/// Points are spread uniformly over their total area.
+pub struct Emitters<'a> {
+    list: &'a [Arc<dyn Hittable>],
+    distribution: Distribution1D,
+    total_area: f64,
+}
+
+impl<'a> Emitters<'a> {
+    pub fn new(list: &'a [Arc<dyn Hittable>]) -> Self {
+        let areas: Vec<f64> = list.iter().map(|e| e.area()).collect();
+        let total_area = areas.iter().sum();
+        Self {
+            list,
+            distribution: Distribution1D::new(areas),
+            total_area,
+        }
+    }
+
+    /// a point on one of the emitters with the outward normal
+    pub fn sample(&self, time: f64) -> Option<HitRecord> {
+        if self.total_area <= 0.0 {
+            return None;
+        }
+        let (_, _, index) = self.distribution.sample_continuous(random_f64(0.0, 1.0));
+        self.list[index].sample_surface(time)
+    }
+
+    /// density per area of `sample`, zero without emitters
+    pub fn pdf(&self) -> f64 {
+        if self.total_area <= 0.0 {
+            return 0.0;
+        }
+        1.0 / self.total_area
+    }
+
+    /// whether `rec` lies on one of the emitters, found with a short ray through the
+    /// surface along its normal
+    pub fn contains(&self, rec: &HitRecord, time: f64) -> bool {
+        let eps = 1e-4 * (1.0 + rec.point.length());
+        let ray_ = Ray::new(rec.point + eps * rec.normal, -rec.normal, time);
+        let mut probe = HitRecord::new(Vec3::zero(), Vec3::zero(), 0.0, false, None);
+        self.list
+            .iter()
+            .any(|e| e.hit(&ray_, Interval::new(0.0, 2.0 * eps), &mut probe))
+    }
+}
+
+pub struct PointLight {
+    pub position: Vec3,
+    pub intensity: Vec3,
+}
+
+impl PointLight {
+    pub fn new(position: Vec3, intensity: Vec3) -> Self {
+        Self {
+            position,
+            intensity,
+        }
+    }
+
+    /// radiant power spread evenly over the sphere of directions
+    pub fn new_by_power(position: Vec3, power: Vec3) -> Self {
+        Self::new(position, power / (4.0 * PI))
+    }
+}
+
+impl Light for PointLight {
+    fn sample_li(&self, p: Vec3) -> Option<LightSample> {
+        let to_light = self.position - p;
+        let dist2 = to_light.squared_length();
+        if dist2 == 0.0 {
+            return None;
+        }
+        let dist = dist2.sqrt();
+        Some(LightSample {
+            wi: to_light / dist,
+            dist,
+            radiance: self.intensity / dist2,
+        })
+    }
+
+    fn sample_photon(&self, _center: Vec3, _radius: f64) -> Option<(Ray, Vec3)> {
+        let ray_ = Ray::new(self.position, Vec3::random_unit_vector(), 0.0);
+        Some((ray_, 4.0 * PI * self.intensity))
+    }
+}
+
+pub struct SpotLight {
+    pub position: Vec3,
+    pub direction: Vec3,
+    pub intensity: Vec3,
+    pub cos_falloff_start: f64,
+    pub cos_total_width: f64,
+}
+
+impl SpotLight {
+    /// angles in degrees: full intensity inside `falloff_start`, none outside `total_width`
+    pub fn new(
+        position: Vec3,
+        lookat: Vec3,
+        intensity: Vec3,
+        falloff_start: f64,
+        total_width: f64,
+    ) -> Self {
+        Self {
+            position,
+            direction: (lookat - position).normalize(),
+            intensity,
+            cos_falloff_start: falloff_start.to_radians().cos(),
+            cos_total_width: total_width.to_radians().cos(),
+        }
+    }
+
+    pub fn new_by_power(
+        position: Vec3,
+        lookat: Vec3,
+        power: Vec3,
+        falloff_start: f64,
+        total_width: f64,
+    ) -> Self {
+        let mut light = Self::new(position, lookat, Vec3::zero(), falloff_start, total_width);
+        let cone = 2.0 * PI * (1.0 - 0.5 * (light.cos_falloff_start + light.cos_total_width));
+        light.intensity = power / cone;
+        light
+    }
+
+    fn falloff(&self, cos_theta: f64) -> f64 {
+        if cos_theta >= self.cos_falloff_start {
+            return 1.0;
+        }
+        if cos_theta <= self.cos_total_width {
+            return 0.0;
+        }
+        let t =
+            (cos_theta - self.cos_total_width) / (self.cos_falloff_start - self.cos_total_width);
+        t * t * (3.0 - 2.0 * t)
+    }
+}
+
+impl Light for SpotLight {
+    fn sample_li(&self, p: Vec3) -> Option<LightSample> {
+        let to_light = self.position - p;
+        let dist2 = to_light.squared_length();
+        if dist2 == 0.0 {
+            return None;
+        }
+        let dist = dist2.sqrt();
+        let wi = to_light / dist;
+        let falloff = self.falloff(-wi * self.direction);
+        if falloff == 0.0 {
+            return None;
+        }
+        Some(LightSample {
+            wi,
+            dist,
+            radiance: falloff * self.intensity / dist2,
+        })
+    }
+
+    fn sample_photon(&self, _center: Vec3, _radius: f64) -> Option<(Ray, Vec3)> {
+        // uniform in the cone of the total width
+        let cos_theta = 1.0 - random_f64(0.0, 1.0) * (1.0 - self.cos_total_width);
+        let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+        let phi = 2.0 * PI * random_f64(0.0, 1.0);
+        let dir = Onb::new(self.direction).local(Vec3::new(
+            sin_theta * phi.cos(),
+            sin_theta * phi.sin(),
+            cos_theta,
+        ));
+        let cone = 2.0 * PI * (1.0 - self.cos_total_width);
+        let power = self.falloff(cos_theta) * cone * self.intensity;
+        Some((Ray::new(self.position, dir, 0.0), power))
+    }
+}
+
+/// distant light such as the sun. A positive angular radius turns it into a
+/// small disk of directions, which softens the shadows.
+pub struct DirectionalLight {
+    /// direction the light travels in
+    pub direction: Vec3,
+    /// irradiance on a surface facing the light
+    pub irradiance: Vec3,
+    pub cos_angular_radius: f64,
+}
+
+impl DirectionalLight {
+    pub fn new(direction: Vec3, irradiance: Vec3) -> Self {
+        Self {
+            direction: direction.normalize(),
+            irradiance,
+            cos_angular_radius: 1.0,
+        }
+    }
+
+    /// `angular_radius` in degrees, the sun is about 0.27
+    pub fn new_with_radius(direction: Vec3, irradiance: Vec3, angular_radius: f64) -> Self {
+        Self {
+            direction: direction.normalize(),
+            irradiance,
+            cos_angular_radius: angular_radius.to_radians().cos(),
+        }
+    }
+}
+
+impl Light for DirectionalLight {
+    fn sample_li(&self, _p: Vec3) -> Option<LightSample> {
+        let to_light = -self.direction;
+        let wi = if self.cos_angular_radius < 1.0 {
+            // uniform in the cone, radiance / pdf equals the irradiance
+            let cos_theta = 1.0 - random_f64(0.0, 1.0) * (1.0 - self.cos_angular_radius);
+            let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+            let phi = 2.0 * PI * random_f64(0.0, 1.0);
+            let onb = Onb::new(to_light);
+            onb.local(Vec3::new(
+                sin_theta * phi.cos(),
+                sin_theta * phi.sin(),
+                cos_theta,
+            ))
+        } else {
+            to_light
+        };
+        Some(LightSample {
+            wi,
+            dist: f64::INFINITY,
+            radiance: self.irradiance,
+        })
+    }
+
+    fn sample_photon(&self, center: Vec3, radius: f64) -> Option<(Ray, Vec3)> {
+        let sample = self.sample_li(center)?;
+        let ray_ = distant_photon(-sample.wi, center, radius);
+        Some((ray_, PI * radius * radius * self.irradiance))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_emitters_contain_only_listed_primitives() {
+        use crate::material::DiffuseLight;
+        use crate::sphere::Sphere;
+        let glow = Arc::new(DiffuseLight::new_by_color(Vec3::ones()));
+        let listed: Arc<dyn Hittable> =
+            Arc::new(Sphere::new(Vec3::zero(), 1.0, Some(glow.clone())));
+        let unlisted = Sphere::new(Vec3::new(5.0, 0.0, 0.0), 1.0, Some(glow));
+        let list = vec![listed.clone()];
+        let emitters = Emitters::new(&list);
+        for _ in 0..10 {
+            let on_listed = listed.sample_surface(0.0).unwrap();
+            assert!(emitters.contains(&on_listed, 0.0));
+            let on_unlisted = unlisted.sample_surface(0.0).unwrap();
+            assert!(!emitters.contains(&on_unlisted, 0.0));
+        }
+    }
+
+    #[test]
+    fn test_point_light_falloff_and_power() {
+        let light =
+            PointLight::new_by_power(Vec3::new(0.0, 4.0, 0.0), Vec3::new(4.0 * PI, 0.0, 0.0));
+        assert!((light.intensity.x - 1.0).abs() < 1e-12);
+        let near = light.sample_li(Vec3::new(0.0, 2.0, 0.0)).unwrap();
+        let far = light.sample_li(Vec3::zero()).unwrap();
+        assert!((near.wi - Vec3::new(0.0, 1.0, 0.0)).length() < 1e-12);
+        assert_eq!((near.dist, far.dist), (2.0, 4.0));
+        assert!((near.radiance.x - 0.25).abs() < 1e-12);
+        assert!((near.radiance.x / far.radiance.x - 4.0).abs() < 1e-12);
+        // photons carry the full power
+        let (_, power) = light.sample_photon(Vec3::zero(), 1.0).unwrap();
+        assert!((power.x - 4.0 * PI).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_spot_light_cone() {
+        let position = Vec3::new(0.0, 1.0, 0.0);
+        let light = SpotLight::new(position, Vec3::zero(), Vec3::ones(), 10.0, 30.0);
+        let at_angle = |degrees: f64| {
+            let r = degrees.to_radians();
+            position + Vec3::new(r.sin(), -r.cos(), 0.0)
+        };
+        assert!((light.sample_li(at_angle(5.0)).unwrap().radiance.x - 1.0).abs() < 1e-12);
+        assert!(light.sample_li(at_angle(35.0)).is_none());
+        let edge = light.sample_li(at_angle(20.0)).unwrap().radiance.x;
+        assert!(edge > 0.0 && edge < 1.0);
+        // smoothstep halfway between the two cosines
+        let cos_mid = 0.5 * (light.cos_falloff_start + light.cos_total_width);
+        assert!((light.falloff(cos_mid) - 0.5).abs() < 1e-12);
+        assert!(light.sample_li(at_angle(15.0)).unwrap().radiance.x >= edge);
+
+        let power = Vec3::new(10.0, 20.0, 30.0);
+        let light = SpotLight::new_by_power(position, Vec3::zero(), power, 10.0, 30.0);
+        let cone = 2.0 * PI * (1.0 - cos_mid);
+        assert!((light.intensity * cone - power).length() < 1e-9);
+    }
+
+    #[test]
+    fn test_directional_light() {
+        let light = DirectionalLight::new(Vec3::new(0.0, -2.0, 0.0), Vec3::new(3.0, 3.0, 3.0));
+        let sample = light.sample_li(Vec3::new(5.0, 0.0, 5.0)).unwrap();
+        assert!((sample.wi - Vec3::new(0.0, 1.0, 0.0)).length() < 1e-12);
+        assert_eq!(sample.dist, f64::INFINITY);
+        assert_eq!(sample.radiance.x, 3.0);
+
+        let soft = DirectionalLight::new_with_radius(Vec3::new(0.0, -1.0, 0.0), Vec3::ones(), 5.0);
+        for _ in 0..100 {
+            let wi = soft.sample_li(Vec3::zero()).unwrap().wi;
+            assert!(wi.y >= soft.cos_angular_radius - 1e-9);
+        }
+    }
+}
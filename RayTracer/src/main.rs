@@ -1,34 +1,86 @@
 mod aabb;
+mod animation;
+mod bdpt;
+mod bezier;
 mod bvh;
 mod camera;
 mod color;
+mod csg;
+mod cylinder;
+mod distribution;
+mod environment;
+mod heightfield;
 mod hit;
 mod hit_list;
 mod interval;
+mod lens;
+mod light;
 mod material;
+mod medium;
+mod mesh;
+mod microfacet;
+mod mlt;
+mod onb;
 mod perlin;
+mod plane;
+mod principled;
+mod procedural;
 mod quad;
 mod ray;
 mod rt;
+mod sdf;
+mod sky;
+mod spectrum;
 mod sphere;
+mod sppm;
+mod subdivision;
 mod texture;
+mod torus;
+mod transform;
 mod vec3;
+mod voxel;
 //
 //
 use crate::quad::gen_box;
+use aabb::AABB;
+use animation::{CameraKey, CameraPath, PathInterpolation, Sequence};
+use bezier::{read_patches, BezierPatch};
 use bvh::Bvh_Node;
-use camera::Camera;
+use camera::{Camera, Integrator, Projection, ShutterCurve};
+use csg::{Csg, CsgOp};
+use cylinder::{Cone, Cylinder};
+use environment::EnvironmentLight;
+use heightfield::Heightfield;
+use hit::Hittable;
 use hit_list::HitList;
 use image::{ImageBuffer, Pixel, RgbImage}; //接收render传的图片，在main中文件输出
-use material::{Dielectric, DiffuseLight, Lambertian, Metal};
+use lens::{Aperture, ApertureImage, ThinLens};
+use light::{DirectionalLight, PointLight, SpotLight};
+use material::{
+    AlphaMask, BumpMap, Conductor, Dielectric, DiffuseLight, Lambertian, Material, Metal,
+    NormalMap, RoughDielectric,
+};
+use mesh::{EdgeTarget, TriangleMesh};
+use perlin::{NoiseBasis, Perlin};
+use plane::Plane;
+use principled::Principled;
+use procedural::{
+    ColorRamp, Fbm, Marble, RidgedMultifractal, Turbulence, Wood, Worley, WorleyFeature,
+};
+use sdf::{Sdf, SdfShape};
+use sky::Sky;
 use sphere::Sphere;
 use std::sync::Arc;
 use std::{fs::File, sync::WaitTimeoutResult};
+use subdivision::{SubdivisionScheme, SubdivisionSurface};
 use texture::{NoiseTexture, Texture};
+use torus::Torus;
+use transform::{Animated, AnimatedTransform, Keyframe, Quat, Transform};
 use vec3::Vec3;
+use voxel::VoxelGrid;
 const AUTHOR: &str = "Teacher_BigN";
 use crate::quad::Quad;
-use crate::texture::{CheckerTexture, ImageTexture};
+use crate::texture::{CheckerTexture, ColorSpace, ImageTexture, TextureFilter, WrapMode};
 use rand::{self, Rng};
 use rt::random_f64;
 //
@@ -449,6 +501,2207 @@ pub fn cornel_box(path: &str) {
         Err(_) => println!("Outputting image fails."),
     }
 }
+pub fn bdpt_caustics(path: &str) {
+    let mut camera = Camera::init(600, 1.0);
+    let mut img: RgbImage = ImageBuffer::new(camera.width as u32, camera.height as u32);
+    let quality = 60;
+    let list = Vec::new();
+    let mut world = HitList::new(list);
+    let red = Arc::new(Lambertian::new(Vec3::new(0.65, 0.05, 0.05)));
+    let white = Arc::new(Lambertian::new(Vec3::new(0.73, 0.73, 0.73)));
+    let green = Arc::new(Lambertian::new(Vec3::new(0.12, 0.45, 0.15)));
+    let light = Arc::new(DiffuseLight::new_by_color(Vec3::new(60.0, 60.0, 60.0)));
+
+    world.add(Arc::new(Quad::new(
+        Vec3::new(555.0, 0.0, 0.0),
+        Vec3::new(0.0, 555.0, 0.0),
+        Vec3::new(0.0, 0.0, 555.0),
+        green.clone(),
+    )));
+    world.add(Arc::new(Quad::new(
+        Vec3::new(0.0, 0.0, 0.0),
+        Vec3::new(0.0, 555.0, 0.0),
+        Vec3::new(0.0, 0.0, 555.0),
+        red.clone(),
+    )));
+    // a small lamp, most of the room is lit through the glass and the walls
+    let lamp = Arc::new(Quad::new(
+        Vec3::new(310.0, 554.0, 305.0),
+        Vec3::new(-65.0, 0.0, 0.0),
+        Vec3::new(0.0, 0.0, -52.0),
+        light.clone(),
+    ));
+    world.add(lamp.clone());
+    world.add(Arc::new(Quad::new(
+        Vec3::new(0.0, 0.0, 0.0),
+        Vec3::new(555.0, 0.0, 0.0),
+        Vec3::new(0.0, 0.0, 555.0),
+        white.clone(),
+    )));
+    world.add(Arc::new(Quad::new(
+        Vec3::new(555.0, 555.0, 555.0),
+        Vec3::new(-555.0, 0.0, 0.0),
+        Vec3::new(0.0, 0.0, -555.0),
+        white.clone(),
+    )));
+    world.add(Arc::new(Quad::new(
+        Vec3::new(0.0, 0.0, 555.0),
+        Vec3::new(555.0, 0.0, 0.0),
+        Vec3::new(0.0, 555.0, 0.0),
+        white.clone(),
+    )));
+    world.add(Arc::new(Sphere::new(
+        Vec3::new(190.0, 90.0, 190.0),
+        90.0,
+        Some(Arc::new(Dielectric::new(1.5))),
+    )));
+    world.add(gen_box(
+        Vec3::new(265.0, 0.0, 295.0),
+        Vec3::new(430.0, 330.0, 460.0),
+        white.clone(),
+    ));
+
+    camera.samples_per_pixel = 100;
+    camera.max_depth = 12;
+    camera.integrator = Integrator::Bdpt;
+    camera.emitters.push(lamp);
+    camera.background = Vec3::new(0.0, 0.0, 0.0);
+    camera.vfov = 40.0;
+    camera.lookfrom = Vec3::new(278.0, 278.0, -800.0);
+    camera.lookat = Vec3::new(278.0, 278.0, 0.0);
+    camera.vup = Vec3::new(0.0, 1.0, 0.0);
+    camera.defocus_angle = 0.0;
+    camera.render(&world, &mut img);
+
+    println!("Ouput image as \"{}\"\n Author: {}", path, AUTHOR);
+    let output_image: image::DynamicImage = image::DynamicImage::ImageRgb8(img);
+    let mut output_file: File = File::create(path).unwrap();
+    match output_image.write_to(&mut output_file, image::ImageOutputFormat::Jpeg(quality)) {
+        Ok(_) => {}
+        Err(_) => println!("Outputting image fails."),
+    }
+}
+pub fn caustic_spheres(path: &str) {
+    let mut camera = Camera::init(400, 16.0 / 9.0);
+    let quality = 100;
+    let mut img: RgbImage = ImageBuffer::new(camera.width as u32, camera.height as u32);
+    let list = Vec::new();
+    let mut world = HitList::new(list);
+    let checker = Arc::new(CheckerTexture::new_by_color(
+        0.32,
+        Vec3::new(0.2, 0.3, 0.1),
+        Vec3::new(0.9, 0.9, 0.9),
+    ));
+    world.add(Arc::new(Sphere::new(
+        Vec3::new(0.0, -1000.0, 0.0),
+        1000.0,
+        Some(Arc::new(Lambertian::new_by_tex(checker))),
+    )));
+    world.add(Arc::new(Sphere::new(
+        Vec3::new(0.0, 1.0, 0.0),
+        1.0,
+        Some(Arc::new(Dielectric::new(1.5))),
+    )));
+    world.add(Arc::new(Sphere::new(
+        Vec3::new(-4.0, 1.0, 0.0),
+        1.0,
+        Some(Arc::new(Lambertian::new(Vec3::new(0.4, 0.2, 0.1)))),
+    )));
+    world.add(Arc::new(Sphere::new(
+        Vec3::new(4.0, 1.0, 0.0),
+        1.0,
+        Some(Arc::new(Metal::new(Vec3::new(0.7, 0.6, 0.5), 0.0))),
+    )));
+    // a small light behind the glass sphere focuses a caustic in front of it
+    let lamp = Arc::new(Sphere::new(
+        Vec3::new(-3.0, 5.0, -3.0),
+        0.3,
+        Some(Arc::new(DiffuseLight::new_by_color(Vec3::new(
+            150.0, 140.0, 120.0,
+        )))),
+    ));
+    world.add(lamp.clone());
+
+    camera.samples_per_pixel = 64;
+    camera.max_depth = 10;
+    camera.background = Vec3::zero();
+    camera.vfov = 20.0;
+    camera.lookfrom = Vec3::new(13.0, 2.0, 3.0);
+    camera.lookat = Vec3::new(0.0, 0.0, 0.0);
+    camera.vup = Vec3::new(0.0, 1.0, 0.0);
+    camera.defocus_angle = 0.0;
+    camera.emitters.push(lamp);
+    camera.integrator = Integrator::Sppm {
+        photons_per_pass: 200_000,
+        initial_radius: 0.1,
+    };
+    camera.render(&world, &mut img);
+
+    println!("Ouput image as \"{}\"\n Author: {}", path, AUTHOR);
+    let output_image: image::DynamicImage = image::DynamicImage::ImageRgb8(img);
+    let mut output_file: File = File::create(path).unwrap();
+    match output_image.write_to(&mut output_file, image::ImageOutputFormat::Jpeg(quality)) {
+        Ok(_) => {}
+        Err(_) => println!("Outputting image fails."),
+    }
+}
+pub fn shaded_lamp(path: &str) {
+    let mut camera = Camera::init(400, 1.0);
+    let mut img: RgbImage = ImageBuffer::new(camera.width as u32, camera.height as u32);
+    let quality = 60;
+    let list = Vec::new();
+    let mut world = HitList::new(list);
+    let red = Arc::new(Lambertian::new(Vec3::new(0.65, 0.05, 0.05)));
+    let white = Arc::new(Lambertian::new(Vec3::new(0.73, 0.73, 0.73)));
+    let green = Arc::new(Lambertian::new(Vec3::new(0.12, 0.45, 0.15)));
+    let light = Arc::new(DiffuseLight::new_by_color(Vec3::new(50.0, 50.0, 50.0)));
+
+    world.add(Arc::new(Quad::new(
+        Vec3::new(555.0, 0.0, 0.0),
+        Vec3::new(0.0, 555.0, 0.0),
+        Vec3::new(0.0, 0.0, 555.0),
+        green,
+    )));
+    world.add(Arc::new(Quad::new(
+        Vec3::new(0.0, 0.0, 0.0),
+        Vec3::new(0.0, 555.0, 0.0),
+        Vec3::new(0.0, 0.0, 555.0),
+        red,
+    )));
+    world.add(Arc::new(Quad::new(
+        Vec3::new(343.0, 554.0, 332.0),
+        Vec3::new(-130.0, 0.0, 0.0),
+        Vec3::new(0.0, 0.0, -105.0),
+        light,
+    )));
+    world.add(Arc::new(Quad::new(
+        Vec3::new(0.0, 0.0, 0.0),
+        Vec3::new(555.0, 0.0, 0.0),
+        Vec3::new(0.0, 0.0, 555.0),
+        white.clone(),
+    )));
+    world.add(Arc::new(Quad::new(
+        Vec3::new(555.0, 555.0, 555.0),
+        Vec3::new(-555.0, 0.0, 0.0),
+        Vec3::new(0.0, 0.0, -555.0),
+        white.clone(),
+    )));
+    world.add(Arc::new(Quad::new(
+        Vec3::new(0.0, 0.0, 555.0),
+        Vec3::new(555.0, 0.0, 0.0),
+        Vec3::new(0.0, 555.0, 0.0),
+        white.clone(),
+    )));
+    // a tray under the lamp, the room only gets the light leaking out past its rim
+    let (x0, x1, z0, z1, y0, y1) = (183.0, 373.0, 197.0, 362.0, 500.0, 535.0);
+    world.add(Arc::new(Quad::new(
+        Vec3::new(x0, y0, z0),
+        Vec3::new(x1 - x0, 0.0, 0.0),
+        Vec3::new(0.0, 0.0, z1 - z0),
+        white.clone(),
+    )));
+    for (corner, side) in [
+        (Vec3::new(x0, y0, z0), Vec3::new(x1 - x0, 0.0, 0.0)),
+        (Vec3::new(x0, y0, z1), Vec3::new(x1 - x0, 0.0, 0.0)),
+        (Vec3::new(x0, y0, z0), Vec3::new(0.0, 0.0, z1 - z0)),
+        (Vec3::new(x1, y0, z0), Vec3::new(0.0, 0.0, z1 - z0)),
+    ] {
+        world.add(Arc::new(Quad::new(
+            corner,
+            side,
+            Vec3::new(0.0, y1 - y0, 0.0),
+            white.clone(),
+        )));
+    }
+    world.add(gen_box(
+        Vec3::new(265.0, 0.0, 295.0),
+        Vec3::new(430.0, 330.0, 460.0),
+        white.clone(),
+    ));
+    world.add(gen_box(
+        Vec3::new(130.0, 0.0, 65.0),
+        Vec3::new(295.0, 165.0, 230.0),
+        white,
+    ));
+
+    camera.samples_per_pixel = 64;
+    camera.max_depth = 12;
+    camera.integrator = Integrator::Pssmlt {
+        chains: 1000,
+        bootstrap: 100_000,
+        large_step_probability: 0.3,
+        sigma: 0.01,
+    };
+    camera.background = Vec3::new(0.0, 0.0, 0.0);
+    camera.vfov = 40.0;
+    camera.lookfrom = Vec3::new(278.0, 278.0, -800.0);
+    camera.lookat = Vec3::new(278.0, 278.0, 0.0);
+    camera.vup = Vec3::new(0.0, 1.0, 0.0);
+    camera.defocus_angle = 0.0;
+    camera.render(&world, &mut img);
+
+    println!("Ouput image as \"{}\"\n Author: {}", path, AUTHOR);
+    let output_image: image::DynamicImage = image::DynamicImage::ImageRgb8(img);
+    let mut output_file: File = File::create(path).unwrap();
+    match output_image.write_to(&mut output_file, image::ImageOutputFormat::Jpeg(quality)) {
+        Ok(_) => {}
+        Err(_) => println!("Outputting image fails."),
+    }
+}
+pub fn primitives(path: &str) {
+    let mut camera = Camera::init(600, 16.0 / 9.0);
+    let mut img: RgbImage = ImageBuffer::new(camera.width as u32, camera.height as u32);
+    let quality = 60;
+    let list = Vec::new();
+    let mut world = HitList::new(list);
+    let checker = Arc::new(CheckerTexture::new_by_color(
+        0.5,
+        Vec3::new(0.2, 0.3, 0.1),
+        Vec3::new(0.9, 0.9, 0.9),
+    ));
+    world.add(Arc::new(Plane::new(
+        Vec3::zero(),
+        Vec3::new(0.0, 1.0, 0.0),
+        Arc::new(Lambertian::new_by_tex(checker)),
+    )));
+    world.add(Arc::new(Cylinder::new(
+        Vec3::new(-4.5, 0.0, 0.0),
+        Vec3::new(-4.5, 2.0, 0.0),
+        0.7,
+        true,
+        Arc::new(Metal::new(Vec3::new(0.8, 0.8, 0.85), 0.05)),
+    )));
+    world.add(Arc::new(Cylinder::new(
+        Vec3::new(-4.0, 0.4, 2.5),
+        Vec3::new(-2.5, 0.4, 3.0),
+        0.4,
+        false,
+        Arc::new(Lambertian::new(Vec3::new(0.2, 0.4, 0.8))),
+    )));
+    world.add(Arc::new(Cone::new(
+        Vec3::new(-1.8, 0.0, 0.0),
+        Vec3::new(-1.8, 2.2, 0.0),
+        0.8,
+        true,
+        Arc::new(Lambertian::new(Vec3::new(0.8, 0.3, 0.1))),
+    )));
+    world.add(Arc::new(Torus::new(
+        Vec3::new(0.8, 0.9, 0.0),
+        Vec3::new(0.0, 1.0, 1.5),
+        0.8,
+        0.3,
+        Arc::new(Conductor::gold(0.2)),
+    )));
+    world.add(Arc::new(Quad::new_triangle(
+        Vec3::new(2.5, 0.0, -0.5),
+        Vec3::new(1.5, 0.0, 0.0),
+        Vec3::new(0.75, 2.0, 0.0),
+        Arc::new(Lambertian::new(Vec3::new(0.7, 0.1, 0.5))),
+    )));
+    world.add(Arc::new(Quad::new_disk(
+        Vec3::new(0.0, 0.01, 2.5),
+        Vec3::new(0.0, 1.0, 0.0),
+        0.8,
+        Arc::new(Lambertian::new(Vec3::new(0.9, 0.8, 0.2))),
+    )));
+    world.add(Arc::new(Quad::new_ellipse(
+        Vec3::new(5.0, 1.2, 0.0),
+        Vec3::new(0.0, 1.0, 0.0),
+        Vec3::new(-0.6, 0.0, 0.2),
+        Arc::new(Lambertian::new(Vec3::new(0.1, 0.6, 0.3))),
+    )));
+    world.add(Arc::new(Sphere::new(
+        Vec3::new(2.5, 0.6, 2.5),
+        0.6,
+        Some(Arc::new(Dielectric::new(1.5))),
+    )));
+    // the plane stays out of the tree, everything else is sorted into it
+    let world = HitList::new_by_arc(Arc::new(Bvh_Node::new_by_list(&world)));
+
+    camera.samples_per_pixel = 100;
+    camera.max_depth = 20;
+    camera.background = Vec3::new(0.7, 0.8, 1.0);
+    camera.vfov = 35.0;
+    camera.lookfrom = Vec3::new(0.0, 4.0, 12.0);
+    camera.lookat = Vec3::new(0.0, 1.0, 0.0);
+    camera.vup = Vec3::new(0.0, 1.0, 0.0);
+    camera.defocus_angle = 0.0;
+    camera.render(&world, &mut img);
+
+    println!("Ouput image as \"{}\"\n Author: {}", path, AUTHOR);
+    let output_image: image::DynamicImage = image::DynamicImage::ImageRgb8(img);
+    let mut output_file: File = File::create(path).unwrap();
+    match output_image.write_to(&mut output_file, image::ImageOutputFormat::Jpeg(quality)) {
+        Ok(_) => {}
+        Err(_) => println!("Outputting image fails."),
+    }
+}
+pub fn csg_shapes(path: &str) {
+    let mut camera = Camera::init(600, 16.0 / 9.0);
+    let mut img: RgbImage = ImageBuffer::new(camera.width as u32, camera.height as u32);
+    let quality = 60;
+    let list = Vec::new();
+    let mut world = HitList::new(list);
+    let checker = Arc::new(CheckerTexture::new_by_color(
+        0.5,
+        Vec3::new(0.2, 0.2, 0.2),
+        Vec3::new(0.8, 0.8, 0.8),
+    ));
+    world.add(Arc::new(Plane::new(
+        Vec3::zero(),
+        Vec3::new(0.0, 1.0, 0.0),
+        Arc::new(Lambertian::new_by_tex(checker)),
+    )));
+
+    // a sphere with a corner cut out
+    let red = Arc::new(Lambertian::new(Vec3::new(0.7, 0.15, 0.1)));
+    world.add(Arc::new(Csg::new(
+        CsgOp::Difference,
+        Arc::new(Sphere::new(
+            Vec3::new(-3.0, 1.0, 0.0),
+            1.0,
+            Some(red.clone()),
+        )),
+        gen_box(Vec3::new(-3.0, 1.0, 0.0), Vec3::new(-1.5, 2.5, 1.5), red),
+    )));
+
+    // the classic: a rounded cube with three drilled holes
+    let blue = Arc::new(Lambertian::new(Vec3::new(0.1, 0.3, 0.8)));
+    let yellow = Arc::new(Lambertian::new(Vec3::new(0.9, 0.7, 0.1)));
+    let center = Vec3::new(0.0, 1.0, 0.0);
+    let rounded_cube = Arc::new(Csg::new(
+        CsgOp::Intersection,
+        gen_box(
+            center - Vec3::ones() * 0.8,
+            center + Vec3::ones() * 0.8,
+            blue,
+        ),
+        Arc::new(Sphere::new(center, 1.05, Some(yellow.clone()))),
+    ));
+    let mut drills: Arc<dyn Hittable> = Arc::new(Cylinder::new(
+        center - Vec3::new(1.0, 0.0, 0.0),
+        center + Vec3::new(1.0, 0.0, 0.0),
+        0.45,
+        true,
+        yellow.clone(),
+    ));
+    for axis in [Vec3::new(0.0, 1.0, 0.0), Vec3::new(0.0, 0.0, 1.0)] {
+        drills = Arc::new(Csg::new(
+            CsgOp::Union,
+            drills,
+            Arc::new(Cylinder::new(
+                center - axis,
+                center + axis,
+                0.45,
+                true,
+                yellow.clone(),
+            )),
+        ));
+    }
+    world.add(Arc::new(Csg::new(CsgOp::Difference, rounded_cube, drills)));
+
+    // a glass lens from two overlapping spheres
+    world.add(Arc::new(Csg::new(
+        CsgOp::Intersection,
+        Arc::new(Sphere::new(
+            Vec3::new(3.0, 1.0, -1.2),
+            1.5,
+            Some(Arc::new(Dielectric::new(1.5))),
+        )),
+        Arc::new(Sphere::new(
+            Vec3::new(3.0, 1.0, 1.2),
+            1.5,
+            Some(Arc::new(Dielectric::new(1.5))),
+        )),
+    )));
+
+    camera.samples_per_pixel = 100;
+    camera.max_depth = 20;
+    camera.background = Vec3::new(0.7, 0.8, 1.0);
+    camera.vfov = 30.0;
+    camera.lookfrom = Vec3::new(4.0, 4.5, 10.0);
+    camera.lookat = Vec3::new(0.0, 0.8, 0.0);
+    camera.vup = Vec3::new(0.0, 1.0, 0.0);
+    camera.defocus_angle = 0.0;
+    camera.render(&world, &mut img);
+
+    println!("Ouput image as \"{}\"\n Author: {}", path, AUTHOR);
+    let output_image: image::DynamicImage = image::DynamicImage::ImageRgb8(img);
+    let mut output_file: File = File::create(path).unwrap();
+    match output_image.write_to(&mut output_file, image::ImageOutputFormat::Jpeg(quality)) {
+        Ok(_) => {}
+        Err(_) => println!("Outputting image fails."),
+    }
+}
+pub fn sdf_shapes(path: &str) {
+    let mut camera = Camera::init(600, 16.0 / 9.0);
+    let mut img: RgbImage = ImageBuffer::new(camera.width as u32, camera.height as u32);
+    let quality = 60;
+    let list = Vec::new();
+    let mut world = HitList::new(list);
+    world.add(Arc::new(Plane::new(
+        Vec3::zero(),
+        Vec3::new(0.0, 1.0, 0.0),
+        Arc::new(Lambertian::new(Vec3::new(0.5, 0.5, 0.5))),
+    )));
+
+    // two spheres melting into a rounded box with a torus carved into its top
+    let blob = Sdf::RoundBox {
+        half: Vec3::new(0.7, 0.5, 0.7),
+        radius: 0.15,
+    }
+    .smooth_union(
+        Sdf::Sphere { radius: 0.45 }.translate(Vec3::new(0.0, 0.7, 0.0)),
+        0.3,
+    )
+    .smooth_union(
+        Sdf::Capsule {
+            a: Vec3::new(-0.9, -0.3, 0.0),
+            b: Vec3::new(0.9, -0.3, 0.0),
+            radius: 0.2,
+        },
+        0.1,
+    )
+    .smooth_subtraction(
+        Sdf::Torus {
+            major: 0.45,
+            minor: 0.12,
+        }
+        .translate(Vec3::new(0.0, 1.1, 0.0)),
+        0.05,
+    );
+    world.add(Arc::new(SdfShape::new(
+        blob.translate(Vec3::new(-3.2, 0.5, 0.0)),
+        AABB::new_by_point(Vec3::new(-4.4, -0.1, -1.1), Vec3::new(-2.0, 2.0, 1.1)),
+        Arc::new(Lambertian::new(Vec3::new(0.8, 0.3, 0.2))),
+    )));
+
+    // a twisted column, the twist stretches the field so the march takes smaller steps
+    let mut column = SdfShape::new(
+        Sdf::Box {
+            half: Vec3::new(0.4, 1.2, 0.4),
+        }
+        .twist(1.2)
+        .translate(Vec3::new(-1.0, 1.2, 0.0)),
+        AABB::new_by_point(Vec3::new(-1.6, 0.0, -0.6), Vec3::new(-0.4, 2.4, 0.6)),
+        Arc::new(Metal::new(Vec3::new(0.8, 0.8, 0.85), 0.1)),
+    );
+    column.step_scale = 0.6;
+    world.add(Arc::new(column));
+
+    let mut bulb = SdfShape::new(
+        Sdf::Mandelbulb {
+            power: 8.0,
+            iterations: 12,
+        }
+        .translate(Vec3::new(1.2, 1.2, 0.0)),
+        AABB::new_by_point(Vec3::new(0.0, 0.0, -1.2), Vec3::new(2.4, 2.4, 1.2)),
+        Arc::new(Lambertian::new(Vec3::new(0.3, 0.5, 0.8))),
+    );
+    bulb.epsilon = 5e-4;
+    world.add(Arc::new(bulb));
+
+    // a row of wavy spheres cut off by the box around them
+    let wavy = Sdf::from_fn(|p| {
+        let ripple = 0.03 * (12.0 * p.x).sin() * (12.0 * p.y).sin();
+        p.length() - 0.3 - ripple
+    });
+    let mut row = SdfShape::new(
+        wavy.repeat(Vec3::new(0.0, 0.0, 0.9))
+            .translate(Vec3::new(3.3, 0.35, 0.0)),
+        AABB::new_by_point(Vec3::new(2.9, 0.0, -2.5), Vec3::new(3.7, 0.7, 2.5)),
+        Arc::new(Lambertian::new(Vec3::new(0.9, 0.7, 0.1))),
+    );
+    row.step_scale = 0.7;
+    world.add(Arc::new(row));
+
+    camera.samples_per_pixel = 100;
+    camera.max_depth = 20;
+    camera.background = Vec3::new(0.7, 0.8, 1.0);
+    camera.vfov = 30.0;
+    camera.lookfrom = Vec3::new(0.0, 4.0, 11.0);
+    camera.lookat = Vec3::new(0.0, 0.9, 0.0);
+    camera.vup = Vec3::new(0.0, 1.0, 0.0);
+    camera.defocus_angle = 0.0;
+    camera.render(&world, &mut img);
+
+    println!("Ouput image as \"{}\"\n Author: {}", path, AUTHOR);
+    let output_image: image::DynamicImage = image::DynamicImage::ImageRgb8(img);
+    let mut output_file: File = File::create(path).unwrap();
+    match output_image.write_to(&mut output_file, image::ImageOutputFormat::Jpeg(quality)) {
+        Ok(_) => {}
+        Err(_) => println!("Outputting image fails."),
+    }
+}
+pub fn perlin_terrain(path: &str) {
+    let mut camera = Camera::init(600, 16.0 / 9.0);
+    let mut img: RgbImage = ImageBuffer::new(camera.width as u32, camera.height as u32);
+    let quality = 60;
+    let list = Vec::new();
+    let mut world = HitList::new(list);
+    // a million samples, only stored as heights
+    world.add(Arc::new(Heightfield::from_perlin(
+        &Perlin::new_seeded(7),
+        1024,
+        1024,
+        0.08,
+        7,
+        Vec3::new(-50.0, 0.0, -50.0),
+        Vec3::new(100.0, 12.0, 100.0),
+        Arc::new(Lambertian::new(Vec3::new(0.45, 0.4, 0.3))),
+    )));
+    world.add(Arc::new(Plane::new(
+        Vec3::new(0.0, 3.0, 0.0),
+        Vec3::new(0.0, 1.0, 0.0),
+        Arc::new(Metal::new(Vec3::new(0.3, 0.45, 0.5), 0.02)),
+    )));
+
+    camera.samples_per_pixel = 64;
+    camera.max_depth = 10;
+    camera.vfov = 40.0;
+    camera.lookfrom = Vec3::new(0.0, 16.0, 45.0);
+    camera.lookat = Vec3::new(0.0, 4.0, 0.0);
+    camera.vup = Vec3::new(0.0, 1.0, 0.0);
+    camera.defocus_angle = 0.0;
+    camera.environment = Some(Arc::new(Sky::new_by_angles(
+        20.0,
+        120.0,
+        3.0,
+        Vec3::new(0.3, 0.3, 0.3),
+    )));
+    camera.render(&world, &mut img);
+
+    println!("Ouput image as \"{}\"\n Author: {}", path, AUTHOR);
+    let output_image: image::DynamicImage = image::DynamicImage::ImageRgb8(img);
+    let mut output_file: File = File::create(path).unwrap();
+    match output_image.write_to(&mut output_file, image::ImageOutputFormat::Jpeg(quality)) {
+        Ok(_) => {}
+        Err(_) => println!("Outputting image fails."),
+    }
+}
+pub fn image_terrain(path: &str) {
+    let mut camera = Camera::init(600, 16.0 / 9.0);
+    let mut img: RgbImage = ImageBuffer::new(camera.width as u32, camera.height as u32);
+    let quality = 60;
+    let list = Vec::new();
+    let mut world = HitList::new(list);
+    let terrain = match Heightfield::from_image(
+        "input/hmap.jpg",
+        Vec3::new(-10.0, 0.0, -10.0),
+        Vec3::new(20.0, 1.5, 20.0),
+        Arc::new(Lambertian::new(Vec3::new(0.6, 0.55, 0.5))),
+    ) {
+        Ok(terrain) => terrain,
+        Err(_) => {
+            println!("Error loading height map!");
+            return;
+        }
+    };
+    world.add(Arc::new(terrain));
+
+    camera.samples_per_pixel = 64;
+    camera.max_depth = 10;
+    camera.vfov = 40.0;
+    camera.lookfrom = Vec3::new(0.0, 12.0, 18.0);
+    camera.lookat = Vec3::new(0.0, 0.0, 0.0);
+    camera.vup = Vec3::new(0.0, 1.0, 0.0);
+    camera.defocus_angle = 0.0;
+    camera.environment = Some(Arc::new(Sky::new_by_angles(
+        30.0,
+        60.0,
+        3.0,
+        Vec3::new(0.3, 0.3, 0.3),
+    )));
+    camera.render(&world, &mut img);
+
+    println!("Ouput image as \"{}\"\n Author: {}", path, AUTHOR);
+    let output_image: image::DynamicImage = image::DynamicImage::ImageRgb8(img);
+    let mut output_file: File = File::create(path).unwrap();
+    match output_image.write_to(&mut output_file, image::ImageOutputFormat::Jpeg(quality)) {
+        Ok(_) => {}
+        Err(_) => println!("Outputting image fails."),
+    }
+}
+pub fn voxel_world(path: &str) {
+    let mut camera = Camera::init(600, 16.0 / 9.0);
+    let mut img: RgbImage = ImageBuffer::new(camera.width as u32, camera.height as u32);
+    let quality = 60;
+    let list = Vec::new();
+    let mut world = HitList::new(list);
+    let earth_texture: Arc<dyn Texture> = match ImageTexture::new("input/earthmap.jpg") {
+        Ok(texture) => Arc::new(texture),
+        Err(_) => {
+            println!("Error creating texture!");
+            return;
+        }
+    };
+    let materials: Vec<Arc<dyn Material>> = vec![
+        Arc::new(Lambertian::new(Vec3::new(0.3, 0.55, 0.2))),
+        Arc::new(Lambertian::new(Vec3::new(0.45, 0.3, 0.2))),
+        Arc::new(Lambertian::new(Vec3::new(0.5, 0.5, 0.5))),
+        Arc::new(Dielectric::new(1.5)),
+        Arc::new(Lambertian::new_by_tex(earth_texture)),
+    ];
+    // grass over dirt over stone, a glass ball and a block of earth maps above
+    let dims = [96, 48, 96];
+    let noise = Perlin::new_seeded(11);
+    let mut values = vec![0; dims[0] * dims[1] * dims[2]];
+    for z in 0..dims[2] {
+        for x in 0..dims[0] {
+            let p = Vec3::new(x as f64, 0.0, z as f64) * 0.05;
+            let top = (6.0 + 14.0 * noise.turb(p, 5)) as usize;
+            for y in 0..top.min(dims[1]) {
+                values[(z * dims[1] + y) * dims[0] + x] = match top - y {
+                    1 => 1,
+                    2..=3 => 2,
+                    _ => 3,
+                };
+            }
+        }
+    }
+    for z in 0..dims[2] {
+        for y in 0..dims[1] {
+            for x in 0..dims[0] {
+                let (dx, dy, dz) = (x as f64 - 40.5, y as f64 - 36.5, z as f64 - 52.5);
+                if dx * dx + dy * dy + dz * dz < 64.0 {
+                    values[(z * dims[1] + y) * dims[0] + x] = 4;
+                }
+                if (58..66).contains(&x) && (30..38).contains(&y) && (44..52).contains(&z) {
+                    values[(z * dims[1] + y) * dims[0] + x] = 5;
+                }
+            }
+        }
+    }
+    world.add(Arc::new(VoxelGrid::from_raw(
+        dims,
+        &values,
+        Vec3::new(-12.0, 0.0, -12.0),
+        0.25,
+        materials,
+    )));
+
+    camera.samples_per_pixel = 64;
+    camera.max_depth = 10;
+    camera.vfov = 40.0;
+    camera.lookfrom = Vec3::new(-10.0, 18.0, 22.0);
+    camera.lookat = Vec3::new(0.0, 5.0, 0.0);
+    camera.vup = Vec3::new(0.0, 1.0, 0.0);
+    camera.defocus_angle = 0.0;
+    camera.environment = Some(Arc::new(Sky::new_by_angles(
+        35.0,
+        140.0,
+        3.0,
+        Vec3::new(0.3, 0.3, 0.3),
+    )));
+    camera.render(&world, &mut img);
+
+    println!("Ouput image as \"{}\"\n Author: {}", path, AUTHOR);
+    let output_image: image::DynamicImage = image::DynamicImage::ImageRgb8(img);
+    let mut output_file: File = File::create(path).unwrap();
+    match output_image.write_to(&mut output_file, image::ImageOutputFormat::Jpeg(quality)) {
+        Ok(_) => {}
+        Err(_) => println!("Outputting image fails."),
+    }
+}
+pub fn vox_model(path: &str) {
+    let mut camera = Camera::init(600, 1.0);
+    let mut img: RgbImage = ImageBuffer::new(camera.width as u32, camera.height as u32);
+    let quality = 60;
+    let list = Vec::new();
+    let mut world = HitList::new(list);
+    let model = match VoxelGrid::from_vox("input/model.vox", Vec3::new(-8.0, 0.0, -8.0), 0.5, |c| {
+        Arc::new(Lambertian::new(c))
+    }) {
+        Ok(model) => model,
+        Err(_) => {
+            println!("Error loading voxel model!");
+            return;
+        }
+    };
+    let center = 0.5 * (model.bounding_box().x.min + model.bounding_box().x.max);
+    world.add(Arc::new(model));
+    world.add(Arc::new(Plane::new(
+        Vec3::zero(),
+        Vec3::new(0.0, 1.0, 0.0),
+        Arc::new(Lambertian::new(Vec3::new(0.5, 0.5, 0.5))),
+    )));
+
+    camera.samples_per_pixel = 64;
+    camera.max_depth = 10;
+    camera.vfov = 40.0;
+    camera.lookfrom = Vec3::new(center + 20.0, 20.0, 30.0);
+    camera.lookat = Vec3::new(center, 4.0, 0.0);
+    camera.vup = Vec3::new(0.0, 1.0, 0.0);
+    camera.defocus_angle = 0.0;
+    camera.environment = Some(Arc::new(Sky::new_by_angles(
+        40.0,
+        120.0,
+        3.0,
+        Vec3::new(0.3, 0.3, 0.3),
+    )));
+    camera.render(&world, &mut img);
+
+    println!("Ouput image as \"{}\"\n Author: {}", path, AUTHOR);
+    let output_image: image::DynamicImage = image::DynamicImage::ImageRgb8(img);
+    let mut output_file: File = File::create(path).unwrap();
+    match output_image.write_to(&mut output_file, image::ImageOutputFormat::Jpeg(quality)) {
+        Ok(_) => {}
+        Err(_) => println!("Outputting image fails."),
+    }
+}
+pub fn smooth_surfaces(path: &str) {
+    let mut camera = Camera::init(600, 16.0 / 9.0);
+    let mut img: RgbImage = ImageBuffer::new(camera.width as u32, camera.height as u32);
+    let quality = 60;
+    camera.samples_per_pixel = 100;
+    camera.max_depth = 20;
+    camera.background = Vec3::new(0.7, 0.8, 1.0);
+    camera.vfov = 35.0;
+    camera.lookfrom = Vec3::new(0.0, 4.0, 12.0);
+    camera.lookat = Vec3::new(0.0, 1.0, 0.0);
+    camera.vup = Vec3::new(0.0, 1.0, 0.0);
+    camera.defocus_angle = 0.0;
+    // diced so that no edge is longer than two pixels from where the camera stands
+    let target = EdgeTarget::new(&camera, 2.0);
+
+    let list = Vec::new();
+    let mut world = HitList::new(list);
+    world.add(Arc::new(Plane::new(
+        Vec3::zero(),
+        Vec3::new(0.0, 1.0, 0.0),
+        Arc::new(Lambertian::new(Vec3::new(0.5, 0.5, 0.5))),
+    )));
+    let earth_texture: Arc<dyn Texture> = match ImageTexture::new("input/earthmap.jpg") {
+        Ok(texture) => Arc::new(texture),
+        Err(_) => {
+            println!("Error creating texture!");
+            return;
+        }
+    };
+    // a cube rounded by Catmull–Clark, with the earth map stretched over its uvs
+    let corners = (0..8)
+        .map(|k| {
+            let coord = |bit: usize, lo: f64, hi: f64| if k & bit != 0 { hi } else { lo };
+            Vec3::new(
+                coord(1, -4.5, -2.5),
+                coord(2, 0.0, 2.0),
+                coord(4, -1.0, 1.0),
+            )
+        })
+        .collect();
+    let quads = [
+        [0, 2, 3, 1],
+        [4, 5, 7, 6],
+        [0, 1, 5, 4],
+        [2, 6, 7, 3],
+        [0, 4, 6, 2],
+        [1, 3, 7, 5],
+    ];
+    let uvs = (0..8)
+        .map(|k| ((k & 1) as f64, (k >> 1 & 1) as f64))
+        .collect();
+    let cube = SubdivisionSurface::new(
+        SubdivisionScheme::CatmullClark,
+        corners,
+        quads.iter().map(|q| q.to_vec()).collect(),
+    )
+    .with_uvs(uvs);
+    world.add(Arc::new(
+        cube.tessellate(
+            &target,
+            Arc::new(Lambertian::new_by_tex(earth_texture.clone())),
+        )
+        .into_bvh(),
+    ));
+    // an octahedron smoothed by Loop
+    let octahedron = SubdivisionSurface::new(
+        SubdivisionScheme::Loop,
+        vec![
+            Vec3::new(1.0, 1.2, 0.0),
+            Vec3::new(-1.0, 1.2, 0.0),
+            Vec3::new(0.0, 2.4, 0.0),
+            Vec3::new(0.0, 0.0, 0.0),
+            Vec3::new(0.0, 1.2, 1.0),
+            Vec3::new(0.0, 1.2, -1.0),
+        ],
+        vec![
+            vec![0, 2, 4],
+            vec![4, 2, 1],
+            vec![1, 2, 5],
+            vec![5, 2, 0],
+            vec![4, 3, 0],
+            vec![1, 3, 4],
+            vec![5, 3, 1],
+            vec![0, 3, 5],
+        ],
+    );
+    world.add(Arc::new(
+        octahedron
+            .tessellate(&target, Arc::new(Dielectric::new(1.5)))
+            .into_bvh(),
+    ));
+    // a wavy sheet of four Bézier patches, each with the whole earth map over its uvs
+    let patches: Vec<BezierPatch> = (0..4)
+        .map(|n| {
+            let (x0, z0) = (2.5 + 1.5 * (n % 2) as f64, -1.5 + 1.5 * (n / 2) as f64);
+            let mut points = [Vec3::zero(); 16];
+            for (k, point) in points.iter_mut().enumerate() {
+                let (x, z) = (x0 + 0.5 * (k % 4) as f64, z0 + 0.5 * (k / 4) as f64);
+                *point = Vec3::new(x, 1.0 + 0.6 * (2.0 * x).sin() * (1.5 * z).cos(), z);
+            }
+            BezierPatch::new(points)
+        })
+        .collect();
+    world.add(Arc::new(
+        bezier::tessellate(
+            &patches,
+            &target,
+            Arc::new(Lambertian::new_by_tex(earth_texture)),
+        )
+        .into_bvh(),
+    ));
+    let world = HitList::new_by_arc(Arc::new(Bvh_Node::new_by_list(&world)));
+    camera.render(&world, &mut img);
+
+    println!("Ouput image as \"{}\"\n Author: {}", path, AUTHOR);
+    let output_image: image::DynamicImage = image::DynamicImage::ImageRgb8(img);
+    let mut output_file: File = File::create(path).unwrap();
+    match output_image.write_to(&mut output_file, image::ImageOutputFormat::Jpeg(quality)) {
+        Ok(_) => {}
+        Err(_) => println!("Outputting image fails."),
+    }
+}
+pub fn teapot(path: &str) {
+    let mut camera = Camera::init(600, 4.0 / 3.0);
+    let mut img: RgbImage = ImageBuffer::new(camera.width as u32, camera.height as u32);
+    let quality = 60;
+    camera.samples_per_pixel = 100;
+    camera.max_depth = 20;
+    camera.background = Vec3::new(0.7, 0.8, 1.0);
+    camera.vfov = 30.0;
+    // the patches of the Utah teapot stand on z = 0 with z up
+    camera.lookfrom = Vec3::new(6.0, -9.0, 5.0);
+    camera.lookat = Vec3::new(0.0, 0.0, 1.4);
+    camera.vup = Vec3::new(0.0, 0.0, 1.0);
+    camera.defocus_angle = 0.0;
+    let patches = match read_patches("input/teapot.bpt") {
+        Ok(patches) => patches,
+        Err(_) => {
+            println!("Error loading teapot patches!");
+            return;
+        }
+    };
+
+    let list = Vec::new();
+    let mut world = HitList::new(list);
+    world.add(Arc::new(Plane::new(
+        Vec3::zero(),
+        Vec3::new(0.0, 0.0, 1.0),
+        Arc::new(Lambertian::new(Vec3::new(0.5, 0.5, 0.5))),
+    )));
+    let mut glaze = Principled::new(Vec3::new(0.8, 0.2, 0.1));
+    glaze.clearcoat = Principled::scalar(1.0);
+    world.add(Arc::new(
+        bezier::tessellate(&patches, &EdgeTarget::new(&camera, 1.0), Arc::new(glaze)).into_bvh(),
+    ));
+    let world = HitList::new_by_arc(Arc::new(Bvh_Node::new_by_list(&world)));
+    camera.render(&world, &mut img);
+
+    println!("Ouput image as \"{}\"\n Author: {}", path, AUTHOR);
+    let output_image: image::DynamicImage = image::DynamicImage::ImageRgb8(img);
+    let mut output_file: File = File::create(path).unwrap();
+    match output_image.write_to(&mut output_file, image::ImageOutputFormat::Jpeg(quality)) {
+        Ok(_) => {}
+        Err(_) => println!("Outputting image fails."),
+    }
+}
+pub fn procedural_textures(path: &str) {
+    let mut camera = Camera::init(600, 16.0 / 9.0);
+    let mut img: RgbImage = ImageBuffer::new(camera.width as u32, camera.height as u32);
+    let quality = 60;
+    let list = Vec::new();
+    let mut world = HitList::new(list);
+    // cracked dry ground from the walls between Worley cells
+    let mut cells = Worley::new_seeded(WorleyFeature::F2MinusF1, 3);
+    cells.transform = Transform::new(Vec3::zero(), Quat::identity(), 0.6 * Vec3::ones());
+    let ground = ColorRamp::new(
+        Arc::new(cells),
+        vec![
+            (0.0, Vec3::new(0.1, 0.07, 0.05)),
+            (0.08, Vec3::new(0.55, 0.45, 0.3)),
+            (1.0, Vec3::new(0.7, 0.6, 0.45)),
+        ],
+    );
+    world.add(Arc::new(Sphere::new(
+        Vec3::new(0.0, -1000.0, 0.0),
+        1000.0,
+        Some(Arc::new(Lambertian::new_by_tex(Arc::new(ground)))),
+    )));
+
+    let mut clouds = Fbm::new(8);
+    clouds.gain = 0.6;
+    clouds.transform = Transform::new(Vec3::zero(), Quat::identity(), 0.5 * Vec3::ones());
+    let clouds = ColorRamp::new(
+        Arc::new(clouds),
+        vec![
+            (0.3, Vec3::new(0.15, 0.3, 0.8)),
+            (0.7, Vec3::new(1.0, 1.0, 1.0)),
+        ],
+    );
+    let mut ridges = RidgedMultifractal::new(6);
+    ridges.lacunarity = 2.2;
+    ridges.offset = 0.9;
+    let ridges = ColorRamp::new(
+        Arc::new(ridges),
+        vec![
+            (0.2, Vec3::new(0.1, 0.25, 0.1)),
+            (0.5, Vec3::new(0.45, 0.35, 0.25)),
+            (0.8, Vec3::new(0.95, 0.95, 0.95)),
+        ],
+    );
+    let mut spots = Worley::new(WorleyFeature::F1);
+    spots.jitter = 0.8;
+    spots.transform = Transform::new(Vec3::zero(), Quat::identity(), 0.3 * Vec3::ones());
+    let spots = ColorRamp::new(
+        Arc::new(spots),
+        vec![
+            (0.2, Vec3::new(0.1, 0.05, 0.0)),
+            (0.35, Vec3::new(0.9, 0.6, 0.1)),
+        ],
+    );
+    let bubbles = ColorRamp::new(
+        Arc::new(Worley::new(WorleyFeature::F2)),
+        vec![
+            (0.5, Vec3::new(0.05, 0.1, 0.3)),
+            (1.0, Vec3::new(0.6, 0.8, 0.9)),
+        ],
+    );
+    let mut rings = Wood::new(6.0);
+    rings.turbulence = 0.8;
+    // the trunk lies along x, leaning forward a little
+    rings.transform = Transform::new(
+        Vec3::new(-0.5, 1.0, 0.0),
+        Quat::from_axis_angle(Vec3::new(1.0, 0.0, 1.0), 90.0),
+        Vec3::ones(),
+    );
+    let wood = ColorRamp::new(
+        Arc::new(rings),
+        vec![
+            (0.0, Vec3::new(0.35, 0.18, 0.07)),
+            (0.7, Vec3::new(0.75, 0.5, 0.25)),
+            (1.0, Vec3::new(0.6, 0.35, 0.15)),
+        ],
+    );
+    let mut veins = Marble::new(Vec3::new(1.0, 1.0, 0.0), 4.0);
+    veins.turbulence = 6.0;
+    let marble = ColorRamp::new(
+        Arc::new(veins),
+        vec![
+            (0.0, Vec3::new(0.2, 0.3, 0.25)),
+            (0.6, Vec3::new(0.9, 0.92, 0.9)),
+        ],
+    );
+    let mut smoke = Turbulence::new(7);
+    smoke.transform = Transform::new(Vec3::zero(), Quat::identity(), 0.7 * Vec3::ones());
+    let textures: Vec<Arc<dyn Texture>> = vec![
+        Arc::new(clouds),
+        Arc::new(ridges),
+        Arc::new(spots),
+        Arc::new(bubbles),
+        Arc::new(wood),
+        Arc::new(marble),
+        Arc::new(smoke),
+    ];
+    for (k, texture) in textures.into_iter().enumerate() {
+        world.add(Arc::new(Sphere::new(
+            Vec3::new(-6.6 + 2.2 * k as f64, 1.0, 0.0),
+            1.0,
+            Some(Arc::new(Lambertian::new_by_tex(texture))),
+        )));
+    }
+
+    camera.samples_per_pixel = 64;
+    camera.max_depth = 10;
+    camera.vfov = 40.0;
+    camera.lookfrom = Vec3::new(0.0, 4.0, 14.0);
+    camera.lookat = Vec3::new(0.0, 1.0, 0.0);
+    camera.vup = Vec3::new(0.0, 1.0, 0.0);
+    camera.defocus_angle = 0.0;
+    camera.background = Vec3::new(0.7, 0.8, 1.0);
+    camera.render(&world, &mut img);
+
+    println!("Ouput image as \"{}\"\n Author: {}", path, AUTHOR);
+    let output_image: image::DynamicImage = image::DynamicImage::ImageRgb8(img);
+    let mut output_file: File = File::create(path).unwrap();
+    match output_image.write_to(&mut output_file, image::ImageOutputFormat::Jpeg(quality)) {
+        Ok(_) => {}
+        Err(_) => println!("Outputting image fails."),
+    }
+}
+pub fn noise_variants(path: &str) {
+    let mut camera = Camera::init(600, 16.0 / 9.0);
+    let mut img: RgbImage = ImageBuffer::new(camera.width as u32, camera.height as u32);
+    let quality = 60;
+    let list = Vec::new();
+    let mut world = HitList::new(list);
+    // the floor tiles every 4 units without seams, one period of the noise per tile
+    let mut tiles = Fbm::new(5);
+    tiles.noise = Perlin::new_seeded(3);
+    tiles.basis = NoiseBasis::Periodic([4, 4, 4]);
+    let floor = ColorRamp::new(
+        Arc::new(tiles),
+        vec![
+            (0.3, Vec3::new(0.2, 0.25, 0.15)),
+            (0.7, Vec3::new(0.7, 0.65, 0.5)),
+        ],
+    );
+    world.add(Arc::new(Quad::new(
+        Vec3::new(-12.0, 0.0, -8.0),
+        Vec3::new(24.0, 0.0, 0.0),
+        Vec3::new(0.0, 0.0, 16.0),
+        Arc::new(Lambertian::new_by_tex(Arc::new(floor))),
+    )));
+
+    // the same seed under each basis, from left to right
+    let bases = [
+        NoiseBasis::Classic,
+        NoiseBasis::Improved,
+        NoiseBasis::Simplex2,
+        NoiseBasis::Simplex3,
+        NoiseBasis::Simplex4(0.0),
+        NoiseBasis::Simplex4(0.5),
+    ];
+    for (k, basis) in bases.into_iter().enumerate() {
+        let mut fbm = Fbm::new(6);
+        fbm.noise = Perlin::new_seeded(1);
+        fbm.basis = basis;
+        fbm.transform = Transform::new(Vec3::zero(), Quat::identity(), 1.2 * Vec3::ones());
+        let texture = ColorRamp::new(
+            Arc::new(fbm),
+            vec![
+                (0.25, Vec3::new(0.1, 0.2, 0.6)),
+                (0.5, Vec3::new(0.9, 0.85, 0.7)),
+                (0.75, Vec3::new(0.6, 0.2, 0.1)),
+            ],
+        );
+        world.add(Arc::new(Sphere::new(
+            Vec3::new(-5.5 + 2.2 * k as f64, 1.0, 0.0),
+            1.0,
+            Some(Arc::new(Lambertian::new_by_tex(Arc::new(texture)))),
+        )));
+    }
+
+    camera.samples_per_pixel = 64;
+    camera.max_depth = 10;
+    camera.vfov = 40.0;
+    camera.lookfrom = Vec3::new(0.0, 5.0, 13.0);
+    camera.lookat = Vec3::new(0.0, 0.5, 0.0);
+    camera.vup = Vec3::new(0.0, 1.0, 0.0);
+    camera.defocus_angle = 0.0;
+    camera.background = Vec3::new(0.7, 0.8, 1.0);
+    camera.render(&world, &mut img);
+
+    println!("Ouput image as \"{}\"\n Author: {}", path, AUTHOR);
+    let output_image: image::DynamicImage = image::DynamicImage::ImageRgb8(img);
+    let mut output_file: File = File::create(path).unwrap();
+    match output_image.write_to(&mut output_file, image::ImageOutputFormat::Jpeg(quality)) {
+        Ok(_) => {}
+        Err(_) => println!("Outputting image fails."),
+    }
+}
+pub fn texture_filtering(path: &str) {
+    let mut camera = Camera::init(600, 16.0 / 9.0);
+    let mut img: RgbImage = ImageBuffer::new(camera.width as u32, camera.height as u32);
+    let quality = 60;
+    let list = Vec::new();
+    let mut world = HitList::new(list);
+    let earth_texture = match ImageTexture::new("input/earthmap.jpg") {
+        Ok(texture) => texture,
+        Err(_) => {
+            println!("Error creating texture!");
+            return;
+        }
+    };
+    let earth = |filter: TextureFilter, wrap: [WrapMode; 2]| -> Arc<dyn Material> {
+        let mut texture = earth_texture.clone();
+        texture.filter = filter;
+        texture.wrap = wrap;
+        Arc::new(Lambertian::new_by_tex(Arc::new(texture)))
+    };
+    // rectangle from `corner` along `a` and `b` with uvs from (0, 0) to `uv_max`,
+    // offset by `uv_min`
+    let panel = |corner: Vec3, a: Vec3, b: Vec3, uv_min: (f64, f64), uv_max: (f64, f64), mat| {
+        let positions = vec![corner, corner + a, corner + a + b, corner + b];
+        let uvs = vec![uv_min, (uv_max.0, uv_min.1), uv_max, (uv_min.0, uv_max.1)];
+        TriangleMesh::new(positions, vec![[0, 1, 2], [0, 2, 3]], mat)
+            .with_uvs(uvs)
+            .into_bvh()
+    };
+    // the earth tiled over the floor up to the horizon: nearest lookups alias into
+    // noise in the distance, trilinear blurs it and EWA keeps it sharp across the view
+    let filters = [
+        TextureFilter::Nearest,
+        TextureFilter::Trilinear,
+        TextureFilter::Ewa,
+    ];
+    for (k, filter) in filters.into_iter().enumerate() {
+        world.add(Arc::new(panel(
+            Vec3::new(-15.0 + 10.0 * k as f64, 0.0, 8.0),
+            Vec3::new(10.0, 0.0, 0.0),
+            Vec3::new(0.0, 0.0, -400.0),
+            (0.0, 0.0),
+            (1.0, 80.0),
+            earth(filter, [WrapMode::Repeat; 2]),
+        )));
+    }
+    // magnified: blocky, smooth and sharper but smooth
+    let close = [
+        TextureFilter::Nearest,
+        TextureFilter::Bilinear,
+        TextureFilter::Bicubic,
+    ];
+    for (k, filter) in close.into_iter().enumerate() {
+        world.add(Arc::new(Sphere::new(
+            Vec3::new(-3.0 + 3.0 * k as f64, 1.2, 3.0),
+            1.2,
+            Some(earth(filter, [WrapMode::Repeat, WrapMode::Clamp])),
+        )));
+    }
+    // a billboard showing the map three times across: mirrored along u, the edge
+    // texels smeared out along v
+    world.add(Arc::new(panel(
+        Vec3::new(-9.0, 3.0, -12.0),
+        Vec3::new(18.0, 0.0, 0.0),
+        Vec3::new(0.0, 6.0, 0.0),
+        (-1.0, -0.5),
+        (2.0, 1.5),
+        earth(TextureFilter::Ewa, [WrapMode::Mirror, WrapMode::Clamp]),
+    )));
+
+    camera.samples_per_pixel = 64;
+    camera.max_depth = 10;
+    camera.vfov = 50.0;
+    camera.lookfrom = Vec3::new(0.0, 4.0, 12.0);
+    camera.lookat = Vec3::new(0.0, 3.0, 0.0);
+    camera.vup = Vec3::new(0.0, 1.0, 0.0);
+    camera.defocus_angle = 0.0;
+    camera.background = Vec3::new(0.7, 0.8, 1.0);
+    camera.render(&world, &mut img);
+
+    println!("Ouput image as \"{}\"\n Author: {}", path, AUTHOR);
+    let output_image: image::DynamicImage = image::DynamicImage::ImageRgb8(img);
+    let mut output_file: File = File::create(path).unwrap();
+    match output_image.write_to(&mut output_file, image::ImageOutputFormat::Jpeg(quality)) {
+        Ok(_) => {}
+        Err(_) => println!("Outputting image fails."),
+    }
+}
+pub fn color_management(path: &str) {
+    let mut camera = Camera::init(600, 16.0 / 9.0);
+    let mut img: RgbImage = ImageBuffer::new(camera.width as u32, camera.height as u32);
+    let quality = 60;
+    let list = Vec::new();
+    let mut world = HitList::new(list);
+    world.add(Arc::new(Plane::new(
+        Vec3::zero(),
+        Vec3::new(0.0, 1.0, 0.0),
+        Arc::new(Lambertian::new(Vec3::new(0.5, 0.5, 0.5))),
+    )));
+    // the earth decoded from sRGB, and read as if it were linear: too bright and pale
+    for (k, space) in [ColorSpace::Srgb, ColorSpace::Linear]
+        .into_iter()
+        .enumerate()
+    {
+        let earth = match ImageTexture::new_by_color_space("input/earthmap.jpg", space) {
+            Ok(texture) => texture,
+            Err(_) => {
+                println!("Error creating texture!");
+                return;
+            }
+        };
+        world.add(Arc::new(Sphere::new(
+            Vec3::new(-4.5 + 2.2 * k as f64, 1.0, 0.0),
+            1.0,
+            Some(Arc::new(Lambertian::new_by_tex(Arc::new(earth)))),
+        )));
+    }
+    // the full range of the HDR sky glowing on a ball
+    if let Ok(sky) = ImageTexture::new("input/environment.hdr") {
+        world.add(Arc::new(Sphere::new(
+            Vec3::new(0.8, 1.0, 0.0),
+            1.0,
+            Some(Arc::new(DiffuseLight::new(Arc::new(sky)))),
+        )));
+    }
+    // a card cut out along the aperture shape, the gray levels turned into alpha
+    let shape = match image::open("input/aperture.png") {
+        Ok(image) => image.to_luma8(),
+        Err(_) => {
+            println!("Error loading aperture image!");
+            return;
+        }
+    };
+    let coverage = ImageBuffer::from_fn(shape.width(), shape.height(), |x, y| {
+        image::LumaA([255u8, shape.get_pixel(x, y)[0]])
+    });
+    let cutout = ImageTexture::from_image(
+        &image::DynamicImage::ImageLumaA8(coverage),
+        ColorSpace::Linear,
+    );
+    world.add(Arc::new(Quad::new(
+        Vec3::new(2.5, 0.0, -0.5),
+        Vec3::new(2.5, 0.0, 0.5),
+        Vec3::new(0.0, 2.5, 0.0),
+        Arc::new(AlphaMask::new(
+            Arc::new(Lambertian::new(Vec3::new(0.8, 0.3, 0.1))),
+            Arc::new(cutout),
+        )),
+    )));
+
+    camera.samples_per_pixel = 64;
+    camera.max_depth = 10;
+    camera.vfov = 40.0;
+    camera.lookfrom = Vec3::new(0.0, 3.0, 10.0);
+    camera.lookat = Vec3::new(0.0, 1.0, 0.0);
+    camera.vup = Vec3::new(0.0, 1.0, 0.0);
+    camera.defocus_angle = 0.0;
+    camera.background = Vec3::new(0.7, 0.8, 1.0);
+    camera.render(&world, &mut img);
+
+    println!("Ouput image as \"{}\"\n Author: {}", path, AUTHOR);
+    let output_image: image::DynamicImage = image::DynamicImage::ImageRgb8(img);
+    let mut output_file: File = File::create(path).unwrap();
+    match output_image.write_to(&mut output_file, image::ImageOutputFormat::Jpeg(quality)) {
+        Ok(_) => {}
+        Err(_) => println!("Outputting image fails."),
+    }
+}
+pub fn microfacet_spheres(path: &str) {
+    let mut camera = Camera::init(400, 16.0 / 9.0);
+    let mut img: RgbImage = ImageBuffer::new(camera.width as u32, camera.height as u32);
+    let quality = 60;
+    let list = Vec::new();
+    let mut world = HitList::new(list);
+    let checker = Arc::new(CheckerTexture::new_by_color(
+        0.5,
+        Vec3::new(0.2, 0.2, 0.2),
+        Vec3::new(0.8, 0.8, 0.8),
+    ));
+    world.add(Arc::new(Sphere::new(
+        Vec3::new(0.0, -1000.0, 0.0),
+        1000.0,
+        Some(Arc::new(Lambertian::new_by_tex(checker))),
+    )));
+    world.add(Arc::new(Sphere::new(
+        Vec3::new(-3.3, 1.0, 0.0),
+        1.0,
+        Some(Arc::new(Conductor::gold(0.3))),
+    )));
+    world.add(Arc::new(Sphere::new(
+        Vec3::new(-1.1, 1.0, 0.0),
+        1.0,
+        Some(Arc::new(Conductor::copper(0.15))),
+    )));
+    world.add(Arc::new(Sphere::new(
+        Vec3::new(1.1, 1.0, 0.0),
+        1.0,
+        Some(Arc::new(Conductor::aluminium(0.5))),
+    )));
+    world.add(Arc::new(Sphere::new(
+        Vec3::new(3.3, 1.0, 0.0),
+        1.0,
+        Some(Arc::new(RoughDielectric::new(1.5, 0.25))),
+    )));
+    camera.samples_per_pixel = 100;
+    camera.max_depth = 50;
+    camera.vfov = 30.0;
+    camera.lookfrom = Vec3::new(0.0, 3.0, 12.0);
+    camera.lookat = Vec3::new(0.0, 1.0, 0.0);
+    camera.vup = Vec3::new(0.0, 1.0, 0.0);
+    camera.defocus_angle = 0.0;
+    camera.background = Vec3::new(0.7, 0.8, 1.0);
+    camera.render(&world, &mut img);
+
+    println!("Ouput image as \"{}\"\n Author: {}", path, AUTHOR);
+    let output_image: image::DynamicImage = image::DynamicImage::ImageRgb8(img);
+    let mut output_file: File = File::create(path).unwrap();
+    match output_image.write_to(&mut output_file, image::ImageOutputFormat::Jpeg(quality)) {
+        Ok(_) => {}
+        Err(_) => println!("Outputting image fails."),
+    }
+}
+pub fn principled_spheres(path: &str) {
+    let mut camera = Camera::init(400, 16.0 / 9.0);
+    let mut img: RgbImage = ImageBuffer::new(camera.width as u32, camera.height as u32);
+    let quality = 60;
+    let list = Vec::new();
+    let mut world = HitList::new(list);
+    world.add(Arc::new(Sphere::new(
+        Vec3::new(0.0, -1000.0, 0.0),
+        1000.0,
+        Some(Arc::new(Principled::new(Vec3::new(0.5, 0.5, 0.5)))),
+    )));
+    let mut plastic = Principled::new(Vec3::new(0.8, 0.1, 0.1));
+    plastic.roughness = Principled::scalar(0.3);
+    plastic.clearcoat = Principled::scalar(1.0);
+    let mut brushed = Principled::new(Vec3::new(0.9, 0.7, 0.4));
+    brushed.metallic = Principled::scalar(1.0);
+    brushed.roughness = Principled::scalar(0.4);
+    let mut velvet = Principled::new_by_tex(Arc::new(NoiseTexture::new_by_scale(4.0)));
+    velvet.roughness = Principled::scalar(0.9);
+    velvet.sheen = Principled::scalar(1.0);
+    velvet.subsurface = Principled::scalar(0.5);
+    let mut glass = Principled::new(Vec3::new(0.9, 1.0, 0.9));
+    glass.roughness = Principled::scalar(0.1);
+    glass.transmission = Principled::scalar(1.0);
+    let materials = [plastic, brushed, velvet, glass];
+    for (i, mat) in materials.into_iter().enumerate() {
+        world.add(Arc::new(Sphere::new(
+            Vec3::new(-3.3 + 2.2 * i as f64, 1.0, 0.0),
+            1.0,
+            Some(Arc::new(mat)),
+        )));
+    }
+    camera.samples_per_pixel = 100;
+    camera.max_depth = 50;
+    camera.vfov = 30.0;
+    camera.lookfrom = Vec3::new(0.0, 3.0, 12.0);
+    camera.lookat = Vec3::new(0.0, 1.0, 0.0);
+    camera.vup = Vec3::new(0.0, 1.0, 0.0);
+    camera.defocus_angle = 0.0;
+    camera.background = Vec3::new(0.7, 0.8, 1.0);
+    camera.render(&world, &mut img);
+
+    println!("Ouput image as \"{}\"\n Author: {}", path, AUTHOR);
+    let output_image: image::DynamicImage = image::DynamicImage::ImageRgb8(img);
+    let mut output_file: File = File::create(path).unwrap();
+    match output_image.write_to(&mut output_file, image::ImageOutputFormat::Jpeg(quality)) {
+        Ok(_) => {}
+        Err(_) => println!("Outputting image fails."),
+    }
+}
+pub fn bumpy_spheres(path: &str) {
+    let mut camera = Camera::init(400, 16.0 / 9.0);
+    let mut img: RgbImage = ImageBuffer::new(camera.width as u32, camera.height as u32);
+    let quality = 60;
+    // heights are data, not colors
+    let height_map: Arc<dyn Texture> =
+        match ImageTexture::new_by_color_space("input/hmap.jpg", ColorSpace::Linear) {
+            Ok(texture) => Arc::new(texture),
+            Err(_) => {
+                println!("Error creating texture!");
+                return;
+            }
+        };
+    let list = Vec::new();
+    let mut world = HitList::new(list);
+    world.add(Arc::new(Sphere::new(
+        Vec3::new(0.0, -1000.0, 0.0),
+        1000.0,
+        Some(Arc::new(Lambertian::new(Vec3::new(0.5, 0.5, 0.5)))),
+    )));
+    let bumped = BumpMap::new(
+        Arc::new(Lambertian::new(Vec3::new(0.8, 0.6, 0.4))),
+        height_map,
+        0.5,
+    );
+    world.add(Arc::new(Sphere::new(
+        Vec3::new(-2.2, 1.0, 0.0),
+        1.0,
+        Some(Arc::new(bumped)),
+    )));
+    let roughness = Arc::new(NoiseTexture::new_seeded(2.0, 5));
+    world.add(Arc::new(Sphere::new(
+        Vec3::new(0.0, 1.0, 0.0),
+        1.0,
+        Some(Arc::new(Metal::new_by_tex(
+            Vec3::new(0.8, 0.8, 0.8),
+            roughness,
+        ))),
+    )));
+    // alternating tilted normals give a faceted look
+    let facets = Arc::new(CheckerTexture::new_by_color(
+        0.15,
+        Vec3::new(0.7, 0.5, 0.9),
+        Vec3::new(0.3, 0.5, 0.9),
+    ));
+    world.add(Arc::new(Sphere::new(
+        Vec3::new(2.2, 1.0, 0.0),
+        1.0,
+        Some(Arc::new(NormalMap::new(
+            Arc::new(Conductor::gold(0.1)),
+            facets,
+        ))),
+    )));
+    camera.samples_per_pixel = 100;
+    camera.max_depth = 50;
+    camera.vfov = 30.0;
+    camera.lookfrom = Vec3::new(0.0, 3.0, 10.0);
+    camera.lookat = Vec3::new(0.0, 1.0, 0.0);
+    camera.vup = Vec3::new(0.0, 1.0, 0.0);
+    camera.defocus_angle = 0.0;
+    camera.background = Vec3::new(0.7, 0.8, 1.0);
+    camera.render(&world, &mut img);
+
+    println!("Ouput image as \"{}\"\n Author: {}", path, AUTHOR);
+    let output_image: image::DynamicImage = image::DynamicImage::ImageRgb8(img);
+    let mut output_file: File = File::create(path).unwrap();
+    match output_image.write_to(&mut output_file, image::ImageOutputFormat::Jpeg(quality)) {
+        Ok(_) => {}
+        Err(_) => println!("Outputting image fails."),
+    }
+}
+pub fn light_rig(path: &str) {
+    let mut camera = Camera::init(400, 16.0 / 9.0);
+    let mut img: RgbImage = ImageBuffer::new(camera.width as u32, camera.height as u32);
+    let quality = 60;
+    let list = Vec::new();
+    let mut world = HitList::new(list);
+    world.add(Arc::new(Sphere::new(
+        Vec3::new(0.0, -1000.0, 0.0),
+        1000.0,
+        Some(Arc::new(Lambertian::new(Vec3::new(0.5, 0.5, 0.5)))),
+    )));
+    let mut subject = Principled::new(Vec3::new(0.2, 0.4, 0.8));
+    subject.roughness = Principled::scalar(0.35);
+    world.add(Arc::new(Sphere::new(
+        Vec3::new(0.0, 1.0, 0.0),
+        1.0,
+        Some(Arc::new(subject)),
+    )));
+    world.add(gen_box(
+        Vec3::new(1.5, 0.0, -1.5),
+        Vec3::new(2.5, 0.7, -0.5),
+        Arc::new(Lambertian::new(Vec3::new(0.8, 0.3, 0.2))),
+    ));
+    world.add(Arc::new(Sphere::new(
+        Vec3::new(-2.0, 0.6, -1.0),
+        0.6,
+        Some(Arc::new(Metal::new(Vec3::new(0.8, 0.8, 0.85), 0.3))),
+    )));
+    // key, fill, rim and a hard back light
+    camera.lights.push(Arc::new(SpotLight::new_by_power(
+        Vec3::new(4.0, 6.0, 4.0),
+        Vec3::new(0.0, 1.0, 0.0),
+        Vec3::new(150.0, 140.0, 120.0),
+        15.0,
+        25.0,
+    )));
+    camera.lights.push(Arc::new(PointLight::new_by_power(
+        Vec3::new(-5.0, 3.0, 3.0),
+        Vec3::new(60.0, 70.0, 90.0),
+    )));
+    camera
+        .lights
+        .push(Arc::new(DirectionalLight::new_with_radius(
+            Vec3::new(0.3, -0.5, 1.0),
+            Vec3::new(1.5, 1.5, 1.5),
+            2.0,
+        )));
+    camera.lights.push(Arc::new(DirectionalLight::new(
+        Vec3::new(-0.2, -0.4, 1.0),
+        Vec3::new(0.4, 0.45, 0.6),
+    )));
+    camera.samples_per_pixel = 50;
+    camera.max_depth = 20;
+    camera.vfov = 30.0;
+    camera.lookfrom = Vec3::new(0.0, 3.0, 10.0);
+    camera.lookat = Vec3::new(0.0, 1.0, 0.0);
+    camera.vup = Vec3::new(0.0, 1.0, 0.0);
+    camera.defocus_angle = 0.0;
+    camera.background = Vec3::new(0.02, 0.02, 0.03);
+    camera.render(&world, &mut img);
+
+    println!("Ouput image as \"{}\"\n Author: {}", path, AUTHOR);
+    let output_image: image::DynamicImage = image::DynamicImage::ImageRgb8(img);
+    let mut output_file: File = File::create(path).unwrap();
+    match output_image.write_to(&mut output_file, image::ImageOutputFormat::Jpeg(quality)) {
+        Ok(_) => {}
+        Err(_) => println!("Outputting image fails."),
+    }
+}
+pub fn environment_spheres(path: &str) {
+    let mut camera = Camera::init(400, 16.0 / 9.0);
+    let mut img: RgbImage = ImageBuffer::new(camera.width as u32, camera.height as u32);
+    let quality = 60;
+    let mut environment = match EnvironmentLight::new("input/environment.hdr") {
+        Ok(environment) => environment,
+        Err(_) => {
+            println!("Error loading environment map!");
+            return;
+        }
+    };
+    environment.rotation = 90.0;
+    environment.intensity = 0.6;
+    let list = Vec::new();
+    let mut world = HitList::new(list);
+    world.add(Arc::new(Sphere::new(
+        Vec3::new(0.0, -1000.0, 0.0),
+        1000.0,
+        Some(Arc::new(Lambertian::new(Vec3::new(0.5, 0.5, 0.5)))),
+    )));
+    let mut plastic = Principled::new(Vec3::new(0.8, 0.1, 0.1));
+    plastic.roughness = Principled::scalar(0.2);
+    plastic.clearcoat = Principled::scalar(1.0);
+    world.add(Arc::new(Sphere::new(
+        Vec3::new(-2.2, 1.0, 0.0),
+        1.0,
+        Some(Arc::new(plastic)),
+    )));
+    world.add(Arc::new(Sphere::new(
+        Vec3::new(0.0, 1.0, 0.0),
+        1.0,
+        Some(Arc::new(Conductor::gold(0.2))),
+    )));
+    world.add(Arc::new(Sphere::new(
+        Vec3::new(2.2, 1.0, 0.0),
+        1.0,
+        Some(Arc::new(Lambertian::new(Vec3::new(0.8, 0.8, 0.8)))),
+    )));
+    camera.samples_per_pixel = 64;
+    camera.max_depth = 20;
+    camera.vfov = 30.0;
+    camera.lookfrom = Vec3::new(0.0, 3.0, 10.0);
+    camera.lookat = Vec3::new(0.0, 1.0, 0.0);
+    camera.vup = Vec3::new(0.0, 1.0, 0.0);
+    camera.defocus_angle = 0.0;
+    camera.environment = Some(Arc::new(environment));
+    camera.render(&world, &mut img);
+
+    println!("Ouput image as \"{}\"\n Author: {}", path, AUTHOR);
+    let output_image: image::DynamicImage = image::DynamicImage::ImageRgb8(img);
+    let mut output_file: File = File::create(path).unwrap();
+    match output_image.write_to(&mut output_file, image::ImageOutputFormat::Jpeg(quality)) {
+        Ok(_) => {}
+        Err(_) => println!("Outputting image fails."),
+    }
+}
+pub fn daylight_spheres(path: &str) {
+    let mut camera = Camera::init(400, 16.0 / 9.0);
+    let mut img: RgbImage = ImageBuffer::new(camera.width as u32, camera.height as u32);
+    let quality = 60;
+    let sky = Sky::new_by_angles(35.0, 60.0, 3.0, Vec3::new(0.3, 0.3, 0.3));
+    let list = Vec::new();
+    let mut world = HitList::new(list);
+    let checker = Arc::new(CheckerTexture::new_by_color(
+        0.32,
+        Vec3::new(0.2, 0.3, 0.1),
+        Vec3::new(0.9, 0.9, 0.9),
+    ));
+    world.add(Arc::new(Sphere::new(
+        Vec3::new(0.0, -1000.0, 0.0),
+        1000.0,
+        Some(Arc::new(Lambertian::new_by_tex(checker))),
+    )));
+    world.add(Arc::new(Sphere::new(
+        Vec3::new(0.0, 1.0, 0.0),
+        1.0,
+        Some(Arc::new(Dielectric::new(1.5))),
+    )));
+    world.add(Arc::new(Sphere::new(
+        Vec3::new(-4.0, 1.0, 0.0),
+        1.0,
+        Some(Arc::new(Lambertian::new(Vec3::new(0.4, 0.2, 0.1)))),
+    )));
+    world.add(Arc::new(Sphere::new(
+        Vec3::new(4.0, 1.0, 0.0),
+        1.0,
+        Some(Arc::new(Metal::new(Vec3::new(0.7, 0.6, 0.5), 0.0))),
+    )));
+    camera.samples_per_pixel = 64;
+    camera.max_depth = 20;
+    camera.vfov = 20.0;
+    camera.lookfrom = Vec3::new(13.0, 2.0, 3.0);
+    camera.lookat = Vec3::new(0.0, 0.0, 0.0);
+    camera.vup = Vec3::new(0.0, 1.0, 0.0);
+    camera.defocus_angle = 0.0;
+    camera.environment = Some(Arc::new(sky));
+    camera.render(&world, &mut img);
+
+    println!("Ouput image as \"{}\"\n Author: {}", path, AUTHOR);
+    let output_image: image::DynamicImage = image::DynamicImage::ImageRgb8(img);
+    let mut output_file: File = File::create(path).unwrap();
+    match output_image.write_to(&mut output_file, image::ImageOutputFormat::Jpeg(quality)) {
+        Ok(_) => {}
+        Err(_) => println!("Outputting image fails."),
+    }
+}
+pub fn dispersion_spheres(path: &str) {
+    let mut camera = Camera::init(400, 16.0 / 9.0);
+    let mut img: RgbImage = ImageBuffer::new(camera.width as u32, camera.height as u32);
+    let quality = 60;
+    let list = Vec::new();
+    let mut world = HitList::new(list);
+    let checker = Arc::new(CheckerTexture::new_by_color(
+        0.5,
+        Vec3::new(0.05, 0.05, 0.05),
+        Vec3::new(0.9, 0.9, 0.9),
+    ));
+    world.add(Arc::new(Sphere::new(
+        Vec3::new(0.0, -1000.0, 0.0),
+        1000.0,
+        Some(Arc::new(Lambertian::new_by_tex(checker))),
+    )));
+    world.add(Arc::new(Sphere::new(
+        Vec3::new(-2.2, 1.0, 0.0),
+        1.0,
+        Some(Arc::new(Dielectric::bk7())),
+    )));
+    world.add(Arc::new(Sphere::new(
+        Vec3::new(0.0, 1.0, 0.0),
+        1.0,
+        Some(Arc::new(Dielectric::diamond())),
+    )));
+    // a heavy flint, exaggerated to make the spread obvious
+    world.add(Arc::new(Sphere::new(
+        Vec3::new(2.2, 1.0, 0.0),
+        1.0,
+        Some(Arc::new(Dielectric::new_by_cauchy(1.6, 0.05))),
+    )));
+    world.add(Arc::new(Sphere::new(
+        Vec3::new(-4.0, 12.0, -6.0),
+        3.0,
+        Some(Arc::new(DiffuseLight::new_by_color(Vec3::new(
+            8.0, 8.0, 8.0,
+        )))),
+    )));
+    camera.spectral = true;
+    camera.samples_per_pixel = 200;
+    camera.max_depth = 20;
+    camera.vfov = 30.0;
+    camera.lookfrom = Vec3::new(0.0, 3.0, 10.0);
+    camera.lookat = Vec3::new(0.0, 1.0, 0.0);
+    camera.vup = Vec3::new(0.0, 1.0, 0.0);
+    camera.defocus_angle = 0.0;
+    camera.background = Vec3::new(0.05, 0.06, 0.08);
+    camera.render(&world, &mut img);
+
+    println!("Ouput image as \"{}\"\n Author: {}", path, AUTHOR);
+    let output_image: image::DynamicImage = image::DynamicImage::ImageRgb8(img);
+    let mut output_file: File = File::create(path).unwrap();
+    match output_image.write_to(&mut output_file, image::ImageOutputFormat::Jpeg(quality)) {
+        Ok(_) => {}
+        Err(_) => println!("Outputting image fails."),
+    }
+}
+pub fn colored_glass(path: &str) {
+    let mut camera = Camera::init(400, 16.0 / 9.0);
+    let mut img: RgbImage = ImageBuffer::new(camera.width as u32, camera.height as u32);
+    let quality = 60;
+    let sky = Sky::new_by_angles(40.0, -30.0, 3.0, Vec3::new(0.3, 0.3, 0.3));
+    let list = Vec::new();
+    let mut world = HitList::new(list);
+    let checker = Arc::new(CheckerTexture::new_by_color(
+        0.5,
+        Vec3::new(0.3, 0.3, 0.3),
+        Vec3::new(0.9, 0.9, 0.9),
+    ));
+    world.add(Arc::new(Sphere::new(
+        Vec3::new(0.0, -1000.0, 0.0),
+        1000.0,
+        Some(Arc::new(Lambertian::new_by_tex(checker))),
+    )));
+    // bottle green glass
+    world.add(Arc::new(Sphere::new(
+        Vec3::new(-2.4, 1.0, 0.0),
+        1.0,
+        Some(Arc::new(Dielectric::new_by_absorption(
+            1.5,
+            Vec3::new(0.4, 0.8, 0.5),
+            1.0,
+        ))),
+    )));
+    // glass ball filled with wine, the liquid wins where the two overlap
+    let mut glass = Dielectric::new(1.5);
+    glass.priority = 1;
+    let mut wine = Dielectric::new_by_absorption(1.33, Vec3::new(0.6, 0.05, 0.1), 0.5);
+    wine.priority = 2;
+    world.add(Arc::new(Sphere::new(
+        Vec3::new(0.0, 1.0, 0.0),
+        1.0,
+        Some(Arc::new(glass)),
+    )));
+    world.add(Arc::new(Sphere::new(
+        Vec3::new(0.0, 1.0, 0.0),
+        0.85,
+        Some(Arc::new(wine)),
+    )));
+    // marble half sunk in a pool of water, the glass carves its own hole
+    let mut water = Dielectric::new_by_absorption(1.33, Vec3::new(0.7, 0.9, 0.95), 1.0);
+    water.priority = 1;
+    let mut marble = Dielectric::new(1.5);
+    marble.priority = 2;
+    world.add(gen_box(
+        Vec3::new(1.3, 0.0, -1.0),
+        Vec3::new(3.5, 0.6, 1.0),
+        Arc::new(water),
+    ));
+    world.add(Arc::new(Sphere::new(
+        Vec3::new(2.4, 0.55, 0.0),
+        0.6,
+        Some(Arc::new(marble)),
+    )));
+    camera.samples_per_pixel = 64;
+    camera.max_depth = 30;
+    camera.vfov = 30.0;
+    camera.lookfrom = Vec3::new(0.0, 3.0, 10.0);
+    camera.lookat = Vec3::new(0.0, 0.8, 0.0);
+    camera.vup = Vec3::new(0.0, 1.0, 0.0);
+    camera.defocus_angle = 0.0;
+    camera.environment = Some(Arc::new(sky));
+    camera.render(&world, &mut img);
+
+    println!("Ouput image as \"{}\"\n Author: {}", path, AUTHOR);
+    let output_image: image::DynamicImage = image::DynamicImage::ImageRgb8(img);
+    let mut output_file: File = File::create(path).unwrap();
+    match output_image.write_to(&mut output_file, image::ImageOutputFormat::Jpeg(quality)) {
+        Ok(_) => {}
+        Err(_) => println!("Outputting image fails."),
+    }
+}
+/// ring of spheres under the daylight sky, rendered with `projection`
+fn projection_scene(
+    path: &str,
+    projection: Projection,
+    width: usize,
+    ratio: f64,
+    lookfrom: Vec3,
+    lookat: Vec3,
+) {
+    let mut camera = Camera::init(width, ratio);
+    let mut img: RgbImage = ImageBuffer::new(camera.width as u32, camera.height as u32);
+    let quality = 60;
+    let sky = Sky::new_by_angles(35.0, 60.0, 3.0, Vec3::new(0.3, 0.3, 0.3));
+    let list = Vec::new();
+    let mut world = HitList::new(list);
+    let checker = Arc::new(CheckerTexture::new_by_color(
+        0.5,
+        Vec3::new(0.2, 0.3, 0.1),
+        Vec3::new(0.9, 0.9, 0.9),
+    ));
+    world.add(Arc::new(Sphere::new(
+        Vec3::new(0.0, -1000.0, 0.0),
+        1000.0,
+        Some(Arc::new(Lambertian::new_by_tex(checker))),
+    )));
+    for k in 0..8 {
+        let angle = k as f64 * std::f64::consts::PI / 4.0;
+        let center = Vec3::new(4.0 * angle.sin(), 1.0, -4.0 * angle.cos());
+        let albedo = Vec3::new(
+            0.5 + 0.4 * angle.cos(),
+            0.5 + 0.4 * (angle + 2.0).cos(),
+            0.5 + 0.4 * (angle + 4.0).cos(),
+        );
+        let material: Arc<dyn Material> = if k % 2 == 0 {
+            Arc::new(Lambertian::new(albedo))
+        } else {
+            Arc::new(Metal::new(albedo, 0.1))
+        };
+        world.add(Arc::new(Sphere::new(center, 1.0, Some(material))));
+    }
+    camera.samples_per_pixel = 32;
+    camera.max_depth = 20;
+    camera.vfov = 60.0;
+    camera.lookfrom = lookfrom;
+    camera.lookat = lookat;
+    camera.vup = Vec3::new(0.0, 1.0, 0.0);
+    camera.defocus_angle = 0.0;
+    camera.projection = projection;
+    camera.environment = Some(Arc::new(sky));
+    camera.render(&world, &mut img);
+
+    println!("Ouput image as \"{}\"\n Author: {}", path, AUTHOR);
+    let output_image: image::DynamicImage = image::DynamicImage::ImageRgb8(img);
+    let mut output_file: File = File::create(path).unwrap();
+    match output_image.write_to(&mut output_file, image::ImageOutputFormat::Jpeg(quality)) {
+        Ok(_) => {}
+        Err(_) => println!("Outputting image fails."),
+    }
+}
+
+// the panoramic cameras stand in the middle of the ring
+
+pub fn panorama_spheres(path: &str) {
+    let eye = Vec3::new(0.0, 1.5, 0.0);
+    let ahead = Vec3::new(0.0, 1.5, -1.0);
+    projection_scene(path, Projection::Equirectangular, 800, 2.0, eye, ahead);
+}
+
+pub fn fisheye_spheres(path: &str) {
+    let eye = Vec3::new(0.0, 1.5, 0.0);
+    let ahead = Vec3::new(0.0, 1.2, -1.0);
+    projection_scene(
+        path,
+        Projection::Fisheye { fov: 200.0 },
+        400,
+        1.0,
+        eye,
+        ahead,
+    );
+}
+
+pub fn cylindrical_spheres(path: &str) {
+    let eye = Vec3::new(0.0, 1.5, 0.0);
+    let ahead = Vec3::new(0.0, 1.5, -1.0);
+    let projection = Projection::Cylindrical { hfov: 360.0 };
+    projection_scene(path, projection, 900, 3.0, eye, ahead);
+}
+
+pub fn ortho_spheres(path: &str) {
+    let eye = Vec3::new(0.0, 8.0, 12.0);
+    let target = Vec3::new(0.0, 1.0, 0.0);
+    let projection = Projection::Orthographic { height: 9.0 };
+    projection_scene(path, projection, 400, 16.0 / 9.0, eye, target);
+}
+/// subject in focus in front of out of focus lights, shot with an 85mm f/1.4 lens
+fn bokeh_scene(path: &str, aperture: Aperture, cats_eye: f64) {
+    let mut camera = Camera::init(400, 16.0 / 9.0);
+    let mut img: RgbImage = ImageBuffer::new(camera.width as u32, camera.height as u32);
+    let quality = 60;
+    let list = Vec::new();
+    let mut world = HitList::new(list);
+    world.add(Arc::new(Sphere::new(
+        Vec3::new(0.0, -1000.0, 0.0),
+        1000.0,
+        Some(Arc::new(Lambertian::new(Vec3::new(0.3, 0.3, 0.3)))),
+    )));
+    world.add(Arc::new(Sphere::new(
+        Vec3::new(0.0, 0.3, 0.0),
+        0.3,
+        Some(Arc::new(Conductor::gold(0.2))),
+    )));
+    for i in 0..12 {
+        for j in 0..4 {
+            let color = Vec3::new(
+                random_f64(0.5, 1.0),
+                random_f64(0.3, 0.9),
+                random_f64(0.2, 0.8),
+            );
+            let center = Vec3::new(
+                -6.0 + i as f64 + random_f64(0.0, 0.5),
+                0.5 + 0.7 * j as f64 + random_f64(0.0, 0.3),
+                -15.0 - random_f64(0.0, 10.0),
+            );
+            world.add(Arc::new(Sphere::new(
+                center,
+                0.05,
+                Some(Arc::new(DiffuseLight::new_by_color(40.0 * color))),
+            )));
+        }
+    }
+    let world = HitList::new_by_arc(Arc::new(Bvh_Node::new_by_list(&world)));
+    camera.samples_per_pixel = 200;
+    camera.max_depth = 10;
+    camera.lookfrom = Vec3::new(0.0, 0.4, 3.0);
+    camera.lookat = Vec3::new(0.0, 0.3, 0.0);
+    camera.vup = Vec3::new(0.0, 1.0, 0.0);
+    camera.lens = Some(ThinLens::new(85.0, 1.4, 36.0, 3.0));
+    camera.aperture = aperture;
+    camera.cats_eye = cats_eye;
+    camera.lights.push(Arc::new(PointLight::new_by_power(
+        Vec3::new(1.0, 2.0, 3.0),
+        Vec3::new(60.0, 60.0, 60.0),
+    )));
+    camera.background = Vec3::new(0.01, 0.01, 0.02);
+    camera.render(&world, &mut img);
+
+    println!("Ouput image as \"{}\"\n Author: {}", path, AUTHOR);
+    let output_image: image::DynamicImage = image::DynamicImage::ImageRgb8(img);
+    let mut output_file: File = File::create(path).unwrap();
+    match output_image.write_to(&mut output_file, image::ImageOutputFormat::Jpeg(quality)) {
+        Ok(_) => {}
+        Err(_) => println!("Outputting image fails."),
+    }
+}
+
+pub fn hexagon_bokeh(path: &str) {
+    let aperture = Aperture::Polygon {
+        blades: 6,
+        rotation: 15.0,
+    };
+    bokeh_scene(path, aperture, 0.8);
+}
+
+pub fn star_bokeh(path: &str) {
+    let star = match ApertureImage::new("input/aperture.png") {
+        Ok(star) => star,
+        Err(_) => {
+            println!("Error loading aperture image!");
+            return;
+        }
+    };
+    bokeh_scene(path, Aperture::Image(Arc::new(star)), 0.0);
+}
+pub fn motion_blur(path: &str) {
+    let mut camera = Camera::init(400, 16.0 / 9.0);
+    let mut img: RgbImage = ImageBuffer::new(camera.width as u32, camera.height as u32);
+    let quality = 60;
+    let list = Vec::new();
+    let mut world = HitList::new(list);
+    world.add(Arc::new(Sphere::new(
+        Vec3::new(0.0, -1000.0, 0.0),
+        1000.0,
+        Some(Arc::new(Lambertian::new(Vec3::new(0.5, 0.5, 0.5)))),
+    )));
+    // wheel rolling to the right: a rim of spokes turning around its axle
+    let rubber = Arc::new(Lambertian::new(Vec3::new(0.1, 0.1, 0.1)));
+    let steel = Arc::new(Metal::new(Vec3::new(0.8, 0.8, 0.85), 0.2));
+    let mut wheel = HitList::new(Vec::new());
+    wheel.add(Arc::new(Sphere::new(
+        Vec3::zero(),
+        0.15,
+        Some(steel.clone()),
+    )));
+    for k in 0..6 {
+        let spoke = gen_box(
+            Vec3::new(0.0, -0.03, -0.03),
+            Vec3::new(0.9, 0.03, 0.03),
+            steel.clone(),
+        );
+        let turn = Quat::from_axis_angle(Vec3::new(0.0, 0.0, 1.0), 60.0 * k as f64);
+        let keys = vec![Keyframe::new(0.0, Vec3::zero(), turn, Vec3::ones())];
+        wheel.add(Arc::new(Animated::new(spoke, AnimatedTransform::new(keys))));
+    }
+    for k in 0..24 {
+        let angle = (15.0 * k as f64).to_radians();
+        wheel.add(Arc::new(Sphere::new(
+            Vec3::new(angle.cos(), angle.sin(), 0.0),
+            0.14,
+            Some(rubber.clone()),
+        )));
+    }
+    let axis = Vec3::new(0.0, 0.0, 1.0);
+    let rolling = AnimatedTransform::new(vec![
+        Keyframe::new(
+            0.0,
+            Vec3::new(-2.2, 1.15, 0.0),
+            Quat::identity(),
+            Vec3::ones(),
+        ),
+        Keyframe::new(
+            1.0,
+            Vec3::new(-1.8, 1.15, 0.0),
+            Quat::from_axis_angle(axis, -40.0),
+            Vec3::ones(),
+        ),
+    ]);
+    world.add(Arc::new(Animated::new(Arc::new(wheel), rolling)));
+    // spinning globe
+    let earth_texture: Arc<dyn Texture> = match ImageTexture::new("input/earthmap.jpg") {
+        Ok(texture) => Arc::new(texture),
+        Err(_) => {
+            println!("Error creating texture!");
+            return;
+        }
+    };
+    let globe = Arc::new(Sphere::new(
+        Vec3::zero(),
+        1.0,
+        Some(Arc::new(Lambertian::new_by_tex(earth_texture))),
+    ));
+    let up = Vec3::new(0.0, 1.0, 0.0);
+    let spinning = AnimatedTransform::new(vec![
+        Keyframe::new(
+            0.0,
+            Vec3::new(0.8, 1.0, 0.0),
+            Quat::identity(),
+            Vec3::ones(),
+        ),
+        Keyframe::new(
+            1.0,
+            Vec3::new(0.8, 1.0, 0.0),
+            Quat::from_axis_angle(up, 60.0),
+            Vec3::ones(),
+        ),
+    ]);
+    world.add(Arc::new(Animated::new(globe, spinning)));
+    // box jumping up while it grows and tumbles
+    let cube = gen_box(
+        Vec3::new(-0.4, -0.4, -0.4),
+        Vec3::new(0.4, 0.4, 0.4),
+        Arc::new(Lambertian::new(Vec3::new(0.8, 0.3, 0.1))),
+    );
+    let jumping = AnimatedTransform::new(vec![
+        Keyframe::new(
+            0.0,
+            Vec3::new(3.0, 0.4, 0.0),
+            Quat::identity(),
+            Vec3::ones(),
+        ),
+        Keyframe::new(
+            0.5,
+            Vec3::new(3.0, 1.0, 0.0),
+            Quat::from_axis_angle(Vec3::new(1.0, 1.0, 0.0), 30.0),
+            Vec3::ones() * 1.2,
+        ),
+        Keyframe::new(
+            1.0,
+            Vec3::new(3.0, 1.4, 0.0),
+            Quat::from_axis_angle(Vec3::new(1.0, 1.0, 0.0), 60.0),
+            Vec3::ones() * 1.4,
+        ),
+    ]);
+    world.add(Arc::new(Animated::new(cube, jumping)));
+    camera.samples_per_pixel = 100;
+    camera.max_depth = 20;
+    camera.vfov = 30.0;
+    camera.lookfrom = Vec3::new(0.0, 2.0, 12.0);
+    camera.lookat = Vec3::new(0.3, 1.0, 0.0);
+    camera.vup = Vec3::new(0.0, 1.0, 0.0);
+    camera.defocus_angle = 0.0;
+    // the shutter opens and closes gradually
+    camera.shutter_curve = ShutterCurve::Custom(vec![0.25, 0.75, 1.0, 1.0, 1.0, 1.0, 0.75, 0.25]);
+    camera.background = Vec3::new(0.7, 0.8, 1.0);
+    camera.render(&world, &mut img);
+
+    println!("Ouput image as \"{}\"\n Author: {}", path, AUTHOR);
+    let output_image: image::DynamicImage = image::DynamicImage::ImageRgb8(img);
+    let mut output_file: File = File::create(path).unwrap();
+    match output_image.write_to(&mut output_file, image::ImageOutputFormat::Jpeg(quality)) {
+        Ok(_) => {}
+        Err(_) => println!("Outputting image fails."),
+    }
+}
+
+pub fn moving_camera(path: &str) {
+    let mut camera = Camera::init(400, 16.0 / 9.0);
+    let mut img: RgbImage = ImageBuffer::new(camera.width as u32, camera.height as u32);
+    let quality = 60;
+    let list = Vec::new();
+    let mut world = HitList::new(list);
+    let checker = Arc::new(CheckerTexture::new_by_color(
+        0.32,
+        Vec3::new(0.2, 0.3, 0.1),
+        Vec3::new(0.9, 0.9, 0.9),
+    ));
+    world.add(Arc::new(Sphere::new(
+        Vec3::new(0.0, -1000.0, 0.0),
+        1000.0,
+        Some(Arc::new(Lambertian::new_by_tex(checker))),
+    )));
+    for k in 0..5 {
+        world.add(Arc::new(Sphere::new(
+            Vec3::new(-4.0 + 2.0 * k as f64, 0.7, -2.0 * k as f64),
+            0.7,
+            Some(Arc::new(Lambertian::new(Vec3::new(
+                0.2 + 0.15 * k as f64,
+                0.3,
+                0.8 - 0.15 * k as f64,
+            )))),
+        )));
+    }
+    camera.samples_per_pixel = 64;
+    camera.max_depth = 20;
+    camera.vfov = 40.0;
+    camera.lookfrom = Vec3::new(0.0, 1.5, 8.0);
+    camera.lookat = Vec3::new(0.0, 0.7, 0.0);
+    camera.vup = Vec3::new(0.0, 1.0, 0.0);
+    camera.defocus_angle = 0.0;
+    // panning to the left during a half open shutter
+    camera.shutter_close = 0.5;
+    camera.shutter_curve = ShutterCurve::Triangle;
+    camera.motion = Some(AnimatedTransform::new(vec![
+        Keyframe::new(0.0, Vec3::zero(), Quat::identity(), Vec3::ones()),
+        Keyframe::new(
+            1.0,
+            Vec3::new(-1.0, 0.0, 0.0),
+            Quat::from_axis_angle(Vec3::new(0.0, 1.0, 0.0), 8.0),
+            Vec3::ones(),
+        ),
+    ]));
+    camera.background = Vec3::new(0.7, 0.8, 1.0);
+    camera.render(&world, &mut img);
+
+    println!("Ouput image as \"{}\"\n Author: {}", path, AUTHOR);
+    let output_image: image::DynamicImage = image::DynamicImage::ImageRgb8(img);
+    let mut output_file: File = File::create(path).unwrap();
+    match output_image.write_to(&mut output_file, image::ImageOutputFormat::Jpeg(quality)) {
+        Ok(_) => {}
+        Err(_) => println!("Outputting image fails."),
+    }
+}
+fn sequence_scene(dir: &str, camera_path: CameraPath, last_frame: usize) {
+    let mut camera = Camera::init(320, 16.0 / 9.0);
+    let list = Vec::new();
+    let mut world = HitList::new(list);
+    let checker = Arc::new(CheckerTexture::new_by_color(
+        0.5,
+        Vec3::new(0.2, 0.3, 0.1),
+        Vec3::new(0.9, 0.9, 0.9),
+    ));
+    world.add(Arc::new(Sphere::new(
+        Vec3::new(0.0, -1000.0, 0.0),
+        1000.0,
+        Some(Arc::new(Lambertian::new_by_tex(checker))),
+    )));
+    // one turn of the globe every four seconds
+    let earth_texture: Arc<dyn Texture> = match ImageTexture::new("input/earthmap.jpg") {
+        Ok(texture) => Arc::new(texture),
+        Err(_) => {
+            println!("Error creating texture!");
+            return;
+        }
+    };
+    let globe = Arc::new(Sphere::new(
+        Vec3::zero(),
+        1.0,
+        Some(Arc::new(Lambertian::new_by_tex(earth_texture))),
+    ));
+    let up = Vec3::new(0.0, 1.0, 0.0);
+    let keys = (0..=4)
+        .map(|k| {
+            let rotation = Quat::from_axis_angle(up, 90.0 * k as f64);
+            Keyframe::new(k as f64, Vec3::new(0.0, 1.0, 0.0), rotation, Vec3::ones())
+        })
+        .collect();
+    world.add(Arc::new(Animated::new(globe, AnimatedTransform::new(keys))));
+    world.add(Arc::new(Sphere::new(
+        Vec3::new(2.5, 0.5, 0.0),
+        0.5,
+        Some(Arc::new(Conductor::gold(0.1))),
+    )));
+    world.add(Arc::new(Sphere::new(
+        Vec3::new(-2.5, 0.5, 0.0),
+        0.5,
+        Some(Arc::new(Dielectric::new(1.5))),
+    )));
+    camera.samples_per_pixel = 32;
+    camera.max_depth = 20;
+    camera.vup = Vec3::new(0.0, 1.0, 0.0);
+    camera.defocus_angle = 0.0;
+    camera.background = Vec3::new(0.7, 0.8, 1.0);
+
+    let mut sequence = Sequence::new(1, last_frame, 12.0, dir);
+    sequence.camera_path = Some(camera_path);
+    sequence.render(&mut camera, &world);
+}
+pub fn orbit_sequence(dir: &str) {
+    // half a circle around the scene, closing in at the end
+    let target = Vec3::new(0.0, 1.0, 0.0);
+    let path = CameraPath::new(
+        vec![
+            CameraKey::new(0.0, Vec3::new(0.0, 2.0, 10.0), target, 35.0),
+            CameraKey::new(1.0, Vec3::new(7.0, 3.0, 7.0), target, 35.0),
+            CameraKey::new(2.0, Vec3::new(10.0, 4.0, 0.0), target, 30.0),
+            CameraKey::new(3.0, Vec3::new(7.0, 3.0, -7.0), target, 25.0),
+            CameraKey::new(4.0, Vec3::new(0.0, 2.0, -8.0), target, 25.0),
+        ],
+        PathInterpolation::CatmullRom,
+    );
+    sequence_scene(dir, path, 48);
+}
+pub fn dolly_zoom_sequence(dir: &str) {
+    // the camera backs off while zooming in, the globe keeps its size
+    let target = Vec3::new(0.0, 1.0, 0.0);
+    let path = CameraPath::new(
+        vec![
+            CameraKey::new(0.0, Vec3::new(0.0, 1.5, 6.0), target, 40.0),
+            CameraKey::new(2.0, Vec3::new(0.0, 1.5, 16.0), target, 15.5),
+        ],
+        PathInterpolation::Linear,
+    );
+    sequence_scene(dir, path, 24);
+}
 fn main() {
     let path = "output/test.jpg";
 
@@ -458,5 +2711,39 @@ fn main() {
     // perlin_shpere(path);
     // quads(path);
     // simple_light(path);
+    // microfacet_spheres(path);
+    // principled_spheres(path);
+    // bumpy_spheres(path);
+    // light_rig(path);
+    // environment_spheres(path);
+    // daylight_spheres(path);
+    // dispersion_spheres(path);
+    // colored_glass(path);
+    // panorama_spheres(path);
+    // fisheye_spheres(path);
+    // cylindrical_spheres(path);
+    // ortho_spheres(path);
+    // hexagon_bokeh(path);
+    // star_bokeh(path);
+    // motion_blur(path);
+    // moving_camera(path);
+    // bdpt_caustics(path);
+    // caustic_spheres(path);
+    // shaded_lamp(path);
+    // primitives(path);
+    // csg_shapes(path);
+    // sdf_shapes(path);
+    // perlin_terrain(path);
+    // image_terrain(path);
+    // voxel_world(path);
+    // vox_model(path);
+    // smooth_surfaces(path);
+    // teapot(path);
+    // procedural_textures(path);
+    // noise_variants(path);
+    // texture_filtering(path);
+    // color_management(path);
+    // orbit_sequence("output/sequence");
+    // dolly_zoom_sequence("output/dolly_zoom");
     cornel_box(path);
 }
@@ -1,11 +1,18 @@
+use std::f64::consts::PI;
 use std::sync::Arc;
 
 use crate::hit::HitRecord;
+use crate::medium::{Medium, MediumStack};
+use crate::microfacet::{
+    fresnel_complex, fresnel_dielectric, reflect_local, refract_local, TrowbridgeReitz,
+};
+use crate::onb::Onb;
 use crate::ray::Ray;
+use crate::rt::random_f64;
+use crate::spectrum::terminate_secondary;
 use crate::texture::{CheckerTexture, SolidColor, Texture};
 use crate::vec3::Vec3;
-use rand::Rng;
-pub trait Material {
+pub trait Material: Send + Sync {
     fn scatter(
         &self,
         ray_in: &Ray,
@@ -14,6 +21,28 @@ pub trait Material {
         scattered: &mut Ray,
     ) -> bool;
     fn emitted(&self, u: f64, v: f64, p: Vec3) -> Vec3;
+
+    /// BSDF times |cos| towards `dir`, used when lights are sampled explicitly.
+    /// Purely specular materials keep the default of zero.
+    fn eval(&self, _ray_in: &Ray, _rec: &HitRecord, _dir: Vec3) -> Vec3 {
+        Vec3::zero()
+    }
+
+    /// density with which `scatter` picks `dir`, zero for specular materials
+    fn scattering_pdf(&self, _ray_in: &Ray, _rec: &HitRecord, _dir: Vec3) -> f64 {
+        0.0
+    }
+
+    /// chance that a ray stops at `rec` rather than passing through a cut out hole,
+    /// shadow rays test it the same way `scatter` does
+    fn coverage(&self, _rec: &HitRecord) -> f64 {
+        1.0
+    }
+
+    /// media enclosing `scattered`, dielectrics add or remove themselves when crossed
+    fn next_media(&self, ray_in: &Ray, _rec: &HitRecord, _scattered: &Ray) -> MediumStack {
+        ray_in.media
+    }
 }
 
 #[derive(Clone)]
@@ -46,18 +75,32 @@ impl Material for Lambertian {
             dir = rec.normal;
         }
         *scattered = Ray::new(rec.point, dir, ray_in.time);
-        *attenuation = self.tex.value(rec.u, rec.v, rec.point);
+        *attenuation = self.tex.value_at(rec);
         true
     }
 
     fn emitted(&self, u: f64, v: f64, p: Vec3) -> Vec3 {
         Vec3::zero()
     }
+
+    fn eval(&self, ray_in: &Ray, rec: &HitRecord, dir: Vec3) -> Vec3 {
+        self.scattering_pdf(ray_in, rec, dir) * self.tex.value_at(rec)
+    }
+
+    fn scattering_pdf(&self, _ray_in: &Ray, rec: &HitRecord, dir: Vec3) -> f64 {
+        let cos_theta = rec.normal * dir.normalize();
+        if cos_theta < 0.0 {
+            0.0
+        } else {
+            cos_theta / PI
+        }
+    }
 }
 
 pub struct Metal {
     pub albedo: Vec3,
     pub fuzz: f64,
+    pub fuzz_tex: Option<Arc<dyn Texture>>,
 }
 
 impl Metal {
@@ -66,8 +109,43 @@ impl Metal {
         Self {
             albedo,
             fuzz: fuzz_,
+            fuzz_tex: None,
         }
     }
+
+    /// fuzz read from the first channel of a roughness texture
+    pub fn new_by_tex(albedo: Vec3, fuzz_tex: Arc<dyn Texture>) -> Self {
+        Self {
+            albedo,
+            fuzz: 0.0,
+            fuzz_tex: Some(fuzz_tex),
+        }
+    }
+
+    fn fuzz_at(&self, rec: &HitRecord) -> f64 {
+        match &self.fuzz_tex {
+            Some(tex) => tex.value_at(rec).x.clamp(0.0, 1.0),
+            None => self.fuzz,
+        }
+    }
+
+    /// density of the directions `scatter` picks around the mirror direction. The fuzz
+    /// point lies on a sphere of radius `fuzz` about the unit reflection, so a direction
+    /// at angle theta meets that sphere at distances cos(theta) +- sqrt(fuzz^2 - sin^2(theta)).
+    fn fuzz_pdf(&self, ray_in: &Ray, rec: &HitRecord, dir: Vec3) -> f64 {
+        let fuzz = self.fuzz_at(rec);
+        if fuzz <= 0.0 || dir * rec.normal <= 0.0 {
+            return 0.0;
+        }
+        let reflected = Vec3::reflect(ray_in.dir, rec.normal).normalize();
+        let cos_theta = dir.normalize() * reflected;
+        let sin2_theta = 1.0 - cos_theta * cos_theta;
+        let root2 = fuzz * fuzz - sin2_theta;
+        if cos_theta <= 0.0 || root2 <= 0.0 {
+            return 0.0;
+        }
+        (cos_theta * cos_theta + root2) / (2.0 * PI * fuzz * root2.sqrt())
+    }
 }
 
 impl Material for Metal {
@@ -78,8 +156,9 @@ impl Material for Metal {
         attenuation: &mut Vec3,
         scattered: &mut Ray,
     ) -> bool {
-        let reflected = Vec3::reflect(ray_in.dir, rec.normal).normalize()
-            + self.fuzz * Vec3::random_unit_vector();
+        let fuzz = self.fuzz_at(rec);
+        let reflected =
+            Vec3::reflect(ray_in.dir, rec.normal).normalize() + fuzz * Vec3::random_unit_vector();
         *scattered = Ray::new(rec.point, reflected, ray_in.time);
         *attenuation = self.albedo;
         scattered.dir * rec.normal > 0.0
@@ -87,16 +166,117 @@ impl Material for Metal {
     fn emitted(&self, u: f64, v: f64, p: Vec3) -> Vec3 {
         Vec3::zero()
     }
+
+    /// a sharp mirror stays specular, fuzzy ones reflect `albedo` times the lobe density
+    fn eval(&self, ray_in: &Ray, rec: &HitRecord, dir: Vec3) -> Vec3 {
+        self.fuzz_pdf(ray_in, rec, dir) * self.albedo
+    }
+
+    fn scattering_pdf(&self, ray_in: &Ray, rec: &HitRecord, dir: Vec3) -> f64 {
+        self.fuzz_pdf(ray_in, rec, dir)
+    }
+}
+
+/// wavelength dependence of a dielectric's index of refraction, wavelengths in micrometers
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Dispersion {
+    None,
+    /// n = a + b / lambda^2
+    Cauchy {
+        a: f64,
+        b: f64,
+    },
+    /// n^2 = 1 + sum of b_i lambda^2 / (lambda^2 - c_i)
+    Sellmeier {
+        b: [f64; 3],
+        c: [f64; 3],
+    },
+}
+
+impl Dispersion {
+    pub fn ior(&self, lambda_um: f64) -> Option<f64> {
+        let l2 = lambda_um * lambda_um;
+        match *self {
+            Dispersion::None => None,
+            Dispersion::Cauchy { a, b } => Some(a + b / l2),
+            Dispersion::Sellmeier { b, c } => {
+                let n2 = 1.0 + (0..3).map(|i| b[i] * l2 / (l2 - c[i])).sum::<f64>();
+                Some(n2.max(1.0).sqrt())
+            }
+        }
+    }
 }
 
 pub struct Dielectric {
+    /// index used by RGB rays, at the sodium d-line for dispersive glass
     pub ref_idx: f64,
+    pub dispersion: Dispersion,
+    /// Beer-Lambert absorption per unit length inside the medium
+    pub absorption: Vec3,
+    /// overlapping dielectrics: the interior with the higher priority wins
+    pub priority: i32,
 }
 
 impl Dielectric {
     pub fn new(ref_idx: f64) -> Self {
-        Self { ref_idx }
+        Self {
+            ref_idx,
+            dispersion: Dispersion::None,
+            absorption: Vec3::zero(),
+            priority: 0,
+        }
+    }
+
+    /// tinted glass or liquid letting through `color` after a path of length `distance`
+    pub fn new_by_absorption(ref_idx: f64, color: Vec3, distance: f64) -> Self {
+        let coeff = |c: f64| -c.clamp(1e-6, 1.0).ln() / distance;
+        Self {
+            absorption: Vec3::new(coeff(color.x), coeff(color.y), coeff(color.z)),
+            ..Self::new(ref_idx)
+        }
+    }
+
+    pub fn new_by_dispersion(dispersion: Dispersion) -> Self {
+        Self {
+            dispersion,
+            ..Self::new(dispersion.ior(0.5876).unwrap_or(1.5))
+        }
+    }
+
+    pub fn new_by_cauchy(a: f64, b: f64) -> Self {
+        Self::new_by_dispersion(Dispersion::Cauchy { a, b })
     }
+
+    pub fn new_by_sellmeier(b: [f64; 3], c: [f64; 3]) -> Self {
+        Self::new_by_dispersion(Dispersion::Sellmeier { b, c })
+    }
+
+    /// Schott N-BK7 crown glass
+    pub fn bk7() -> Self {
+        Self::new_by_sellmeier(
+            [1.03961212, 0.231792344, 1.01046945],
+            [0.00600069867, 0.0200179144, 103.560653],
+        )
+    }
+
+    pub fn diamond() -> Self {
+        Self::new_by_sellmeier([0.3306, 4.3356, 0.0], [0.030625, 0.011236, 0.0])
+    }
+
+    fn id(&self) -> usize {
+        self as *const Self as usize
+    }
+
+    /// index of refraction seen by the ray, spectral rays use their hero wavelength
+    fn ior(&self, ray_in: &Ray) -> f64 {
+        if ray_in.is_spectral() {
+            if let Some(ior) = self.dispersion.ior(ray_in.lambda.x / 1000.0) {
+                return ior;
+            }
+        }
+        self.ref_idx
+    }
+
     pub fn reflectance(cosine: f64, refr_idx: f64) -> f64 {
         let mut r0 = (1.0 - refr_idx) / (1.0 + refr_idx);
         r0 = r0 * r0;
@@ -112,31 +292,261 @@ impl Material for Dielectric {
         attenuation: &mut Vec3,
         scattered: &mut Ray,
     ) -> bool {
-        let mut rng = rand::thread_rng();
         *attenuation = Vec3::new(1.0, 1.0, 1.0);
+        // the medium on the far side of the surface, None outside of all dielectrics
+        let (inside, outside) = if rec.front_face {
+            (ray_in.media.current(), None)
+        } else {
+            (None, ray_in.media.current_without(self.id()))
+        };
+        let other = inside.or(outside);
+        if other.is_some_and(|m| m.priority > self.priority) {
+            // a surface inside a medium of higher priority does not exist for the ray
+            *scattered = Ray::new(rec.point, ray_in.dir, ray_in.time);
+            return true;
+        }
+        let ref_idx = self.ior(ray_in);
+        let other_idx = other.map_or(1.0, |m| m.ior);
         let ri = if rec.front_face {
-            1.0 / self.ref_idx
+            other_idx / ref_idx
         } else {
-            self.ref_idx
+            ref_idx / other_idx
         };
         let unit_dir = ray_in.dir.normalize();
         let cos_theta = (-unit_dir * rec.normal).min(1.0);
         let sin_theta = f64::sqrt(1.0 - cos_theta * cos_theta);
         let cannot_refract = ri * sin_theta > 1.0;
-        if cannot_refract || Dielectric::reflectance(cos_theta, ri) > rng.gen_range(0.0..1.0) {
+        if cannot_refract || Dielectric::reflectance(cos_theta, ri) > random_f64(0.0, 1.0) {
             let reflected = Vec3::reflect(unit_dir, rec.normal);
             *scattered = Ray::new(rec.point, reflected, ray_in.time);
         } else {
             let refracted = Vec3::refract(unit_dir, rec.normal, ri);
             *scattered = Ray::new(rec.point, refracted, ray_in.time);
         }
+        if ray_in.is_spectral() && self.dispersion != Dispersion::None {
+            scattered.lambda = terminate_secondary(ray_in.lambda);
+        }
         true
     }
+
+    fn next_media(&self, ray_in: &Ray, rec: &HitRecord, scattered: &Ray) -> MediumStack {
+        let mut media = ray_in.media;
+        // the normal faces the incoming ray, crossing the surface goes against it
+        if scattered.dir * rec.normal < 0.0 {
+            if rec.front_face {
+                media.push(Medium {
+                    id: self.id(),
+                    priority: self.priority,
+                    ior: self.ior(ray_in),
+                    absorption: self.absorption,
+                });
+            } else {
+                media.remove(self.id());
+            }
+        }
+        media
+    }
     fn emitted(&self, u: f64, v: f64, p: Vec3) -> Vec3 {
         Vec3::zero()
     }
 }
 
+/// Cook-Torrance conductor with complex index of refraction `eta + i k`
+pub struct Conductor {
+    pub eta: Vec3,
+    pub k: Vec3,
+    pub distrib: TrowbridgeReitz,
+}
+
+impl Conductor {
+    pub fn new(eta: Vec3, k: Vec3, roughness: f64) -> Self {
+        Self {
+            eta,
+            k,
+            distrib: TrowbridgeReitz::new_by_roughness(roughness),
+        }
+    }
+
+    pub fn gold(roughness: f64) -> Self {
+        Self::new(
+            Vec3::new(0.143, 0.374, 1.442),
+            Vec3::new(3.983, 2.385, 1.603),
+            roughness,
+        )
+    }
+
+    pub fn copper(roughness: f64) -> Self {
+        Self::new(
+            Vec3::new(0.200, 0.924, 1.102),
+            Vec3::new(3.912, 2.452, 2.142),
+            roughness,
+        )
+    }
+
+    pub fn aluminium(roughness: f64) -> Self {
+        Self::new(
+            Vec3::new(1.657, 0.880, 0.521),
+            Vec3::new(9.224, 6.270, 4.837),
+            roughness,
+        )
+    }
+}
+
+impl Conductor {
+    fn eval_local(&self, wo: Vec3, wi: Vec3) -> (Vec3, f64) {
+        if self.distrib.effectively_smooth() || wo.z <= 0.0 || wi.z <= 0.0 {
+            return (Vec3::zero(), 0.0);
+        }
+        let wm = wo + wi;
+        if wm.near_zero() {
+            return (Vec3::zero(), 0.0);
+        }
+        let wm = wm.normalize();
+        let f = fresnel_complex((wo * wm).abs(), self.eta, self.k);
+        let value = self.distrib.d(wm) * self.distrib.g(wo, wi) / (4.0 * wo.z * wi.z);
+        let pdf = self.distrib.d_visible(wo, wm) / (4.0 * (wo * wm).abs());
+        (value * f, pdf)
+    }
+}
+
+impl Material for Conductor {
+    fn scatter(
+        &self,
+        ray_in: &Ray,
+        rec: &HitRecord,
+        attenuation: &mut Vec3,
+        scattered: &mut Ray,
+    ) -> bool {
+        let onb = Onb::new(rec.normal);
+        let wo = onb.to_local(-ray_in.dir.normalize());
+        if wo.z <= 0.0 {
+            return false;
+        }
+        if self.distrib.effectively_smooth() {
+            let wi = Vec3::new(-wo.x, -wo.y, wo.z);
+            *scattered = Ray::new(rec.point, onb.local(wi), ray_in.time);
+            *attenuation = fresnel_complex(wo.z, self.eta, self.k);
+            return true;
+        }
+        let wm = self
+            .distrib
+            .sample_wm(wo, random_f64(0.0, 1.0), random_f64(0.0, 1.0));
+        let wi = reflect_local(wo, wm);
+        if wi.z <= 0.0 {
+            return false;
+        }
+        // with visible normal sampling the estimator reduces to F * G2 / G1
+        let f = fresnel_complex((wo * wm).abs(), self.eta, self.k);
+        *scattered = Ray::new(rec.point, onb.local(wi), ray_in.time);
+        *attenuation = f * (self.distrib.g(wo, wi) / self.distrib.g1(wo));
+        true
+    }
+    fn emitted(&self, _u: f64, _v: f64, _p: Vec3) -> Vec3 {
+        Vec3::zero()
+    }
+
+    fn eval(&self, ray_in: &Ray, rec: &HitRecord, dir: Vec3) -> Vec3 {
+        let onb = Onb::new(rec.normal);
+        let wo = onb.to_local(-ray_in.dir.normalize());
+        let wi = onb.to_local(dir.normalize());
+        self.eval_local(wo, wi).0 * wi.z.abs()
+    }
+
+    fn scattering_pdf(&self, ray_in: &Ray, rec: &HitRecord, dir: Vec3) -> f64 {
+        let onb = Onb::new(rec.normal);
+        let wo = onb.to_local(-ray_in.dir.normalize());
+        let wi = onb.to_local(dir.normalize());
+        self.eval_local(wo, wi).1
+    }
+}
+
+/// frosted glass: a dielectric interface with GGX distributed microfacets
+pub struct RoughDielectric {
+    pub ref_idx: f64,
+    pub distrib: TrowbridgeReitz,
+}
+
+impl RoughDielectric {
+    pub fn new(ref_idx: f64, roughness: f64) -> Self {
+        Self {
+            ref_idx,
+            distrib: TrowbridgeReitz::new_by_roughness(roughness),
+        }
+    }
+
+    /// the normal faces the incoming ray, so this is always n_t / n_i
+    fn eta(&self, rec: &HitRecord) -> f64 {
+        if rec.front_face {
+            self.ref_idx
+        } else {
+            1.0 / self.ref_idx
+        }
+    }
+}
+
+impl Material for RoughDielectric {
+    fn scatter(
+        &self,
+        ray_in: &Ray,
+        rec: &HitRecord,
+        attenuation: &mut Vec3,
+        scattered: &mut Ray,
+    ) -> bool {
+        if self.distrib.effectively_smooth() {
+            return Dielectric::new(self.ref_idx).scatter(ray_in, rec, attenuation, scattered);
+        }
+        let eta = self.eta(rec);
+        let onb = Onb::new(rec.normal);
+        let wo = onb.to_local(-ray_in.dir.normalize());
+        if wo.z <= 0.0 {
+            return false;
+        }
+        let wm = self
+            .distrib
+            .sample_wm(wo, random_f64(0.0, 1.0), random_f64(0.0, 1.0));
+        let f = fresnel_dielectric(wo * wm, eta);
+        let wi = if random_f64(0.0, 1.0) < f {
+            let wi = reflect_local(wo, wm);
+            if wi.z <= 0.0 {
+                return false;
+            }
+            wi
+        } else {
+            match refract_local(wo, wm, eta) {
+                Some(wi) if wi.z < 0.0 => wi,
+                _ => return false,
+            }
+        };
+        // choosing reflection with probability F cancels the Fresnel term
+        *scattered = Ray::new(rec.point, onb.local(wi), ray_in.time);
+        *attenuation = Vec3::ones() * (self.distrib.g(wo, wi) / self.distrib.g1(wo));
+        true
+    }
+    fn emitted(&self, _u: f64, _v: f64, _p: Vec3) -> Vec3 {
+        Vec3::zero()
+    }
+
+    fn eval(&self, ray_in: &Ray, rec: &HitRecord, dir: Vec3) -> Vec3 {
+        if self.distrib.effectively_smooth() {
+            return Vec3::zero();
+        }
+        let onb = Onb::new(rec.normal);
+        let wo = onb.to_local(-ray_in.dir.normalize());
+        let wi = onb.to_local(dir.normalize());
+        Vec3::ones() * (self.distrib.eval_dielectric(wo, wi, self.eta(rec)).0 * wi.z.abs())
+    }
+
+    fn scattering_pdf(&self, ray_in: &Ray, rec: &HitRecord, dir: Vec3) -> f64 {
+        if self.distrib.effectively_smooth() {
+            return 0.0;
+        }
+        let onb = Onb::new(rec.normal);
+        let wo = onb.to_local(-ray_in.dir.normalize());
+        let wi = onb.to_local(dir.normalize());
+        self.distrib.eval_dielectric(wo, wi, self.eta(rec)).1
+    }
+}
+
 pub struct DiffuseLight {
     tex: Arc<dyn Texture>,
 }
@@ -166,3 +576,313 @@ impl Material for DiffuseLight {
         self.tex.value(u, v, p)
     }
 }
+
+/// perturbs the shading normal with a tangent space normal map, then defers to `inner`
+pub struct NormalMap {
+    pub inner: Arc<dyn Material>,
+    pub map: Arc<dyn Texture>,
+    pub strength: f64,
+}
+
+impl NormalMap {
+    pub fn new(inner: Arc<dyn Material>, map: Arc<dyn Texture>) -> Self {
+        Self {
+            inner,
+            map,
+            strength: 1.0,
+        }
+    }
+
+    fn shade(&self, rec: &HitRecord) -> HitRecord {
+        let (frame, sign) = rec.tangent_frame();
+        let texel = self.map.value_at(rec);
+        let n = Vec3::new(
+            self.strength * (2.0 * texel.x - 1.0),
+            sign * self.strength * (2.0 * texel.y - 1.0),
+            2.0 * texel.z - 1.0,
+        );
+        let mut shaded = rec.clone();
+        shaded.set_shading_normal(frame.local(n));
+        shaded
+    }
+}
+
+impl Material for NormalMap {
+    fn scatter(
+        &self,
+        ray_in: &Ray,
+        rec: &HitRecord,
+        attenuation: &mut Vec3,
+        scattered: &mut Ray,
+    ) -> bool {
+        self.inner
+            .scatter(ray_in, &self.shade(rec), attenuation, scattered)
+    }
+    fn emitted(&self, u: f64, v: f64, p: Vec3) -> Vec3 {
+        self.inner.emitted(u, v, p)
+    }
+
+    fn eval(&self, ray_in: &Ray, rec: &HitRecord, dir: Vec3) -> Vec3 {
+        self.inner.eval(ray_in, &self.shade(rec), dir)
+    }
+
+    fn scattering_pdf(&self, ray_in: &Ray, rec: &HitRecord, dir: Vec3) -> f64 {
+        self.inner.scattering_pdf(ray_in, &self.shade(rec), dir)
+    }
+
+    fn coverage(&self, rec: &HitRecord) -> f64 {
+        self.inner.coverage(rec)
+    }
+
+    fn next_media(&self, ray_in: &Ray, rec: &HitRecord, scattered: &Ray) -> MediumStack {
+        self.inner.next_media(ray_in, rec, scattered)
+    }
+}
+
+/// perturbs the shading normal by the gradient of a height texture, then defers to `inner`
+pub struct BumpMap {
+    pub inner: Arc<dyn Material>,
+    pub height: Arc<dyn Texture>,
+    pub strength: f64,
+    /// uv step of the finite differences
+    pub delta: f64,
+}
+
+impl BumpMap {
+    pub fn new(inner: Arc<dyn Material>, height: Arc<dyn Texture>, strength: f64) -> Self {
+        Self {
+            inner,
+            height,
+            strength,
+            delta: 0.0005,
+        }
+    }
+
+    fn shade(&self, rec: &HitRecord) -> HitRecord {
+        let d = self.delta;
+        let h = self.height.value(rec.u, rec.v, rec.point).x;
+        let h_u = self
+            .height
+            .value(rec.u + d, rec.v, rec.point + d * rec.dpdu)
+            .x;
+        let h_v = self
+            .height
+            .value(rec.u, rec.v + d, rec.point + d * rec.dpdv)
+            .x;
+        let dhdu = self.strength * (h_u - h) / d;
+        let dhdv = self.strength * (h_v - h) / d;
+        let dpdu = rec.dpdu + dhdu * rec.normal;
+        let dpdv = rec.dpdv + dhdv * rec.normal;
+        let mut n = dpdu.cross(dpdv);
+        if n * rec.normal < 0.0 {
+            n = -n;
+        }
+        let mut shaded = rec.clone();
+        if !n.near_zero() {
+            shaded.set_shading_normal(n);
+        }
+        shaded
+    }
+}
+
+impl Material for BumpMap {
+    fn scatter(
+        &self,
+        ray_in: &Ray,
+        rec: &HitRecord,
+        attenuation: &mut Vec3,
+        scattered: &mut Ray,
+    ) -> bool {
+        self.inner
+            .scatter(ray_in, &self.shade(rec), attenuation, scattered)
+    }
+    fn emitted(&self, u: f64, v: f64, p: Vec3) -> Vec3 {
+        self.inner.emitted(u, v, p)
+    }
+
+    fn eval(&self, ray_in: &Ray, rec: &HitRecord, dir: Vec3) -> Vec3 {
+        self.inner.eval(ray_in, &self.shade(rec), dir)
+    }
+
+    fn scattering_pdf(&self, ray_in: &Ray, rec: &HitRecord, dir: Vec3) -> f64 {
+        self.inner.scattering_pdf(ray_in, &self.shade(rec), dir)
+    }
+
+    fn coverage(&self, rec: &HitRecord) -> f64 {
+        self.inner.coverage(rec)
+    }
+
+    fn next_media(&self, ray_in: &Ray, rec: &HitRecord, scattered: &Ray) -> MediumStack {
+        self.inner.next_media(ray_in, rec, scattered)
+    }
+}
+
+/// cuts holes into `inner` where the alpha of `mask` is below one, like leaves or a fence
+/// on a flat card. Rays go on straight through with the chance 1 - alpha.
+pub struct AlphaMask {
+    pub inner: Arc<dyn Material>,
+    pub mask: Arc<dyn Texture>,
+}
+
+impl AlphaMask {
+    pub fn new(inner: Arc<dyn Material>, mask: Arc<dyn Texture>) -> Self {
+        Self { inner, mask }
+    }
+
+    fn alpha(&self, rec: &HitRecord) -> f64 {
+        self.mask.alpha_at(rec).clamp(0.0, 1.0)
+    }
+}
+
+impl Material for AlphaMask {
+    fn scatter(
+        &self,
+        ray_in: &Ray,
+        rec: &HitRecord,
+        attenuation: &mut Vec3,
+        scattered: &mut Ray,
+    ) -> bool {
+        if random_f64(0.0, 1.0) >= self.alpha(rec) {
+            *scattered = Ray {
+                orig: rec.point,
+                ..*ray_in
+            };
+            *attenuation = Vec3::ones();
+            return true;
+        }
+        self.inner.scatter(ray_in, rec, attenuation, scattered)
+    }
+    fn emitted(&self, u: f64, v: f64, p: Vec3) -> Vec3 {
+        self.inner.emitted(u, v, p)
+    }
+
+    fn eval(&self, ray_in: &Ray, rec: &HitRecord, dir: Vec3) -> Vec3 {
+        self.alpha(rec) * self.inner.eval(ray_in, rec, dir)
+    }
+
+    fn scattering_pdf(&self, ray_in: &Ray, rec: &HitRecord, dir: Vec3) -> f64 {
+        self.alpha(rec) * self.inner.scattering_pdf(ray_in, rec, dir)
+    }
+
+    fn coverage(&self, rec: &HitRecord) -> f64 {
+        self.alpha(rec) * self.inner.coverage(rec)
+    }
+
+    fn next_media(&self, ray_in: &Ray, rec: &HitRecord, scattered: &Ray) -> MediumStack {
+        // through a hole the ray stays where it was
+        if scattered.dir == ray_in.dir {
+            return ray_in.media;
+        }
+        self.inner.next_media(ray_in, rec, scattered)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// height that rises along u
+    struct Ramp;
+
+    impl Texture for Ramp {
+        fn value(&self, u: f64, _v: f64, _p: Vec3) -> Vec3 {
+            Vec3::new(u, u, u)
+        }
+    }
+
+    fn flat_hit() -> HitRecord {
+        let mut rec = HitRecord::new(Vec3::zero(), Vec3::new(0.0, 0.0, 1.0), 1.0, true, None);
+        rec.u = 0.5;
+        rec.v = 0.5;
+        rec.dpdu = Vec3::new(1.0, 0.0, 0.0);
+        rec.dpdv = Vec3::new(0.0, 1.0, 0.0);
+        rec
+    }
+
+    #[test]
+    fn test_fuzzy_metal_density() {
+        let rec = flat_hit();
+        let ray_in = Ray::new(Vec3::new(-1.0, 0.0, 1.0), Vec3::new(1.0, 0.0, -1.0), 0.0);
+        let metal = Metal::new(Vec3::new(0.9, 0.8, 0.7), 0.4);
+        let (mut attenuation, mut scattered) = (Vec3::zero(), ray_in);
+        for _ in 0..1000 {
+            if metal.scatter(&ray_in, &rec, &mut attenuation, &mut scattered) {
+                let pdf = metal.scattering_pdf(&ray_in, &rec, scattered.dir);
+                let f = metal.eval(&ray_in, &rec, scattered.dir);
+                assert!(pdf > 0.0);
+                assert!((f / pdf - attenuation).length() < 1e-9);
+            }
+        }
+
+        // the density covers the directions `scatter` keeps. The lobe is the cone of
+        // half angle asin(fuzz) around the mirror direction, integrated over the angle
+        // psi with sin(gamma) = fuzz * sin(psi) to take out the singularity at its rim
+        let reflected = Vec3::reflect(ray_in.dir, rec.normal).normalize();
+        let frame = Onb::new(reflected);
+        let (steps, mut integral) = (200, 0.0);
+        for i in 0..steps {
+            let psi = (i as f64 + 0.5) / steps as f64 * PI / 2.0;
+            let sin_gamma = metal.fuzz * psi.sin();
+            let cos_gamma = (1.0 - sin_gamma * sin_gamma).sqrt();
+            let weight = sin_gamma * metal.fuzz * psi.cos() / cos_gamma * (PI / 2.0 / steps as f64);
+            for j in 0..steps {
+                let phi = (j as f64 + 0.5) / steps as f64 * 2.0 * PI;
+                let dir =
+                    cos_gamma * frame.w + sin_gamma * (phi.cos() * frame.u + phi.sin() * frame.v);
+                let pdf = metal.scattering_pdf(&ray_in, &rec, dir);
+                integral += pdf * weight * 2.0 * PI / steps as f64;
+            }
+        }
+        // the offset along the normal is uniform over [-fuzz, fuzz]
+        let kept = ((1.0 + reflected * rec.normal / metal.fuzz) / 2.0).clamp(0.0, 1.0);
+        assert!((integral - kept).abs() < 1e-3, "{}", integral);
+
+        let mirror = Metal::new(Vec3::ones(), 0.0);
+        let reflected = Vec3::reflect(ray_in.dir, rec.normal);
+        assert_eq!(mirror.scattering_pdf(&ray_in, &rec, reflected), 0.0);
+    }
+
+    #[test]
+    fn test_normal_map() {
+        let inner: Arc<dyn Material> = Arc::new(Lambertian::new(Vec3::ones()));
+        let rec = flat_hit();
+
+        let flat = NormalMap::new(
+            inner.clone(),
+            Arc::new(SolidColor::new(Vec3::new(0.5, 0.5, 1.0))),
+        );
+        assert!((flat.shade(&rec).normal - rec.normal).length() < 1e-9);
+
+        let tilted = NormalMap::new(
+            inner.clone(),
+            Arc::new(SolidColor::new(Vec3::new(0.75, 0.75, 1.0))),
+        );
+        let expected = Vec3::new(0.5, 0.5, 1.0).normalize();
+        assert!((tilted.shade(&rec).normal - expected).length() < 1e-9);
+
+        // with mirrored uvs the green channel points the other way along the surface
+        let mut mirrored = flat_hit();
+        mirrored.dpdv = -mirrored.dpdv;
+        let expected = Vec3::new(0.5, -0.5, 1.0).normalize();
+        assert!((tilted.shade(&mirrored).normal - expected).length() < 1e-9);
+
+        // strength scales only the tangential part
+        let mut weak = NormalMap::new(inner, Arc::new(SolidColor::new(Vec3::new(1.0, 0.5, 0.5))));
+        weak.strength = 0.0;
+        assert!((weak.shade(&rec).normal - rec.normal).length() < 1e-9);
+    }
+
+    #[test]
+    fn test_bump_map() {
+        let inner: Arc<dyn Material> = Arc::new(Lambertian::new(Vec3::ones()));
+        let rec = flat_hit();
+
+        let level = BumpMap::new(inner.clone(), Arc::new(SolidColor::new(Vec3::ones())), 1.0);
+        assert!((level.shade(&rec).normal - rec.normal).length() < 1e-9);
+
+        // a height of s * u tilts the normal away from +u by atan(s)
+        let ramp = BumpMap::new(inner, Arc::new(Ramp), 0.5);
+        let expected = Vec3::new(-0.5, 0.0, 1.0).normalize();
+        assert!((ramp.shade(&rec).normal - expected).length() < 1e-6);
+    }
+}
@@ -0,0 +1,116 @@
+use crate::vec3::Vec3;
+
+/// how many dielectrics a ray can be inside of at the same time
+const MAX_DEPTH: usize = 4;
+
+/// interior of a dielectric that a ray has entered
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Medium {
+    /// address of the material, tells the media apart
+    pub id: usize,
+    /// where volumes overlap the medium with the higher priority wins
+    pub priority: i32,
+    pub ior: f64,
+    /// Beer-Lambert absorption coefficient per unit length
+    pub absorption: Vec3,
+}
+
+/// the dielectrics enclosing a ray, used for nested dielectrics
+/// (Schmidt and Budge, "Simple Nested Dielectrics in Ray Traced Images")
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MediumStack {
+    media: [Option<Medium>; MAX_DEPTH],
+}
+
+impl MediumStack {
+    pub fn new() -> Self {
+        Self {
+            media: [None; MAX_DEPTH],
+        }
+    }
+
+    pub fn push(&mut self, medium: Medium) {
+        // deeper nesting than supported is dropped, the ray then sees the outer medium
+        if let Some(slot) = self.media.iter_mut().find(|m| m.is_none()) {
+            *slot = Some(medium);
+        }
+    }
+
+    /// leaves the innermost medium `id`, the media entered after it move down
+    pub fn remove(&mut self, id: usize) {
+        if let Some(i) = self
+            .media
+            .iter()
+            .rposition(|m| m.is_some_and(|m| m.id == id))
+        {
+            self.media[i..].rotate_left(1);
+            self.media[MAX_DEPTH - 1] = None;
+        }
+    }
+
+    /// the medium the ray travels in, the one with the highest priority
+    pub fn current(&self) -> Option<Medium> {
+        self.current_without(usize::MAX)
+    }
+
+    /// the medium the ray travels in once it has left medium `id`
+    pub fn current_without(&self, id: usize) -> Option<Medium> {
+        self.media
+            .iter()
+            .flatten()
+            .filter(|m| m.id != id)
+            .fold(None, |best: Option<Medium>, m| match best {
+                // on a tie the medium entered last wins
+                Some(b) if b.priority > m.priority => Some(b),
+                _ => Some(*m),
+            })
+    }
+
+    /// fraction of light that survives `dist` in the current medium
+    pub fn transmittance(&self, dist: f64) -> Vec3 {
+        match self.current() {
+            Some(m) => Vec3::new(
+                (-m.absorption.x * dist).exp(),
+                (-m.absorption.y * dist).exp(),
+                (-m.absorption.z * dist).exp(),
+            ),
+            None => Vec3::ones(),
+        }
+    }
+}
+
+impl Default for MediumStack {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn medium(id: usize, priority: i32) -> Medium {
+        Medium {
+            id,
+            priority,
+            ior: 1.0 + id as f64 / 10.0,
+            absorption: Vec3::zero(),
+        }
+    }
+
+    #[test]
+    fn test_medium_stack_priority() {
+        let mut media = MediumStack::new();
+        assert_eq!(media.current(), None);
+        media.push(medium(1, 1));
+        media.push(medium(2, 0));
+        // entered last, but the lower priority loses
+        assert_eq!(media.current().unwrap().id, 1);
+        media.push(medium(3, 1));
+        assert_eq!(media.current().unwrap().id, 3);
+        assert_eq!(media.current_without(3).unwrap().id, 1);
+        media.remove(1);
+        media.remove(3);
+        assert_eq!(media.current().unwrap().id, 2);
+    }
+}
@@ -0,0 +1,261 @@
+use crate::aabb::AABB;
+use crate::bvh::Bvh_Node;
+use crate::camera::Camera;
+use crate::hit::{HitRecord, Hittable};
+use crate::interval::Interval;
+use crate::material::Material;
+use crate::onb::Onb;
+use crate::ray::Ray;
+use crate::rt::random_f64;
+use crate::vec3::Vec3;
+use std::sync::Arc;
+
+/// ray parameter and barycentrics of the hit on the triangle p0 p1 p2 (Möller–Trumbore)
+pub fn hit_triangle(ray_: &Ray, p0: Vec3, p1: Vec3, p2: Vec3) -> Option<(f64, f64, f64)> {
+    let (e1, e2) = (p1 - p0, p2 - p0);
+    let pvec = ray_.dir.cross(e2);
+    let det = e1 * pvec;
+    if det.abs() < 1e-12 {
+        return None;
+    }
+    let inv_det = 1.0 / det;
+    let tvec = ray_.orig - p0;
+    let b1 = (tvec * pvec) * inv_det;
+    if !(0.0..=1.0).contains(&b1) {
+        return None;
+    }
+    let qvec = tvec.cross(e1);
+    let b2 = (ray_.dir * qvec) * inv_det;
+    if b2 < 0.0 || b1 + b2 > 1.0 {
+        return None;
+    }
+    Some(((e2 * qvec) * inv_det, b1, b2))
+}
+
+/// how long the edges of a tessellation may get, in pixels of the camera they are seen by
+#[derive(Clone, Copy)]
+pub struct EdgeTarget {
+    eye: Vec3,
+    /// width of a pixel one unit in front of the camera
+    pixel_size: f64,
+    pixels: f64,
+    /// most segments a curve is split into
+    pub max_segments: usize,
+}
+
+impl EdgeTarget {
+    pub fn new(camera: &Camera, pixels: f64) -> Self {
+        let h = (camera.vfov.to_radians() / 2.0).tan();
+        Self {
+            eye: camera.lookfrom,
+            pixel_size: 2.0 * h / camera.height as f64,
+            pixels,
+            max_segments: 64,
+        }
+    }
+
+    /// longest edge allowed at p
+    pub fn max_length(&self, p: Vec3) -> f64 {
+        (self.pixels * self.pixel_size * (p - self.eye).length()).max(1e-9)
+    }
+
+    /// segments for the curve around `points` to meet the target, a power of two so
+    /// that coarser neighbours line up with it
+    pub fn segments(&self, points: &[Vec3]) -> usize {
+        let length: f64 = points.windows(2).map(|w| (w[1] - w[0]).length()).sum();
+        let nearest = points
+            .iter()
+            .map(|&p| self.max_length(p))
+            .fold(f64::INFINITY, f64::min);
+        let n = (length / nearest).ceil().max(1.0) as usize;
+        n.next_power_of_two().min(self.max_segments)
+    }
+}
+
+/// triangles over shared vertices, with optional per-vertex normals and uvs.
+/// Triangles without uvs get (0, 0), (1, 0) and (0, 1) at their corners.
+pub struct TriangleMesh {
+    pub positions: Vec<Vec3>,
+    pub normals: Option<Vec<Vec3>>,
+    pub uvs: Option<Vec<(f64, f64)>>,
+    pub indices: Vec<[usize; 3]>,
+    pub mat: Arc<dyn Material>,
+}
+
+impl TriangleMesh {
+    pub fn new(positions: Vec<Vec3>, indices: Vec<[usize; 3]>, mat: Arc<dyn Material>) -> Self {
+        Self {
+            positions,
+            normals: None,
+            uvs: None,
+            indices,
+            mat,
+        }
+    }
+
+    pub fn with_uvs(mut self, uvs: Vec<(f64, f64)>) -> Self {
+        self.uvs = Some(uvs);
+        self
+    }
+
+    /// smooth normals from the area weighted normals of the triangles around each vertex
+    pub fn compute_normals(&mut self) {
+        let mut normals = vec![Vec3::zero(); self.positions.len()];
+        for &[a, b, c] in &self.indices {
+            let (pa, pb, pc) = (self.positions[a], self.positions[b], self.positions[c]);
+            let n = (pb - pa).cross(pc - pa);
+            for i in [a, b, c] {
+                normals[i] += n;
+            }
+        }
+        for n in normals.iter_mut().filter(|n| !n.near_zero()) {
+            *n = n.normalize();
+        }
+        self.normals = Some(normals);
+    }
+
+    /// the triangles in a BVH of their own
+    pub fn into_bvh(self) -> Bvh_Node {
+        let mesh = Arc::new(self);
+        let triangles: Vec<Arc<dyn Hittable>> = (0..mesh.indices.len())
+            .map(|index| Arc::new(MeshTriangle::new(mesh.clone(), index)) as Arc<dyn Hittable>)
+            .collect();
+        let n = triangles.len();
+        Bvh_Node::new(triangles, 0, n)
+    }
+}
+
+pub struct MeshTriangle {
+    mesh: Arc<TriangleMesh>,
+    index: usize,
+    bbox: AABB,
+}
+
+impl MeshTriangle {
+    pub fn new(mesh: Arc<TriangleMesh>, index: usize) -> Self {
+        let [a, b, c] = mesh.indices[index].map(|i| mesh.positions[i]);
+        let bbox = AABB::new_by_aabb(&AABB::new_by_point(a, b), &AABB::new_by_point(c, c));
+        Self { mesh, index, bbox }
+    }
+
+    fn uvs(&self) -> [(f64, f64); 3] {
+        match &self.mesh.uvs {
+            Some(uvs) => self.mesh.indices[self.index].map(|i| uvs[i]),
+            None => [(0.0, 0.0), (1.0, 0.0), (0.0, 1.0)],
+        }
+    }
+
+    /// record at the barycentrics (b1, b2)
+    fn record(&self, b1: f64, b2: f64, t: f64) -> HitRecord {
+        let ids = self.mesh.indices[self.index];
+        let [p0, p1, p2] = ids.map(|i| self.mesh.positions[i]);
+        let weights = [1.0 - b1 - b2, b1, b2];
+        let point = weights[0] * p0 + weights[1] * p1 + weights[2] * p2;
+        let face = (p1 - p0).cross(p2 - p0).normalize();
+        let mut rec = HitRecord::new(point, face, t, true, Some(self.mesh.mat.clone()));
+        let uv = self.uvs();
+        rec.u = weights[0] * uv[0].0 + weights[1] * uv[1].0 + weights[2] * uv[2].0;
+        rec.v = weights[0] * uv[0].1 + weights[1] * uv[1].1 + weights[2] * uv[2].1;
+        // dpdu and dpdv from the uv differences along the edges
+        let (du1, dv1) = (uv[1].0 - uv[0].0, uv[1].1 - uv[0].1);
+        let (du2, dv2) = (uv[2].0 - uv[0].0, uv[2].1 - uv[0].1);
+        let det = du1 * dv2 - dv1 * du2;
+        if det.abs() > 1e-12 {
+            let (e1, e2) = (p1 - p0, p2 - p0);
+            rec.dpdu = (dv2 * e1 - dv1 * e2) / det;
+            rec.dpdv = (du1 * e2 - du2 * e1) / det;
+        } else {
+            let frame = Onb::new(face);
+            (rec.dpdu, rec.dpdv) = (frame.u, frame.v);
+        }
+        rec
+    }
+
+    /// interpolated vertex normal on the side of the face normal `face`
+    fn shading_normal(&self, b1: f64, b2: f64) -> Option<Vec3> {
+        let normals = self.mesh.normals.as_ref()?;
+        let [n0, n1, n2] = self.mesh.indices[self.index].map(|i| normals[i]);
+        let n = (1.0 - b1 - b2) * n0 + b1 * n1 + b2 * n2;
+        if n.near_zero() {
+            return None;
+        }
+        Some(n.normalize())
+    }
+}
+
+impl Hittable for MeshTriangle {
+    fn hit(&self, ray_: &Ray, int: Interval, rec: &mut HitRecord) -> bool {
+        let [p0, p1, p2] = self.mesh.indices[self.index].map(|i| self.mesh.positions[i]);
+        let (t, b1, b2) = match hit_triangle(ray_, p0, p1, p2) {
+            Some(hit) if int.surrounds(hit.0) => hit,
+            _ => return false,
+        };
+        let hit = self.record(b1, b2, t);
+        let out_normal = hit.normal;
+        *rec = hit;
+        rec.set_face_normal(ray_, out_normal);
+        if let Some(smooth) = self.shading_normal(b1, b2) {
+            // vertex normals follow the winding of the triangle, not the ray
+            let smooth = if smooth * out_normal < 0.0 {
+                -smooth
+            } else {
+                smooth
+            };
+            rec.set_shading_normal(if rec.front_face { smooth } else { -smooth });
+        }
+        true
+    }
+
+    fn bounding_box(&self) -> AABB {
+        self.bbox
+    }
+
+    fn area(&self) -> f64 {
+        let [p0, p1, p2] = self.mesh.indices[self.index].map(|i| self.mesh.positions[i]);
+        0.5 * (p1 - p0).cross(p2 - p0).length()
+    }
+
+    fn sample_surface(&self, _time: f64) -> Option<HitRecord> {
+        let r = random_f64(0.0, 1.0).sqrt();
+        let s = random_f64(0.0, 1.0);
+        Some(self.record(r * (1.0 - s), r * s, 0.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::Lambertian;
+
+    #[test]
+    fn test_mesh_interpolates_normals_and_uvs() {
+        let positions = vec![
+            Vec3::new(0.0, 0.0, 0.0),
+            Vec3::new(2.0, 0.0, 0.0),
+            Vec3::new(0.0, 2.0, 0.0),
+        ];
+        let normals = vec![
+            Vec3::new(0.0, 0.0, 1.0),
+            Vec3::new(1.0, 0.0, 1.0).normalize(),
+            Vec3::new(0.0, 0.0, 1.0),
+        ];
+        let mat = Arc::new(Lambertian::new(Vec3::ones()));
+        let mut mesh = TriangleMesh::new(positions, vec![[0, 1, 2]], mat).with_uvs(vec![
+            (0.0, 0.0),
+            (1.0, 0.0),
+            (0.0, 1.0),
+        ]);
+        mesh.normals = Some(normals);
+        let bvh = mesh.into_bvh();
+        let mut rec = HitRecord::new(Vec3::zero(), Vec3::zero(), 0.0, false, None);
+        let ray_ = Ray::new(Vec3::new(1.0, 0.5, 3.0), Vec3::new(0.0, 0.0, -1.0), 0.0);
+        assert!(bvh.hit(&ray_, Interval::new(0.001, f64::INFINITY), &mut rec));
+        assert!((rec.t - 3.0).abs() < 1e-9);
+        assert!((rec.u - 0.5).abs() < 1e-9 && (rec.v - 0.25).abs() < 1e-9);
+        assert!((rec.dpdu - Vec3::new(2.0, 0.0, 0.0)).length() < 1e-9);
+        let expected = (0.5 * Vec3::new(0.0, 0.0, 1.0)
+            + 0.5 * Vec3::new(1.0, 0.0, 1.0).normalize())
+        .normalize();
+        assert!((rec.normal - expected).length() < 1e-9);
+    }
+}
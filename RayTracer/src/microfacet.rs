@@ -0,0 +1,309 @@
+use crate::vec3::Vec3;
+use std::f64::consts::PI;
+
+// all directions below live in the local shading frame, z is the surface normal
+
+fn cos2_theta(w: Vec3) -> f64 {
+    w.z * w.z
+}
+
+fn sin2_theta(w: Vec3) -> f64 {
+    (1.0 - cos2_theta(w)).max(0.0)
+}
+
+fn tan2_theta(w: Vec3) -> f64 {
+    sin2_theta(w) / cos2_theta(w)
+}
+
+fn cos2_phi(w: Vec3) -> f64 {
+    let sin2 = sin2_theta(w);
+    if sin2 == 0.0 {
+        1.0
+    } else {
+        (w.x * w.x / sin2).clamp(0.0, 1.0)
+    }
+}
+
+fn sin2_phi(w: Vec3) -> f64 {
+    let sin2 = sin2_theta(w);
+    if sin2 == 0.0 {
+        0.0
+    } else {
+        (w.y * w.y / sin2).clamp(0.0, 1.0)
+    }
+}
+
+pub fn reflect_local(wo: Vec3, n: Vec3) -> Vec3 {
+    -wo + 2.0 * (wo * n) * n
+}
+
+/// refracts `wo` about `n`, `eta` is the relative index n_t / n_i.
+/// Returns None on total internal reflection.
+pub fn refract_local(wo: Vec3, n: Vec3, eta: f64) -> Option<Vec3> {
+    let cos_i = wo * n;
+    let sin2_i = (1.0 - cos_i * cos_i).max(0.0);
+    let sin2_t = sin2_i / (eta * eta);
+    if sin2_t >= 1.0 {
+        return None;
+    }
+    let cos_t = (1.0 - sin2_t).sqrt();
+    Some(-wo / eta + (cos_i / eta - cos_t) * n)
+}
+
+/// unpolarized Fresnel reflectance of a dielectric interface, `eta` = n_t / n_i
+pub fn fresnel_dielectric(cos_theta_i: f64, eta: f64) -> f64 {
+    let mut cos_i = cos_theta_i.clamp(-1.0, 1.0);
+    let mut eta = eta;
+    if cos_i < 0.0 {
+        eta = 1.0 / eta;
+        cos_i = -cos_i;
+    }
+    let sin2_i = 1.0 - cos_i * cos_i;
+    let sin2_t = sin2_i / (eta * eta);
+    if sin2_t >= 1.0 {
+        return 1.0;
+    }
+    let cos_t = (1.0 - sin2_t).max(0.0).sqrt();
+    let r_parl = (eta * cos_i - cos_t) / (eta * cos_i + cos_t);
+    let r_perp = (cos_i - eta * cos_t) / (cos_i + eta * cos_t);
+    (r_parl * r_parl + r_perp * r_perp) / 2.0
+}
+
+#[derive(Clone, Copy)]
+struct Complex {
+    re: f64,
+    im: f64,
+}
+
+impl Complex {
+    fn new(re: f64, im: f64) -> Self {
+        Self { re, im }
+    }
+
+    fn add(self, o: Complex) -> Complex {
+        Complex::new(self.re + o.re, self.im + o.im)
+    }
+
+    fn sub(self, o: Complex) -> Complex {
+        Complex::new(self.re - o.re, self.im - o.im)
+    }
+
+    fn mul(self, o: Complex) -> Complex {
+        Complex::new(
+            self.re * o.re - self.im * o.im,
+            self.re * o.im + self.im * o.re,
+        )
+    }
+
+    fn div(self, o: Complex) -> Complex {
+        let scale = 1.0 / (o.re * o.re + o.im * o.im);
+        Complex::new(
+            scale * (self.re * o.re + self.im * o.im),
+            scale * (self.im * o.re - self.re * o.im),
+        )
+    }
+
+    fn norm(self) -> f64 {
+        self.re * self.re + self.im * self.im
+    }
+
+    fn sqrt(self) -> Complex {
+        let n = self.norm().sqrt();
+        if n == 0.0 {
+            return Complex::new(0.0, 0.0);
+        }
+        let t1 = (0.5 * (n + self.re.abs())).sqrt();
+        let t2 = 0.5 * self.im / t1;
+        if self.re >= 0.0 {
+            Complex::new(t1, t2)
+        } else {
+            Complex::new(t2.abs(), t1.copysign(self.im))
+        }
+    }
+}
+
+fn fresnel_complex_channel(cos_theta_i: f64, eta: f64, k: f64) -> f64 {
+    let cos_i = cos_theta_i.clamp(0.0, 1.0);
+    let eta = Complex::new(eta, k);
+    let cos_i_c = Complex::new(cos_i, 0.0);
+    let sin2_i = Complex::new(1.0 - cos_i * cos_i, 0.0);
+    let sin2_t = sin2_i.div(eta.mul(eta));
+    let cos_t = Complex::new(1.0, 0.0).sub(sin2_t).sqrt();
+    let r_parl = eta.mul(cos_i_c).sub(cos_t).div(eta.mul(cos_i_c).add(cos_t));
+    let r_perp = cos_i_c.sub(eta.mul(cos_t)).div(cos_i_c.add(eta.mul(cos_t)));
+    (r_parl.norm() + r_perp.norm()) / 2.0
+}
+
+/// Fresnel reflectance of a conductor with complex index `eta + i k`, per RGB channel
+pub fn fresnel_complex(cos_theta_i: f64, eta: Vec3, k: Vec3) -> Vec3 {
+    Vec3::new(
+        fresnel_complex_channel(cos_theta_i, eta.x, k.x),
+        fresnel_complex_channel(cos_theta_i, eta.y, k.y),
+        fresnel_complex_channel(cos_theta_i, eta.z, k.z),
+    )
+}
+
+/// GGX / Trowbridge-Reitz microfacet distribution with Smith masking-shadowing
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TrowbridgeReitz {
+    pub alpha_x: f64,
+    pub alpha_y: f64,
+}
+
+impl TrowbridgeReitz {
+    pub fn new(alpha_x: f64, alpha_y: f64) -> Self {
+        Self { alpha_x, alpha_y }
+    }
+
+    pub fn new_by_roughness(roughness: f64) -> Self {
+        let alpha = TrowbridgeReitz::roughness_to_alpha(roughness);
+        Self::new(alpha, alpha)
+    }
+
+    /// perceptual roughness in [0, 1] to the distribution's alpha
+    pub fn roughness_to_alpha(roughness: f64) -> f64 {
+        let r = roughness.clamp(0.0, 1.0);
+        r * r
+    }
+
+    pub fn effectively_smooth(&self) -> bool {
+        self.alpha_x.max(self.alpha_y) < 1e-3
+    }
+
+    pub fn d(&self, wm: Vec3) -> f64 {
+        let tan2 = tan2_theta(wm);
+        if tan2.is_infinite() || tan2.is_nan() {
+            return 0.0;
+        }
+        let cos4 = cos2_theta(wm) * cos2_theta(wm);
+        if cos4 < 1e-16 {
+            return 0.0;
+        }
+        let e = tan2
+            * (cos2_phi(wm) / (self.alpha_x * self.alpha_x)
+                + sin2_phi(wm) / (self.alpha_y * self.alpha_y));
+        1.0 / (PI * self.alpha_x * self.alpha_y * cos4 * (1.0 + e) * (1.0 + e))
+    }
+
+    pub fn lambda(&self, w: Vec3) -> f64 {
+        let tan2 = tan2_theta(w);
+        if tan2.is_infinite() || tan2.is_nan() {
+            return 0.0;
+        }
+        let alpha2 =
+            cos2_phi(w) * self.alpha_x * self.alpha_x + sin2_phi(w) * self.alpha_y * self.alpha_y;
+        ((1.0 + alpha2 * tan2).sqrt() - 1.0) / 2.0
+    }
+
+    pub fn g1(&self, w: Vec3) -> f64 {
+        1.0 / (1.0 + self.lambda(w))
+    }
+
+    /// height-correlated masking-shadowing
+    pub fn g(&self, wo: Vec3, wi: Vec3) -> f64 {
+        1.0 / (1.0 + self.lambda(wo) + self.lambda(wi))
+    }
+
+    /// distribution of normals visible from `w`
+    pub fn d_visible(&self, w: Vec3, wm: Vec3) -> f64 {
+        let cos = w.z.abs();
+        if cos == 0.0 {
+            return 0.0;
+        }
+        self.g1(w) / cos * self.d(wm) * (w * wm).abs()
+    }
+
+    /// value and pdf of a rough dielectric interface for the pair (wo, wi), `eta` = n_t / n_i.
+    /// The pdf assumes reflection is chosen with probability F.
+    pub fn eval_dielectric(&self, wo: Vec3, wi: Vec3, eta: f64) -> (f64, f64) {
+        let (cos_o, cos_i) = (wo.z, wi.z);
+        if cos_o == 0.0 || cos_i == 0.0 {
+            return (0.0, 0.0);
+        }
+        let reflect = cos_o * cos_i > 0.0;
+        let mut wm = if reflect { wo + wi } else { wo + eta * wi };
+        if wm.near_zero() {
+            return (0.0, 0.0);
+        }
+        wm = wm.normalize();
+        if wm.z < 0.0 {
+            wm = -wm;
+        }
+        // discard back facing microfacets
+        if (wm * wi) * cos_i < 0.0 || (wm * wo) * cos_o < 0.0 {
+            return (0.0, 0.0);
+        }
+        let f = fresnel_dielectric(wo * wm, eta);
+        if reflect {
+            let value = self.d(wm) * self.g(wo, wi) * f / (4.0 * (cos_o * cos_i).abs());
+            let pdf = self.d_visible(wo, wm) / (4.0 * (wo * wm).abs()) * f;
+            (value, pdf)
+        } else {
+            let denom = wi * wm + (wo * wm) / eta;
+            let denom2 = denom * denom;
+            let value = self.d(wm)
+                * self.g(wo, wi)
+                * (1.0 - f)
+                * ((wi * wm) * (wo * wm) / (cos_i * cos_o * denom2)).abs();
+            let pdf = self.d_visible(wo, wm) * (wi * wm).abs() / denom2 * (1.0 - f);
+            (value, pdf)
+        }
+    }
+
+    /// samples a visible microfacet normal (Heitz 2018), `w` must be in the upper hemisphere
+    pub fn sample_wm(&self, w: Vec3, u1: f64, u2: f64) -> Vec3 {
+        let wh = Vec3::new(self.alpha_x * w.x, self.alpha_y * w.y, w.z).normalize();
+        let t1 = if wh.z < 0.99999 {
+            Vec3::new(0.0, 0.0, 1.0).cross(wh).normalize()
+        } else {
+            Vec3::new(1.0, 0.0, 0.0)
+        };
+        let t2 = wh.cross(t1);
+        let r = u1.sqrt();
+        let phi = 2.0 * PI * u2;
+        let p1 = r * phi.cos();
+        let mut p2 = r * phi.sin();
+        let h = (1.0 - p1 * p1).max(0.0).sqrt();
+        let s = 0.5 * (1.0 + wh.z);
+        p2 = (1.0 - s) * h + s * p2;
+        let pz = (1.0 - p1 * p1 - p2 * p2).max(0.0).sqrt();
+        let nh = p1 * t1 + p2 * t2 + pz * wh;
+        Vec3::new(self.alpha_x * nh.x, self.alpha_y * nh.y, nh.z.max(1e-6)).normalize()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fresnel_dielectric_normal_incidence() {
+        let eta = 1.5;
+        let r0 = ((eta - 1.0) / (eta + 1.0)) * ((eta - 1.0) / (eta + 1.0));
+        assert!((fresnel_dielectric(1.0, eta) - r0).abs() < 1e-12);
+        assert_eq!(fresnel_dielectric(0.1, 1.0 / eta), 1.0);
+    }
+
+    #[test]
+    fn test_fresnel_complex_matches_dielectric() {
+        for cos in [0.1, 0.5, 0.9, 1.0] {
+            let f = fresnel_complex(cos, Vec3::ones() * 1.5, Vec3::zero());
+            assert!((f.x - fresnel_dielectric(cos, 1.5)).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_ggx_normalized() {
+        // integral of D(wm) cos(theta_m) over the hemisphere is one
+        let distrib = TrowbridgeReitz::new(0.3, 0.3);
+        let n = 400;
+        let mut sum = 0.0;
+        for i in 0..n {
+            let theta = (i as f64 + 0.5) / n as f64 * 0.5 * PI;
+            let wm = Vec3::new(theta.sin(), 0.0, theta.cos());
+            let d_theta = 0.5 * PI / n as f64;
+            sum += distrib.d(wm) * theta.cos() * theta.sin() * d_theta * 2.0 * PI;
+        }
+        assert!((sum - 1.0).abs() < 1e-2);
+    }
+}
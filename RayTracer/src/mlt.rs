@@ -0,0 +1,302 @@
+use crate::camera::Camera;
+use crate::color::{luminance, write_color};
+use crate::distribution::Distribution1D;
+use crate::hit::Hittable;
+use crate::rt::{random_f64, set_sample_source, SampleSource};
+use crate::spectrum::spectrum_to_rgb;
+use crate::vec3::Vec3;
+use image::RgbImage;
+use indicatif::ProgressBar;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use rayon::prelude::*;
+use std::cell::RefCell;
+use std::f64::consts::PI;
+use std::rc::Rc;
+
+// primary sample space Metropolis light transport (Kelemen et al. 2002, in the
+// formulation of pbrt): a path is a function of the uniform numbers the path
+// tracer draws, so Markov chains mutate those numbers instead of the path.
+// While a chain runs, `rt::random_f64` reads them from the chain's sampler.
+
+/// the chain's sampler as the sample source of its thread, shared with the chain
+/// that accepts and rejects its mutations
+struct ChainSource(Rc<RefCell<MltSampler>>);
+
+impl SampleSource for ChainSource {
+    fn next_sample(&mut self) -> f64 {
+        self.0.borrow_mut().next()
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+struct PrimarySample {
+    value: f64,
+    last_modification: u64,
+    /// state before the current mutation, restored on rejection
+    value_backup: f64,
+    modify_backup: u64,
+}
+
+/// lazily mutated vector of primary samples
+struct MltSampler {
+    rng: StdRng,
+    sigma: f64,
+    large_step_probability: f64,
+    samples: Vec<PrimarySample>,
+    iteration: u64,
+    large_step: bool,
+    last_large_step: u64,
+    index: usize,
+}
+
+impl MltSampler {
+    fn new(seed: u64, sigma: f64, large_step_probability: f64) -> Self {
+        Self {
+            rng: StdRng::seed_from_u64(seed),
+            sigma,
+            large_step_probability,
+            samples: Vec::new(),
+            iteration: 0,
+            // the first path is a fresh one
+            large_step: true,
+            last_large_step: 0,
+            index: 0,
+        }
+    }
+
+    fn start_iteration(&mut self) {
+        self.iteration += 1;
+        self.large_step = self.rng.gen::<f64>() < self.large_step_probability;
+        self.index = 0;
+    }
+
+    fn accept(&mut self) {
+        if self.large_step {
+            self.last_large_step = self.iteration;
+        }
+    }
+
+    fn reject(&mut self) {
+        for x in self.samples.iter_mut() {
+            if x.last_modification == self.iteration {
+                x.value = x.value_backup;
+                x.last_modification = x.modify_backup;
+            }
+        }
+        self.iteration -= 1;
+    }
+
+    /// the sample at the current index, brought up to date with the mutations it missed
+    fn next(&mut self) -> f64 {
+        if self.index >= self.samples.len() {
+            // a number the chain has not used yet starts out uniform, as of the last iteration
+            let value = self.rng.gen();
+            self.samples.push(PrimarySample {
+                value,
+                last_modification: self.iteration.saturating_sub(1),
+                ..Default::default()
+            });
+        }
+        let x = &mut self.samples[self.index];
+        self.index += 1;
+        if x.last_modification < self.last_large_step {
+            x.value = self.rng.gen();
+            x.last_modification = self.last_large_step;
+        }
+        x.value_backup = x.value;
+        x.modify_backup = x.last_modification;
+        if self.large_step {
+            x.value = self.rng.gen();
+        } else {
+            // the small steps it missed add up to one wider gaussian step
+            let steps = (self.iteration - x.last_modification) as f64;
+            let (u1, u2): (f64, f64) = (self.rng.gen(), self.rng.gen());
+            let normal = (-2.0 * (1.0 - u1).ln()).sqrt() * (2.0 * PI * u2).cos();
+            x.value += normal * self.sigma * steps.sqrt();
+            x.value -= x.value.floor();
+        }
+        x.last_modification = self.iteration;
+        x.value
+    }
+}
+
+pub struct Pssmlt<'a> {
+    camera: &'a Camera,
+    chains: usize,
+    bootstrap: usize,
+    large_step_probability: f64,
+    sigma: f64,
+}
+
+impl<'a> Pssmlt<'a> {
+    pub fn new(
+        camera: &'a Camera,
+        chains: usize,
+        bootstrap: usize,
+        large_step_probability: f64,
+        sigma: f64,
+    ) -> Self {
+        Self {
+            camera,
+            chains: chains.max(1),
+            bootstrap: bootstrap.max(1),
+            large_step_probability,
+            sigma,
+        }
+    }
+
+    /// the path of the active sampler's numbers: film position and RGB radiance
+    fn radiance<T: Hittable>(&self, world: &T) -> (usize, usize, Vec3) {
+        let cam = self.camera;
+        let i = ((random_f64(0.0, 1.0) * cam.width as f64) as usize).min(cam.width - 1);
+        let j = ((random_f64(0.0, 1.0) * cam.height as f64) as usize).min(cam.height - 1);
+        let color = match cam.get_ray(i, j) {
+            Some(ray_) => {
+                let color = cam.ray_color(ray_, world, cam.max_depth);
+                if ray_.is_spectral() {
+                    spectrum_to_rgb(color, ray_.lambda)
+                } else {
+                    color
+                }
+            }
+            None => Vec3::zero(),
+        };
+        if !luminance(color).is_finite() {
+            return (i, j, Vec3::zero());
+        }
+        (i, j, color)
+    }
+
+    /// installs a fresh sampler with `seed` on this thread and makes its path
+    fn start<T: Hittable>(
+        &self,
+        world: &T,
+        seed: u64,
+    ) -> (Rc<RefCell<MltSampler>>, (usize, usize, Vec3)) {
+        let sampler = Rc::new(RefCell::new(MltSampler::new(
+            seed,
+            self.sigma,
+            self.large_step_probability,
+        )));
+        set_sample_source(Some(Box::new(ChainSource(sampler.clone()))));
+        (sampler, self.radiance(world))
+    }
+
+    pub fn render<T: Hittable>(&self, world: &T, img: &mut RgbImage, bar: &ProgressBar) {
+        let cam = self.camera;
+        let (width, height) = (cam.width, cam.height);
+        // bootstrap: independent paths estimate the image brightness b and seed the chains
+        let weights: Vec<f64> = (0..self.bootstrap)
+            .into_par_iter()
+            .map(|seed| {
+                let (_, (_, _, color)) = self.start(world, seed as u64);
+                set_sample_source(None);
+                luminance(color).max(0.0)
+            })
+            .collect();
+        let b = weights.iter().sum::<f64>() / self.bootstrap as f64;
+        if b <= 0.0 {
+            return;
+        }
+        let starts = Distribution1D::new(weights);
+
+        let mutations = (cam.samples_per_pixel * width * height) as u64;
+        let p_large = self.large_step_probability;
+        bar.set_length(self.chains as u64);
+        let film = (0..self.chains)
+            .into_par_iter()
+            .fold(
+                || vec![Vec3::zero(); width * height],
+                |mut film, chain| {
+                    let n = mutations / self.chains as u64
+                        + (chain < (mutations % self.chains as u64) as usize) as u64;
+                    let mut rng = StdRng::seed_from_u64(self.bootstrap as u64 + chain as u64);
+                    let (_, _, seed) = starts.sample_continuous(rng.gen());
+                    let (sampler, (mut i, mut j, mut current)) = self.start(world, seed as u64);
+                    // chains starting on the same path must not mutate it the same way
+                    sampler.borrow_mut().rng = StdRng::seed_from_u64(rng.gen());
+                    for _ in 0..n {
+                        sampler.borrow_mut().start_iteration();
+                        let (pi, pj, proposed) = self.radiance(world);
+                        let (y_current, y_proposed) = (luminance(current), luminance(proposed));
+                        let accept = if y_current > 0.0 {
+                            (y_proposed / y_current).clamp(0.0, 1.0)
+                        } else {
+                            1.0
+                        };
+                        // both states are splatted by their expected weight, large steps
+                        // also count as independent samples of the image
+                        let large = sampler.borrow().large_step as u8 as f64;
+                        if y_proposed > 0.0 {
+                            film[pj * width + pi] +=
+                                proposed * ((accept + large) / (y_proposed / b + p_large));
+                        }
+                        if y_current > 0.0 {
+                            film[j * width + i] +=
+                                current * ((1.0 - accept) / (y_current / b + p_large));
+                        }
+                        if rng.gen::<f64>() < accept {
+                            (i, j, current) = (pi, pj, proposed);
+                            sampler.borrow_mut().accept();
+                        } else {
+                            sampler.borrow_mut().reject();
+                        }
+                    }
+                    set_sample_source(None);
+                    bar.inc(1);
+                    film
+                },
+            )
+            .reduce(
+                || vec![Vec3::zero(); width * height],
+                |mut a, b| {
+                    for (x, y) in a.iter_mut().zip(b) {
+                        *x += y;
+                    }
+                    a
+                },
+            );
+        bar.finish();
+        let scale = 1.0 / cam.samples_per_pixel as f64;
+        for (k, color) in film.iter().enumerate() {
+            write_color(*color * scale, img, k % width, k / width);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chain_source_feeds_random_f64() {
+        let sampler = Rc::new(RefCell::new(MltSampler::new(3, 0.01, 0.3)));
+        set_sample_source(Some(Box::new(ChainSource(sampler.clone()))));
+        let drawn: Vec<f64> = (0..4).map(|_| random_f64(0.0, 2.0)).collect();
+        set_sample_source(None);
+        let samples: Vec<f64> = sampler.borrow().samples.iter().map(|x| x.value).collect();
+        assert_eq!(drawn, samples.iter().map(|u| 2.0 * u).collect::<Vec<_>>());
+        // without a source the thread draws plain random numbers again
+        random_f64(0.0, 1.0);
+        assert_eq!(sampler.borrow().samples.len(), 4);
+    }
+
+    #[test]
+    fn test_rejected_mutation_is_replayed() {
+        let mut sampler = MltSampler::new(7, 0.01, 0.3);
+        let first: Vec<f64> = (0..5).map(|_| sampler.next()).collect();
+        for _ in 0..20 {
+            sampler.start_iteration();
+            let mutated: Vec<f64> = (0..5).map(|_| sampler.next()).collect();
+            assert!(mutated.iter().all(|u| (0.0..1.0).contains(u)));
+            sampler.reject();
+        }
+        // every proposal was rejected, so the chain still sits on its first path
+        sampler.start_iteration();
+        sampler.large_step = false;
+        sampler.sigma = 0.0;
+        let again: Vec<f64> = (0..5).map(|_| sampler.next()).collect();
+        assert_eq!(first, again);
+    }
+}
@@ -0,0 +1,42 @@
+use crate::vec3::Vec3;
+
+/// orthonormal basis whose w axis is aligned with the given normal
+#[derive(Clone, Debug, PartialEq, Copy)]
+pub struct Onb {
+    pub u: Vec3,
+    pub v: Vec3,
+    pub w: Vec3,
+}
+
+impl Onb {
+    pub fn new(n: Vec3) -> Self {
+        let w = n.normalize();
+        let a = if w.x.abs() > 0.9 {
+            Vec3::new(0.0, 1.0, 0.0)
+        } else {
+            Vec3::new(1.0, 0.0, 0.0)
+        };
+        let v = w.cross(a).normalize();
+        let u = w.cross(v);
+        Self { u, v, w }
+    }
+
+    pub fn new_by_tangent(n: Vec3, tangent: Vec3) -> Self {
+        let w = n.normalize();
+        let t = tangent - (tangent * w) * w;
+        if t.near_zero() {
+            return Onb::new(n);
+        }
+        let u = t.normalize();
+        let v = w.cross(u);
+        Self { u, v, w }
+    }
+
+    pub fn local(&self, a: Vec3) -> Vec3 {
+        a.x * self.u + a.y * self.v + a.z * self.w
+    }
+
+    pub fn to_local(self, a: Vec3) -> Vec3 {
+        Vec3::new(a * self.u, a * self.v, a * self.w)
+    }
+}
@@ -1,50 +1,129 @@
-use crate::rt::{random_f64, random_int};
+use crate::rt::random_f64;
 use crate::vec3::Vec3;
-use rand::Rng;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 static POINT_COUNT: i32 = 256;
+
+/// the 12 edge directions of a cube, for improved and simplex noise
+const GRAD3: [[f64; 3]; 12] = [
+    [1.0, 1.0, 0.0],
+    [-1.0, 1.0, 0.0],
+    [1.0, -1.0, 0.0],
+    [-1.0, -1.0, 0.0],
+    [1.0, 0.0, 1.0],
+    [-1.0, 0.0, 1.0],
+    [1.0, 0.0, -1.0],
+    [-1.0, 0.0, -1.0],
+    [0.0, 1.0, 1.0],
+    [0.0, -1.0, 1.0],
+    [0.0, 1.0, -1.0],
+    [0.0, -1.0, -1.0],
+];
+
+/// quintic fade of improved noise, with zero second derivative at the lattice
+fn fade(t: f64) -> f64 {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+fn lerp(t: f64, a: f64, b: f64) -> f64 {
+    a + t * (b - a)
+}
+
+/// the noise function `Perlin::sample` evaluates
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum NoiseBasis {
+    /// random unit gradients with cubic smoothing, as `Perlin::noise`
+    Classic,
+    /// Ken Perlin's 2002 noise with quintic fade and the 12 cube edge gradients
+    Improved,
+    /// improved noise repeating every `period` lattice cells along each axis
+    Periodic([i32; 3]),
+    /// 2D simplex noise over the xy plane
+    Simplex2,
+    Simplex3,
+    /// 4D simplex noise with this value for the fourth coordinate, like a point in time
+    Simplex4(f64),
+}
+
+impl NoiseBasis {
+    /// the basis for the noise at `factor` times the lookup point: periods are stretched
+    /// to keep tiling, which only holds when `factor` times them is whole, and the fourth
+    /// coordinate is scaled along
+    pub fn scaled(self, factor: f64) -> Self {
+        match self {
+            NoiseBasis::Periodic(period) => {
+                NoiseBasis::Periodic(period.map(|n| (n as f64 * factor).round() as i32))
+            }
+            NoiseBasis::Simplex4(w) => NoiseBasis::Simplex4(factor * w),
+            basis => basis,
+        }
+    }
+}
+
 pub struct Perlin {
     randvec: Vec<Vec3>,
     perm_x: Vec<i32>,
     perm_y: Vec<i32>,
     perm_z: Vec<i32>,
+    /// permutation repeated twice, hashing lattice points of the newer noises
+    perm: Vec<usize>,
 }
 
 impl Perlin {
-    fn generate_perm() -> Vec<i32> {
+    fn generate_perm(uniform: &mut impl FnMut() -> f64) -> Vec<i32> {
         let mut p: Vec<i32> = (0..POINT_COUNT).collect();
-        Perlin::permute(&mut p);
+        Perlin::permute(&mut p, uniform);
         p
     }
 
-    fn permute(p: &mut Vec<i32>) {
+    fn permute(p: &mut [i32], uniform: &mut impl FnMut() -> f64) {
         for i in (1..p.len()).rev() {
-            let target = random_int(0, i as i32);
-            p.swap(i, target as usize);
+            let target = ((uniform() * (i + 1) as f64) as usize).min(i);
+            p.swap(i, target);
         }
     }
 
-    pub fn new() -> Self {
+    /// tables from the uniform numbers in [0, 1) that `uniform` hands out
+    fn from_uniform(mut uniform: impl FnMut() -> f64) -> Self {
         let randvec: Vec<_> = (0..POINT_COUNT)
-            .map(|_| {
-                Vec3::new(
-                    random_f64(0.0, 1.0),
-                    random_f64(0.0, 1.0),
-                    random_f64(0.0, 1.0),
-                )
+            .map(|_| loop {
+                let v = Vec3::new(
+                    2.0 * uniform() - 1.0,
+                    2.0 * uniform() - 1.0,
+                    2.0 * uniform() - 1.0,
+                );
+                let length = v.squared_length();
+                if 1e-8 < length && length <= 1.0 {
+                    break v / length.sqrt();
+                }
             })
             .collect();
-        let perm_x = Perlin::generate_perm();
-        let perm_y = Perlin::generate_perm();
-        let perm_z = Perlin::generate_perm();
+        let perm_x = Perlin::generate_perm(&mut uniform);
+        let perm_y = Perlin::generate_perm(&mut uniform);
+        let perm_z = Perlin::generate_perm(&mut uniform);
+        let once = Perlin::generate_perm(&mut uniform);
+        let perm = once.iter().chain(&once).map(|&i| i as usize).collect();
 
         Self {
             randvec,
             perm_x,
             perm_y,
             perm_z,
+            perm,
         }
     }
 
+    /// different noise each time, from the renderer's random numbers
+    pub fn new() -> Self {
+        Perlin::from_uniform(|| random_f64(0.0, 1.0))
+    }
+
+    /// the same noise for the same seed, on every run
+    pub fn new_seeded(seed: u64) -> Self {
+        let mut rng = StdRng::seed_from_u64(seed);
+        Perlin::from_uniform(move || rng.gen())
+    }
+
     pub fn noise(&self, p: Vec3) -> f64 {
         let u = p.x - p.x.floor();
         let v = p.y - p.y.floor();
@@ -101,4 +180,229 @@ impl Perlin {
         }
         accum.abs()
     }
+
+    /// noise of the given kind at p, about in [-1, 1]
+    pub fn sample(&self, basis: NoiseBasis, p: Vec3) -> f64 {
+        match basis {
+            NoiseBasis::Classic => self.noise(p),
+            NoiseBasis::Improved => self.improved(p, [256; 3]),
+            NoiseBasis::Periodic(period) => self.improved(p, period),
+            NoiseBasis::Simplex2 => self.simplex2(p.x, p.y),
+            NoiseBasis::Simplex3 => self.simplex3(p),
+            NoiseBasis::Simplex4(w) => self.simplex4(p, w),
+        }
+    }
+
+    fn hash3(&self, i: i32, j: i32, k: i32) -> usize {
+        let p = &self.perm;
+        p[p[p[(i & 255) as usize] + (j & 255) as usize] + (k & 255) as usize]
+    }
+
+    /// improved noise over a lattice that repeats after `period` cells, up to 256
+    fn improved(&self, p: Vec3, period: [i32; 3]) -> f64 {
+        let (i, j, k) = (p.x.floor() as i32, p.y.floor() as i32, p.z.floor() as i32);
+        let (x, y, z) = (p.x - i as f64, p.y - j as f64, p.z - k as f64);
+        let (u, v, w) = (fade(x), fade(y), fade(z));
+        let wrap = |c: i32, axis: usize| c.rem_euclid(period[axis].clamp(1, 256));
+        let corner = |di: i32, dj: i32, dk: i32| {
+            let h = self.hash3(wrap(i + di, 0), wrap(j + dj, 1), wrap(k + dk, 2));
+            let g = GRAD3[h % 12];
+            g[0] * (x - di as f64) + g[1] * (y - dj as f64) + g[2] * (z - dk as f64)
+        };
+        lerp(
+            w,
+            lerp(
+                v,
+                lerp(u, corner(0, 0, 0), corner(1, 0, 0)),
+                lerp(u, corner(0, 1, 0), corner(1, 1, 0)),
+            ),
+            lerp(
+                v,
+                lerp(u, corner(0, 0, 1), corner(1, 0, 1)),
+                lerp(u, corner(0, 1, 1), corner(1, 1, 1)),
+            ),
+        )
+    }
+
+    fn simplex2(&self, x: f64, y: f64) -> f64 {
+        let f2 = 0.5 * (3.0_f64.sqrt() - 1.0);
+        let g2 = (3.0 - 3.0_f64.sqrt()) / 6.0;
+        // skew into the lattice of squares made of two triangles each
+        let s = (x + y) * f2;
+        let (i, j) = ((x + s).floor() as i32, (y + s).floor() as i32);
+        let t = (i + j) as f64 * g2;
+        let (x0, y0) = (x - (i as f64 - t), y - (j as f64 - t));
+        let (i1, j1) = if x0 > y0 { (1, 0) } else { (0, 1) };
+        let corners = [
+            (0, 0, x0, y0),
+            (i1, j1, x0 - i1 as f64 + g2, y0 - j1 as f64 + g2),
+            (1, 1, x0 - 1.0 + 2.0 * g2, y0 - 1.0 + 2.0 * g2),
+        ];
+        let p = &self.perm;
+        let sum: f64 = corners
+            .iter()
+            .map(|&(di, dj, dx, dy)| {
+                let t = 0.5 - dx * dx - dy * dy;
+                if t < 0.0 {
+                    return 0.0;
+                }
+                let h = p[((i + di) & 255) as usize + p[((j + dj) & 255) as usize]];
+                let g = GRAD3[h % 12];
+                t * t * t * t * (g[0] * dx + g[1] * dy)
+            })
+            .sum();
+        70.0 * sum
+    }
+
+    fn simplex3(&self, q: Vec3) -> f64 {
+        let (f3, g3) = (1.0 / 3.0, 1.0 / 6.0);
+        let s = (q.x + q.y + q.z) * f3;
+        let (i, j, k) = (
+            (q.x + s).floor() as i32,
+            (q.y + s).floor() as i32,
+            (q.z + s).floor() as i32,
+        );
+        let t = (i + j + k) as f64 * g3;
+        let d0 = [
+            q.x - (i as f64 - t),
+            q.y - (j as f64 - t),
+            q.z - (k as f64 - t),
+        ];
+        // the corners of the tetrahedron around the point, by the order of its coordinates
+        let (o1, o2) = if d0[0] >= d0[1] {
+            if d0[1] >= d0[2] {
+                ([1, 0, 0], [1, 1, 0])
+            } else if d0[0] >= d0[2] {
+                ([1, 0, 0], [1, 0, 1])
+            } else {
+                ([0, 0, 1], [1, 0, 1])
+            }
+        } else if d0[1] < d0[2] {
+            ([0, 0, 1], [0, 1, 1])
+        } else if d0[0] < d0[2] {
+            ([0, 1, 0], [0, 1, 1])
+        } else {
+            ([0, 1, 0], [1, 1, 0])
+        };
+        let sum: f64 = [[0, 0, 0], o1, o2, [1, 1, 1]]
+            .iter()
+            .enumerate()
+            .map(|(n, o)| {
+                let d = [0, 1, 2].map(|a| d0[a] - o[a] as f64 + n as f64 * g3);
+                let t = 0.6 - d[0] * d[0] - d[1] * d[1] - d[2] * d[2];
+                if t < 0.0 {
+                    return 0.0;
+                }
+                let g = GRAD3[self.hash3(i + o[0], j + o[1], k + o[2]) % 12];
+                t * t * t * t * (g[0] * d[0] + g[1] * d[1] + g[2] * d[2])
+            })
+            .sum();
+        32.0 * sum
+    }
+
+    fn simplex4(&self, q: Vec3, w: f64) -> f64 {
+        let f4 = (5.0_f64.sqrt() - 1.0) / 4.0;
+        let g4 = (5.0 - 5.0_f64.sqrt()) / 20.0;
+        let x = [q.x, q.y, q.z, w];
+        let s = x.iter().sum::<f64>() * f4;
+        let cell = x.map(|c| (c + s).floor() as i32);
+        let t = cell.iter().sum::<i32>() as f64 * g4;
+        let d0 = [0, 1, 2, 3].map(|a| x[a] - (cell[a] as f64 - t));
+        // rank of each coordinate among the others picks the corners of the simplex
+        let mut rank = [0; 4];
+        for a in 0..4 {
+            for b in a + 1..4 {
+                if d0[a] > d0[b] {
+                    rank[a] += 1;
+                } else {
+                    rank[b] += 1;
+                }
+            }
+        }
+        let p = &self.perm;
+        let sum: f64 = (0..5)
+            .map(|n| {
+                // the n-th corner steps along the n coordinates of the highest rank
+                let o = rank.map(|r| if r >= 4 - n { 1 } else { 0 });
+                let d = [0, 1, 2, 3].map(|a| d0[a] - o[a] as f64 + n as f64 * g4);
+                let t = 0.6 - d.iter().map(|c| c * c).sum::<f64>();
+                if t < 0.0 {
+                    return 0.0;
+                }
+                let c = [0, 1, 2, 3].map(|a| ((cell[a] + o[a]) & 255) as usize);
+                let h = p[c[0] + p[c[1] + p[c[2] + p[c[3]]]]] % 32;
+                // the 32 directions to the edge midpoints of a tesseract, with a zero
+                // in coordinate h / 8 and the signs of the others from h % 8
+                let (zero, signs) = (h / 8, h % 8);
+                let mut dot = 0.0;
+                let mut bit = 0;
+                for (a, &da) in d.iter().enumerate() {
+                    if a != zero {
+                        dot += if signs >> bit & 1 == 0 { da } else { -da };
+                        bit += 1;
+                    }
+                }
+                t * t * t * t * dot
+            })
+            .sum();
+        27.0 * sum
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seeded_noise_repeats() {
+        let (a, b, c) = (
+            Perlin::new_seeded(1),
+            Perlin::new_seeded(1),
+            Perlin::new_seeded(2),
+        );
+        let p = Vec3::new(0.3, 1.7, -2.2);
+        for basis in [
+            NoiseBasis::Classic,
+            NoiseBasis::Improved,
+            NoiseBasis::Simplex3,
+        ] {
+            assert_eq!(a.sample(basis, p), b.sample(basis, p));
+            assert_ne!(a.sample(basis, p), c.sample(basis, p));
+        }
+    }
+
+    #[test]
+    fn test_noise_ranges_and_tiling() {
+        let noise = Perlin::new_seeded(5);
+        let period = [4, 3, 5];
+        let bases = [
+            NoiseBasis::Classic,
+            NoiseBasis::Improved,
+            NoiseBasis::Periodic(period),
+            NoiseBasis::Simplex2,
+            NoiseBasis::Simplex3,
+            NoiseBasis::Simplex4(0.7),
+        ];
+        let mut rng = StdRng::seed_from_u64(9);
+        for _ in 0..2000 {
+            let p = Vec3::new(
+                rng.gen_range(-20.0..20.0),
+                rng.gen_range(-20.0..20.0),
+                rng.gen_range(-20.0..20.0),
+            );
+            for basis in bases {
+                assert!(noise.sample(basis, p).abs() <= 1.1);
+            }
+            let shifted = p + Vec3::new(4.0, -6.0, 15.0);
+            let (a, b) = (
+                noise.sample(NoiseBasis::Periodic(period), p),
+                noise.sample(NoiseBasis::Periodic(period), shifted),
+            );
+            assert!((a - b).abs() < 1e-9);
+        }
+        // gradient noise vanishes on the lattice
+        let corner = Vec3::new(3.0, -2.0, 7.0);
+        assert!(noise.sample(NoiseBasis::Improved, corner).abs() < 1e-12);
+        assert!(noise.sample(NoiseBasis::Classic, corner).abs() < 1e-12);
+    }
 }
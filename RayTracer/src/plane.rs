@@ -0,0 +1,103 @@
+use crate::aabb::AABB;
+use crate::hit::{HitRecord, Hittable};
+use crate::interval::Interval;
+use crate::material::Material;
+use crate::onb::Onb;
+use crate::ray::Ray;
+use crate::vec3::Vec3;
+use std::sync::Arc;
+
+/// infinite plane through `point`, the uv repeat every world unit along the plane
+pub struct Plane {
+    point: Vec3,
+    normal: Vec3,
+    tangent: Vec3,
+    bitangent: Vec3,
+    mat: Arc<dyn Material>,
+    bbox: AABB,
+}
+
+impl Plane {
+    pub fn new(point: Vec3, normal: Vec3, mat: Arc<dyn Material>) -> Self {
+        let frame = Onb::new(normal);
+        let normal = frame.w;
+        // only axis aligned planes have a finite side to their box
+        let mut lo = Vec3::new(f64::NEG_INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY);
+        let mut hi = Vec3::new(f64::INFINITY, f64::INFINITY, f64::INFINITY);
+        for i in 0..3 {
+            if normal[i].abs() > 1.0 - 1e-12 {
+                lo[i] = point[i];
+                hi[i] = point[i];
+            }
+        }
+        Self {
+            point,
+            normal,
+            tangent: frame.u,
+            bitangent: normal.cross(frame.u),
+            mat,
+            bbox: AABB::new_by_point(lo, hi),
+        }
+    }
+}
+
+impl Hittable for Plane {
+    fn hit(&self, ray_: &Ray, ray_t: Interval, rec: &mut HitRecord) -> bool {
+        let denom = self.normal * ray_.dir;
+        if denom.abs() < 1e-8 {
+            return false;
+        }
+        let t = (self.normal * (self.point - ray_.orig)) / denom;
+        if !ray_t.contains(t) {
+            return false;
+        }
+        rec.t = t;
+        rec.point = ray_.at(t);
+        let local = rec.point - self.point;
+        rec.u = (local * self.tangent).rem_euclid(1.0);
+        rec.v = (local * self.bitangent).rem_euclid(1.0);
+        rec.dpdu = self.tangent;
+        rec.dpdv = self.bitangent;
+        rec.mat_ptr = Some(self.mat.clone());
+        rec.set_face_normal(ray_, self.normal);
+        true
+    }
+
+    fn bounding_box(&self) -> AABB {
+        self.bbox
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::Lambertian;
+
+    #[test]
+    fn test_plane_hit_and_bounds() {
+        let mat = Arc::new(Lambertian::new(Vec3::ones()));
+        let plane = Plane::new(Vec3::zero(), Vec3::new(0.0, 2.0, 0.0), mat.clone());
+        let all = Interval::new(0.001, f64::INFINITY);
+        let mut rec = HitRecord::new(Vec3::zero(), Vec3::zero(), 0.0, false, None);
+        let down = Ray::new(Vec3::new(0.0, 1.0, 0.0), Vec3::new(1.0, -1.0, 0.0), 0.0);
+        assert!(plane.hit(&down, all, &mut rec));
+        assert!((rec.t - 1.0).abs() < 1e-9);
+        assert!((rec.point - Vec3::new(1.0, 0.0, 0.0)).length() < 1e-9);
+        assert!(rec.front_face);
+        assert!((rec.normal - Vec3::new(0.0, 1.0, 0.0)).length() < 1e-9);
+        assert!((0.0..1.0).contains(&rec.u) && (0.0..1.0).contains(&rec.v));
+        let up = Ray::new(Vec3::new(3.0, -2.0, 7.0), Vec3::new(0.0, 1.0, 0.0), 0.0);
+        assert!(plane.hit(&up, all, &mut rec));
+        assert!(!rec.front_face);
+        assert!((rec.normal - Vec3::new(0.0, -1.0, 0.0)).length() < 1e-9);
+        let parallel = Ray::new(Vec3::new(0.0, 1.0, 0.0), Vec3::new(1.0, 0.0, 1.0), 0.0);
+        assert!(!plane.hit(&parallel, all, &mut rec));
+
+        // an axis aligned plane has a flat side to its box, a tilted one none
+        let bbox = plane.bounding_box();
+        assert!(bbox.is_unbounded());
+        assert!(bbox.y.contains(0.0) && bbox.y.size() < 1e-3);
+        let tilted = Plane::new(Vec3::zero(), Vec3::new(1.0, 1.0, 0.0), mat);
+        assert_eq!(tilted.bounding_box().y.max, f64::INFINITY);
+    }
+}
@@ -0,0 +1,388 @@
+use crate::perlin::{NoiseBasis, Perlin};
+use crate::rt::random_int;
+use crate::texture::Texture;
+use crate::transform::Transform;
+use crate::vec3::Vec3;
+use std::sync::Arc;
+
+// The patterns below are gray textures with values in [0, 1], to be turned into colors by
+// a `ColorRamp`. Each one is placed in the world by its `transform`, so the lookup point
+// goes through the inverse of it. Those built on noise look it up in `basis` from their
+// own `noise`, which may be swapped for a seeded one to get the same pattern every run,
+// as `Worley` does with its `seed`.
+
+/// sum of `octaves` layers of `layer(noise)`, each `lacunarity` times finer and `gain`
+/// times weaker than the last, divided by the sum of the weights
+fn octave_sum(
+    noise: &Perlin,
+    basis: NoiseBasis,
+    p: Vec3,
+    octaves: usize,
+    lacunarity: f64,
+    gain: f64,
+    layer: impl Fn(f64) -> f64,
+) -> f64 {
+    let (mut sum, mut total, mut weight, mut q, mut basis) = (0.0, 0.0, 1.0, p, basis);
+    for _ in 0..octaves.max(1) {
+        sum += weight * layer(noise.sample(basis, q));
+        total += weight;
+        weight *= gain;
+        q = lacunarity * q;
+        basis = basis.scaled(lacunarity);
+    }
+    sum / total
+}
+
+fn gray(value: f64) -> Vec3 {
+    value.clamp(0.0, 1.0) * Vec3::ones()
+}
+
+/// fractional Brownian motion, layers of noise around 0.5
+pub struct Fbm {
+    pub noise: Perlin,
+    pub basis: NoiseBasis,
+    pub octaves: usize,
+    pub lacunarity: f64,
+    pub gain: f64,
+    pub transform: Transform,
+}
+
+impl Fbm {
+    pub fn new(octaves: usize) -> Self {
+        Self {
+            noise: Perlin::new(),
+            basis: NoiseBasis::Classic,
+            octaves,
+            lacunarity: 2.0,
+            gain: 0.5,
+            transform: Transform::identity(),
+        }
+    }
+}
+
+impl Texture for Fbm {
+    fn value(&self, _u: f64, _v: f64, p: Vec3) -> Vec3 {
+        let p = self.transform.inverse_point(p);
+        gray(
+            0.5 + 0.5
+                * octave_sum(
+                    &self.noise,
+                    self.basis,
+                    p,
+                    self.octaves,
+                    self.lacunarity,
+                    self.gain,
+                    |n| n,
+                ),
+        )
+    }
+}
+
+/// layers of the absolute value of noise, creased where the noise crosses zero
+pub struct Turbulence {
+    pub noise: Perlin,
+    pub basis: NoiseBasis,
+    pub octaves: usize,
+    pub lacunarity: f64,
+    pub gain: f64,
+    pub transform: Transform,
+}
+
+impl Turbulence {
+    pub fn new(octaves: usize) -> Self {
+        Self {
+            noise: Perlin::new(),
+            basis: NoiseBasis::Classic,
+            octaves,
+            lacunarity: 2.0,
+            gain: 0.5,
+            transform: Transform::identity(),
+        }
+    }
+}
+
+impl Texture for Turbulence {
+    fn value(&self, _u: f64, _v: f64, p: Vec3) -> Vec3 {
+        let p = self.transform.inverse_point(p);
+        gray(octave_sum(
+            &self.noise,
+            self.basis,
+            p,
+            self.octaves,
+            self.lacunarity,
+            self.gain,
+            f64::abs,
+        ))
+    }
+}
+
+/// Musgrave's ridged multifractal: sharp ridges along the zeros of the noise, with the
+/// finer layers showing mostly on the ridges of the coarser ones
+pub struct RidgedMultifractal {
+    pub noise: Perlin,
+    pub basis: NoiseBasis,
+    pub octaves: usize,
+    pub lacunarity: f64,
+    pub gain: f64,
+    /// height of the ridges before they are squared, about 1
+    pub offset: f64,
+    pub transform: Transform,
+}
+
+impl RidgedMultifractal {
+    pub fn new(octaves: usize) -> Self {
+        Self {
+            noise: Perlin::new(),
+            basis: NoiseBasis::Classic,
+            octaves,
+            lacunarity: 2.0,
+            gain: 0.5,
+            offset: 1.0,
+            transform: Transform::identity(),
+        }
+    }
+}
+
+impl Texture for RidgedMultifractal {
+    fn value(&self, _u: f64, _v: f64, p: Vec3) -> Vec3 {
+        let mut q = self.transform.inverse_point(p);
+        let (mut sum, mut total, mut amplitude, mut weight) = (0.0, 0.0, 1.0, 1.0);
+        let mut basis = self.basis;
+        for _ in 0..self.octaves.max(1) {
+            let ridge = (self.offset - self.noise.sample(basis, q).abs()).max(0.0);
+            let signal = ridge * ridge * weight;
+            sum += amplitude * signal;
+            total += amplitude * self.offset * self.offset;
+            weight = (2.0 * signal).clamp(0.0, 1.0);
+            amplitude *= self.gain;
+            q = self.lacunarity * q;
+            basis = basis.scaled(self.lacunarity);
+        }
+        gray(sum / total)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum WorleyFeature {
+    /// distance to the closest feature point, dark spots in bright cells
+    F1,
+    /// distance to the second closest one
+    F2,
+    /// bright cell walls between the points
+    F2MinusF1,
+}
+
+/// Worley's cellular noise over one feature point in each unit cell
+pub struct Worley {
+    /// places the feature points, the same seed gives the same cells on every run
+    pub seed: u64,
+    pub feature: WorleyFeature,
+    /// how far the points may stray from the cell centers, 1 for the whole cell
+    pub jitter: f64,
+    pub transform: Transform,
+}
+
+impl Worley {
+    /// different cells each time, from the renderer's random numbers
+    pub fn new(feature: WorleyFeature) -> Self {
+        Worley::new_seeded(feature, random_int(0, i32::MAX) as u64)
+    }
+
+    pub fn new_seeded(feature: WorleyFeature, seed: u64) -> Self {
+        Self {
+            seed,
+            feature,
+            jitter: 1.0,
+            transform: Transform::identity(),
+        }
+    }
+
+    /// the feature point of cell (i, j, k)
+    fn point(&self, i: i64, j: i64, k: i64) -> Vec3 {
+        let mut h = self.seed
+            ^ (i as u64).wrapping_mul(0x9e37_79b9_7f4a_7c15)
+            ^ (j as u64).wrapping_mul(0xc2b2_ae3d_27d4_eb4f)
+            ^ (k as u64).wrapping_mul(0x1656_67b1_9e37_79f9);
+        // a murmur finalizer mixes the bits, 21 of them go to each coordinate
+        h ^= h >> 33;
+        h = h.wrapping_mul(0xff51_afd7_ed55_8ccd);
+        h ^= h >> 33;
+        h = h.wrapping_mul(0xc4ce_b9fe_1a85_ec53);
+        h ^= h >> 33;
+        let unit = |shift: u32| ((h >> shift) & 0x1f_ffff) as f64 / 0x20_0000 as f64;
+        let offset = Vec3::new(unit(0), unit(21), unit(42)) - 0.5 * Vec3::ones();
+        Vec3::new(i as f64, j as f64, k as f64) + 0.5 * Vec3::ones() + self.jitter * offset
+    }
+
+    /// distances to the closest and the second closest feature point
+    pub fn distances(&self, p: Vec3) -> (f64, f64) {
+        let (ci, cj, ck) = (p.x.floor() as i64, p.y.floor() as i64, p.z.floor() as i64);
+        let (mut f1, mut f2) = (f64::INFINITY, f64::INFINITY);
+        for i in ci - 1..=ci + 1 {
+            for j in cj - 1..=cj + 1 {
+                for k in ck - 1..=ck + 1 {
+                    let d = (self.point(i, j, k) - p).length();
+                    if d < f1 {
+                        f2 = f1;
+                        f1 = d;
+                    } else if d < f2 {
+                        f2 = d;
+                    }
+                }
+            }
+        }
+        (f1, f2)
+    }
+}
+
+impl Texture for Worley {
+    fn value(&self, _u: f64, _v: f64, p: Vec3) -> Vec3 {
+        let (f1, f2) = self.distances(self.transform.inverse_point(p));
+        gray(match self.feature {
+            WorleyFeature::F1 => f1,
+            WorleyFeature::F2 => f2,
+            WorleyFeature::F2MinusF1 => f2 - f1,
+        })
+    }
+}
+
+/// growth rings around the y axis, bright at the start of each ring and fading out
+pub struct Wood {
+    pub noise: Perlin,
+    pub basis: NoiseBasis,
+    /// rings per unit of radius
+    pub rings: f64,
+    /// how far noise shifts the rings, in rings
+    pub turbulence: f64,
+    pub octaves: usize,
+    pub transform: Transform,
+}
+
+impl Wood {
+    pub fn new(rings: f64) -> Self {
+        Self {
+            noise: Perlin::new(),
+            basis: NoiseBasis::Classic,
+            rings,
+            turbulence: 0.5,
+            octaves: 4,
+            transform: Transform::identity(),
+        }
+    }
+}
+
+impl Texture for Wood {
+    fn value(&self, _u: f64, _v: f64, p: Vec3) -> Vec3 {
+        let p = self.transform.inverse_point(p);
+        let radius = (p.x * p.x + p.z * p.z).sqrt();
+        let wobble = octave_sum(&self.noise, self.basis, p, self.octaves, 2.0, 0.5, |n| n);
+        let ring = self.rings * radius + self.turbulence * wobble;
+        gray(1.0 - (ring - ring.floor()))
+    }
+}
+
+/// veins across `axis`, bent by turbulence. The veins of `NoiseTexture` run across z.
+pub struct Marble {
+    pub noise: Perlin,
+    pub basis: NoiseBasis,
+    pub axis: Vec3,
+    /// angular frequency of the stripes along the axis
+    pub frequency: f64,
+    /// how far the veins are bent, in radians of the stripes
+    pub turbulence: f64,
+    pub octaves: usize,
+    pub transform: Transform,
+}
+
+impl Marble {
+    pub fn new(axis: Vec3, frequency: f64) -> Self {
+        Self {
+            noise: Perlin::new(),
+            basis: NoiseBasis::Classic,
+            axis: axis.normalize(),
+            frequency,
+            turbulence: 10.0,
+            octaves: 7,
+            transform: Transform::identity(),
+        }
+    }
+}
+
+impl Texture for Marble {
+    fn value(&self, _u: f64, _v: f64, p: Vec3) -> Vec3 {
+        let p = self.transform.inverse_point(p);
+        let turb = octave_sum(&self.noise, self.basis, p, self.octaves, 2.0, 0.5, f64::abs);
+        gray(0.5 + 0.5 * (self.frequency * (p * self.axis) + self.turbulence * turb).sin())
+    }
+}
+
+/// gradient map from the red channel of `input` to colors, linear between the stops
+pub struct ColorRamp {
+    input: Arc<dyn Texture>,
+    stops: Vec<(f64, Vec3)>,
+}
+
+impl ColorRamp {
+    /// stops as (position, color), in any order
+    pub fn new(input: Arc<dyn Texture>, mut stops: Vec<(f64, Vec3)>) -> Self {
+        assert!(!stops.is_empty());
+        stops.sort_by(|a, b| a.0.total_cmp(&b.0));
+        Self { input, stops }
+    }
+
+    pub fn at(&self, t: f64) -> Vec3 {
+        let k = self.stops.partition_point(|stop| stop.0 <= t);
+        if k == 0 {
+            return self.stops[0].1;
+        }
+        if k == self.stops.len() {
+            return self.stops[k - 1].1;
+        }
+        let ((t0, c0), (t1, c1)) = (self.stops[k - 1], self.stops[k]);
+        let f = (t - t0) / (t1 - t0);
+        (1.0 - f) * c0 + f * c1
+    }
+}
+
+impl Texture for ColorRamp {
+    fn value(&self, u: f64, v: f64, p: Vec3) -> Vec3 {
+        self.at(self.input.value(u, v, p).x)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transform::Quat;
+
+    #[test]
+    fn test_ramp_and_worley() {
+        let ramp = ColorRamp::new(
+            Arc::new(Fbm::new(6)),
+            vec![
+                (1.0, Vec3::ones()),
+                (0.0, Vec3::zero()),
+                (0.5, Vec3::new(1.0, 0.0, 0.0)),
+            ],
+        );
+        assert_eq!(ramp.at(-1.0), Vec3::zero());
+        assert_eq!(ramp.at(0.75), Vec3::new(1.0, 0.5, 0.5));
+        assert_eq!(ramp.at(2.0), Vec3::ones());
+
+        let mut worley = Worley::new(WorleyFeature::F1);
+        let p = Vec3::new(2.3, -0.7, 5.1);
+        let (f1, f2) = worley.distances(p);
+        assert!(0.0 <= f1 && f1 <= f2);
+        // a seed fixes the cells
+        let seeded = |seed| Worley::new_seeded(WorleyFeature::F1, seed).distances(p);
+        assert_eq!(seeded(5), seeded(5));
+        assert_ne!(seeded(5), seeded(6));
+        // without jitter the points sit in the cell centers
+        worley.jitter = 0.0;
+        assert!((worley.distances(Vec3::new(0.5, 0.5, 0.5)).0).abs() < 1e-12);
+        // moving the pattern moves its lookups the other way
+        worley.transform = Transform::new(Vec3::new(0.2, 0.0, 0.0), Quat::identity(), Vec3::ones());
+        let shifted = worley.value(0.0, 0.0, Vec3::new(0.7, 0.5, 0.5));
+        assert!(shifted.x.abs() < 1e-12);
+    }
+}
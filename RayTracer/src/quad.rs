@@ -3,15 +3,70 @@ use crate::hit::{HitRecord, Hittable};
 use crate::hit_list::HitList;
 use crate::interval::Interval;
 use crate::material::Material;
+use crate::onb::Onb;
 use crate::ray::Ray;
+use crate::rt::random_f64;
 use crate::vec3::Vec3;
+use std::f64::consts::PI;
 use std::ops::Deref;
 use std::sync::Arc;
+/// which part of the plane through Q spanned by u and v a `Quad` covers,
+/// in the plane coordinates a and b of Q + a u + b v
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PlanarShape {
+    /// a and b in [0, 1]
+    Parallelogram,
+    /// a, b >= 0 and a + b <= 1
+    Triangle,
+    /// a^2 + b^2 <= 1 around Q, u and v are the semi-axes
+    Ellipse,
+}
+
+impl PlanarShape {
+    /// sets the uv of points inside of the shape
+    pub fn is_interior(&self, alpha: f64, beta: f64, rec: &mut HitRecord) -> bool {
+        match self {
+            PlanarShape::Parallelogram => {
+                let unit_inteveral = Interval::new(0.0, 1.0);
+                if !unit_inteveral.contains(alpha) || !unit_inteveral.contains(beta) {
+                    return false;
+                }
+                rec.u = alpha;
+                rec.v = beta;
+            }
+            PlanarShape::Triangle => {
+                if alpha < 0.0 || beta < 0.0 || alpha + beta > 1.0 {
+                    return false;
+                }
+                rec.u = alpha;
+                rec.v = beta;
+            }
+            PlanarShape::Ellipse => {
+                if alpha * alpha + beta * beta > 1.0 {
+                    return false;
+                }
+                rec.u = 0.5 * alpha + 0.5;
+                rec.v = 0.5 * beta + 0.5;
+            }
+        }
+        true
+    }
+
+    /// dpdu and dpdv as multiples of u and v
+    fn tangent_scale(&self) -> f64 {
+        match self {
+            PlanarShape::Ellipse => 2.0,
+            _ => 1.0,
+        }
+    }
+}
+
 pub struct Quad {
     Q: Vec3,
     u: Vec3,
     v: Vec3,
     w: Vec3,
+    shape: PlanarShape,
     mat: Arc<dyn Material>,
     bbox: AABB,
     normal: Vec3,
@@ -20,34 +75,68 @@ pub struct Quad {
 
 impl Quad {
     pub fn new(Q: Vec3, u: Vec3, v: Vec3, mat: Arc<dyn Material>) -> Self {
+        Quad::new_by_shape(PlanarShape::Parallelogram, Q, u, v, mat)
+    }
+
+    /// triangle with the corners `corner`, `corner + u` and `corner + v`
+    pub fn new_triangle(corner: Vec3, u: Vec3, v: Vec3, mat: Arc<dyn Material>) -> Self {
+        Quad::new_by_shape(PlanarShape::Triangle, corner, u, v, mat)
+    }
+
+    /// ellipse around `center` with the semi-axes u and v
+    pub fn new_ellipse(center: Vec3, u: Vec3, v: Vec3, mat: Arc<dyn Material>) -> Self {
+        Quad::new_by_shape(PlanarShape::Ellipse, center, u, v, mat)
+    }
+
+    /// disk facing `normal`
+    pub fn new_disk(center: Vec3, normal: Vec3, radius: f64, mat: Arc<dyn Material>) -> Self {
+        let n = normal.normalize();
+        let u = Onb::new(n).u;
+        Quad::new_ellipse(center, radius * u, radius * n.cross(u), mat)
+    }
+
+    pub fn new_by_shape(
+        shape: PlanarShape,
+        origin: Vec3,
+        u: Vec3,
+        v: Vec3,
+        mat: Arc<dyn Material>,
+    ) -> Self {
         let n = u.cross(v);
         let normal = n.normalize();
-        let D = normal * Q;
+        let d = normal * origin;
         let w = n / (n * n);
         //set_bounding_box
-        let bbox_diag1 = AABB::new_by_point(Q, Q + u + v);
-        let bbox_diag2 = AABB::new_by_point(Q + u, Q + v);
-        let bbox = AABB::new_by_aabb(&bbox_diag1, &bbox_diag2);
+        let bbox = match shape {
+            PlanarShape::Parallelogram => {
+                let bbox_diag1 = AABB::new_by_point(origin, origin + u + v);
+                let bbox_diag2 = AABB::new_by_point(origin + u, origin + v);
+                AABB::new_by_aabb(&bbox_diag1, &bbox_diag2)
+            }
+            PlanarShape::Triangle => AABB::new_by_aabb(
+                &AABB::new_by_point(origin, origin + u),
+                &AABB::new_by_point(origin, origin + v),
+            ),
+            PlanarShape::Ellipse => {
+                // along each axis the ellipse reaches sqrt(u_i^2 + v_i^2) from its center
+                let mut extent = Vec3::zero();
+                for i in 0..3 {
+                    extent[i] = (u[i] * u[i] + v[i] * v[i]).sqrt();
+                }
+                AABB::new_by_point(origin - extent, origin + extent)
+            }
+        };
         Self {
-            Q,
+            Q: origin,
             u,
             v,
             w,
+            shape,
             mat,
             bbox,
             normal,
-            D,
-        }
-    }
-
-    pub fn is_interior(alpha: f64, beta: f64, rec: &mut HitRecord) -> bool {
-        let unit_inteveral = Interval::new(0.0, 1.0);
-        if !unit_inteveral.contains(alpha) || !unit_inteveral.contains(beta) {
-            return false;
+            D: d,
         }
-        rec.u = alpha;
-        rec.v = beta;
-        true
     }
 }
 
@@ -65,12 +154,14 @@ impl Hittable for Quad {
         let planar_hitpt_vector = intersection - self.Q;
         let alpha = self.w * (planar_hitpt_vector.cross(self.v));
         let beta = self.w * (self.u.cross(planar_hitpt_vector));
-        if !Quad::is_interior(alpha, beta, rec) {
+        if !self.shape.is_interior(alpha, beta, rec) {
             return false;
         }
 
         rec.t = t;
         rec.point = intersection;
+        rec.dpdu = self.shape.tangent_scale() * self.u;
+        rec.dpdv = self.shape.tangent_scale() * self.v;
         rec.mat_ptr = Some(self.mat.clone());
         rec.set_face_normal(ray, self.normal.clone());
         true
@@ -79,6 +170,36 @@ impl Hittable for Quad {
     fn bounding_box(&self) -> AABB {
         self.bbox
     }
+
+    fn area(&self) -> f64 {
+        let parallelogram = self.u.cross(self.v).length();
+        match self.shape {
+            PlanarShape::Parallelogram => parallelogram,
+            PlanarShape::Triangle => 0.5 * parallelogram,
+            PlanarShape::Ellipse => PI * parallelogram,
+        }
+    }
+
+    fn sample_surface(&self, _time: f64) -> Option<HitRecord> {
+        let (r1, r2) = (random_f64(0.0, 1.0), random_f64(0.0, 1.0));
+        let (alpha, beta) = match self.shape {
+            PlanarShape::Parallelogram => (r1, r2),
+            PlanarShape::Triangle => {
+                let s = r1.sqrt();
+                (1.0 - s, r2 * s)
+            }
+            PlanarShape::Ellipse => {
+                let (r, phi) = (r1.sqrt(), 2.0 * PI * r2);
+                (r * phi.cos(), r * phi.sin())
+            }
+        };
+        let point = self.Q + alpha * self.u + beta * self.v;
+        let mut rec = HitRecord::new(point, self.normal, 0.0, true, Some(self.mat.clone()));
+        self.shape.is_interior(alpha, beta, &mut rec);
+        rec.dpdu = self.shape.tangent_scale() * self.u;
+        rec.dpdv = self.shape.tangent_scale() * self.v;
+        Some(rec)
+    }
 }
 
 unsafe impl Sync for Quad {}
@@ -133,3 +254,52 @@ pub fn gen_box(a: Vec3, b: Vec3, mat: Arc<dyn Material>) -> Arc<dyn Hittable> {
     )));
     Arc::new(sides)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::Lambertian;
+
+    fn hits(quad: &Quad, a: f64, b: f64, rec: &mut HitRecord) -> bool {
+        // straight down onto the point Q + a u + b v of a quad in the y = 0 plane
+        let target = quad.Q + a * quad.u + b * quad.v;
+        let ray_ = Ray::new(
+            target + Vec3::new(0.0, 1.0, 0.0),
+            Vec3::new(0.0, -1.0, 0.0),
+            0.0,
+        );
+        quad.hit(&ray_, Interval::new(0.001, f64::INFINITY), rec)
+    }
+
+    #[test]
+    fn test_triangle_and_ellipse() {
+        let mat = Arc::new(Lambertian::new(Vec3::ones()));
+        let (u, v) = (Vec3::new(2.0, 0.0, 0.0), Vec3::new(0.0, 0.0, -1.0));
+        let mut rec = HitRecord::new(Vec3::zero(), Vec3::zero(), 0.0, false, None);
+
+        let triangle = Quad::new_triangle(Vec3::new(1.0, 0.0, 1.0), u, v, mat.clone());
+        assert!(hits(&triangle, 0.25, 0.5, &mut rec));
+        assert!((rec.u - 0.25).abs() < 1e-9 && (rec.v - 0.5).abs() < 1e-9);
+        assert!(!hits(&triangle, 0.6, 0.6, &mut rec));
+        assert!(!hits(&triangle, -0.1, 0.5, &mut rec));
+        assert!((triangle.area() - 1.0).abs() < 1e-12);
+
+        let ellipse = Quad::new_ellipse(Vec3::new(1.0, 0.0, 1.0), u, v, mat);
+        assert!(hits(&ellipse, 0.9, 0.0, &mut rec));
+        assert!((rec.u - 0.95).abs() < 1e-9 && (rec.v - 0.5).abs() < 1e-9);
+        assert!(hits(&ellipse, -0.5, -0.5, &mut rec));
+        assert!(!hits(&ellipse, 0.8, 0.8, &mut rec));
+        assert!((ellipse.area() - 2.0 * PI).abs() < 1e-12);
+        let bbox = ellipse.bounding_box();
+        assert_eq!((bbox.x.min, bbox.x.max, bbox.z.min), (-1.0, 3.0, 0.0));
+
+        // samples land on the shapes
+        for shape in [&triangle, &ellipse] {
+            for _ in 0..50 {
+                let sample = shape.sample_surface(0.0).unwrap();
+                let ray_ = Ray::new(sample.point + sample.normal, -sample.normal, 0.0);
+                assert!(shape.hit(&ray_, Interval::new(0.001, f64::INFINITY), &mut rec));
+            }
+        }
+    }
+}
@@ -1,3 +1,4 @@
+use crate::medium::MediumStack;
 use crate::vec3::Vec3;
 #[derive(Clone, Debug, PartialEq, Copy)]
 
@@ -5,6 +6,15 @@ pub struct Ray {
     pub orig: Vec3,
     pub dir: Vec3,
     pub time: f64,
+    /// hero wavelengths in nm for spectral rendering, zero for RGB rays
+    pub lambda: Vec3,
+    /// dielectrics the ray is travelling inside of
+    pub media: MediumStack,
+    /// width of the cone around the ray at its origin, for the texture footprint
+    pub cone_width: f64,
+    /// how much wider the cone gets per unit of distance travelled, zero for rays
+    /// without a footprint
+    pub cone_spread: f64,
 }
 
 impl Ray {
@@ -13,10 +23,41 @@ impl Ray {
             orig: origin,
             dir: direction,
             time: time,
+            lambda: Vec3::zero(),
+            media: MediumStack::new(),
+            cone_width: 0.0,
+            cone_spread: 0.0,
         }
     }
 
+    pub fn new_by_lambda(origin: Vec3, direction: Vec3, time: f64, lambda: Vec3) -> Self {
+        Self {
+            orig: origin,
+            dir: direction,
+            time,
+            lambda,
+            media: MediumStack::new(),
+            cone_width: 0.0,
+            cone_spread: 0.0,
+        }
+    }
+
+    pub fn is_spectral(&self) -> bool {
+        self.lambda.x > 0.0
+    }
+
     pub fn at(self, t: f64) -> Vec3 {
         self.orig + t * self.dir
     }
+
+    /// width of the cone at the point at t
+    pub fn cone_width_at(&self, t: f64) -> f64 {
+        self.cone_width + self.cone_spread * t * self.dir.length()
+    }
+
+    /// carries on the cone of `parent` from its hit at t, ignoring the curvature there
+    pub fn follow_cone(&mut self, parent: &Ray, t: f64) {
+        self.cone_width = parent.cone_width_at(t);
+        self.cone_spread = parent.cone_spread;
+    }
 }
@@ -1,11 +1,55 @@
 use rand::Rng;
+use std::cell::{Cell, RefCell};
 
+/// replacement for the random numbers of a path, see `set_sample_source`
+pub trait SampleSource {
+    /// uniform in [0, 1)
+    fn next_sample(&mut self) -> f64;
+}
+
+thread_local! {
+    static SOURCE: RefCell<Option<Box<dyn SampleSource>>> = const { RefCell::new(None) };
+    /// whether SOURCE is set, so that plain sampling skips the RefCell
+    static HOOKED: Cell<bool> = const { Cell::new(false) };
+}
+
+/// draws every random number of this thread from `source` until it is reset with None,
+/// which is how `mlt` replays and mutates paths
+pub fn set_sample_source(source: Option<Box<dyn SampleSource>>) {
+    HOOKED.with(|hooked| hooked.set(source.is_some()));
+    SOURCE.with(|current| *current.borrow_mut() = source);
+}
+
+fn hooked_sample() -> Option<f64> {
+    if !HOOKED.with(|hooked| hooked.get()) {
+        return None;
+    }
+    SOURCE.with(|current| current.borrow_mut().as_mut().map(|s| s.next_sample()))
+}
+
+/// every random number of a path goes through here, so a sample source can take over
 pub fn random_f64(a: f64, b: f64) -> f64 {
+    if let Some(u) = hooked_sample() {
+        return a + u * (b - a);
+    }
     let mut rng = rand::thread_rng();
     rng.gen_range(a..b)
 }
 
 pub fn random_int(a: i32, b: i32) -> i32 {
+    if let Some(u) = hooked_sample() {
+        return (a + (u * (b - a + 1) as f64) as i32).min(b);
+    }
     let mut rng = rand::thread_rng();
     rng.gen_range(a..=b)
 }
+
+/// multiple importance sampling weight of strategy f against g, one sample each
+pub fn power_heuristic(f_pdf: f64, g_pdf: f64) -> f64 {
+    let f2 = f_pdf * f_pdf;
+    let g2 = g_pdf * g_pdf;
+    if f2 + g2 == 0.0 {
+        return 0.0;
+    }
+    f2 / (f2 + g2)
+}
@@ -0,0 +1,313 @@
+use crate::aabb::AABB;
+use crate::hit::{HitRecord, Hittable};
+use crate::interval::Interval;
+use crate::material::Material;
+use crate::onb::Onb;
+use crate::ray::Ray;
+use crate::sphere::Sphere;
+use crate::vec3::Vec3;
+use std::sync::Arc;
+
+fn abs(p: Vec3) -> Vec3 {
+    Vec3::new(p.x.abs(), p.y.abs(), p.z.abs())
+}
+
+fn max0(p: Vec3) -> Vec3 {
+    Vec3::new(p.x.max(0.0), p.y.max(0.0), p.z.max(0.0))
+}
+
+/// polynomial smooth minimum, blending over a distance of about k
+fn smooth_min(a: f64, b: f64, k: f64) -> f64 {
+    if k <= 0.0 {
+        return a.min(b);
+    }
+    let h = (0.5 + 0.5 * (b - a) / k).clamp(0.0, 1.0);
+    b * (1.0 - h) + a * h - k * h * (1.0 - h)
+}
+
+/// signed distance function as a tree of shapes and operations on them, negative inside.
+/// The shapes are centered on the origin and placed with `translate`.
+#[derive(Clone)]
+pub enum Sdf {
+    Sphere {
+        radius: f64,
+    },
+    Box {
+        half: Vec3,
+    },
+    /// box with its edges rounded off by `radius`, within the same half extents
+    RoundBox {
+        half: Vec3,
+        radius: f64,
+    },
+    /// ring around the y axis
+    Torus {
+        major: f64,
+        minor: f64,
+    },
+    /// segment from a to b thickened by `radius`
+    Capsule {
+        a: Vec3,
+        b: Vec3,
+        radius: f64,
+    },
+    /// distance estimate of the power `power` Mandelbulb, about 1.2 across
+    Mandelbulb {
+        power: f64,
+        iterations: usize,
+    },
+    Translate(Box<Sdf>, Vec3),
+    /// union blended over a distance of about k, k = 0 is a sharp union
+    SmoothUnion(Box<Sdf>, Box<Sdf>, f64),
+    /// the first shape with the second carved out, blended over about k
+    SmoothSubtraction(Box<Sdf>, Box<Sdf>, f64),
+    /// rotates slices of the shape around the y axis by `rate` radians per unit of height
+    Twist(Box<Sdf>, f64),
+    /// infinite copies of the shape spaced by `period`, 0 on an axis does not repeat
+    Repeat(Box<Sdf>, Vec3),
+    Custom(Arc<dyn Fn(Vec3) -> f64 + Send + Sync>),
+}
+
+impl Sdf {
+    pub fn from_fn(f: impl Fn(Vec3) -> f64 + Send + Sync + 'static) -> Self {
+        Sdf::Custom(Arc::new(f))
+    }
+
+    pub fn translate(self, offset: Vec3) -> Self {
+        Sdf::Translate(Box::new(self), offset)
+    }
+
+    pub fn smooth_union(self, other: Sdf, k: f64) -> Self {
+        Sdf::SmoothUnion(Box::new(self), Box::new(other), k)
+    }
+
+    pub fn smooth_subtraction(self, other: Sdf, k: f64) -> Self {
+        Sdf::SmoothSubtraction(Box::new(self), Box::new(other), k)
+    }
+
+    pub fn twist(self, rate: f64) -> Self {
+        Sdf::Twist(Box::new(self), rate)
+    }
+
+    pub fn repeat(self, period: Vec3) -> Self {
+        Sdf::Repeat(Box::new(self), period)
+    }
+
+    pub fn distance(&self, p: Vec3) -> f64 {
+        match self {
+            Sdf::Sphere { radius } => p.length() - radius,
+            Sdf::Box { half } => {
+                let q = abs(p) - *half;
+                max0(q).length() + q.x.max(q.y).max(q.z).min(0.0)
+            }
+            Sdf::RoundBox { half, radius } => {
+                let q = abs(p) - *half + Vec3::ones() * *radius;
+                max0(q).length() + q.x.max(q.y).max(q.z).min(0.0) - radius
+            }
+            Sdf::Torus { major, minor } => {
+                let ring = (p.x * p.x + p.z * p.z).sqrt() - major;
+                (ring * ring + p.y * p.y).sqrt() - minor
+            }
+            Sdf::Capsule { a, b, radius } => {
+                let (pa, ba) = (p - *a, *b - *a);
+                let h = ((pa * ba) / (ba * ba)).clamp(0.0, 1.0);
+                (pa - h * ba).length() - radius
+            }
+            Sdf::Mandelbulb { power, iterations } => mandelbulb(p, *power, *iterations),
+            Sdf::Translate(sdf, offset) => sdf.distance(p - *offset),
+            Sdf::SmoothUnion(a, b, k) => smooth_min(a.distance(p), b.distance(p), *k),
+            Sdf::SmoothSubtraction(a, b, k) => -smooth_min(-a.distance(p), b.distance(p), *k),
+            Sdf::Twist(sdf, rate) => {
+                let (s, c) = (rate * p.y).sin_cos();
+                sdf.distance(Vec3::new(c * p.x - s * p.z, p.y, s * p.x + c * p.z))
+            }
+            Sdf::Repeat(sdf, period) => {
+                let mut q = p;
+                for i in 0..3 {
+                    if period[i] > 0.0 {
+                        q[i] -= period[i] * (p[i] / period[i]).round();
+                    }
+                }
+                sdf.distance(q)
+            }
+            Sdf::Custom(f) => f(p),
+        }
+    }
+}
+
+fn mandelbulb(p: Vec3, power: f64, iterations: usize) -> f64 {
+    let mut z = p;
+    let mut dr = 1.0;
+    let mut r = z.length();
+    for _ in 0..iterations {
+        if r > 2.0 || r == 0.0 {
+            break;
+        }
+        let theta = (z.z / r).acos() * power;
+        let phi = z.y.atan2(z.x) * power;
+        dr = r.powf(power - 1.0) * power * dr + 1.0;
+        z = r.powf(power)
+            * Vec3::new(
+                theta.sin() * phi.cos(),
+                theta.sin() * phi.sin(),
+                theta.cos(),
+            )
+            + p;
+        r = z.length();
+    }
+    if r == 0.0 {
+        return 0.0;
+    }
+    0.5 * r.ln() * r / dr
+}
+
+/// a `Sdf` rendered by sphere tracing inside of `bbox`, which has to enclose the surface
+pub struct SdfShape {
+    sdf: Sdf,
+    bbox: AABB,
+    mat: Arc<dyn Material>,
+    /// distance at which the march counts as on the surface
+    pub epsilon: f64,
+    pub max_steps: usize,
+    /// fraction of the distance marched per step, below 1 for distorted fields
+    /// like `Twist` whose values overestimate the true distance
+    pub step_scale: f64,
+}
+
+impl SdfShape {
+    pub fn new(sdf: Sdf, bbox: AABB, mat: Arc<dyn Material>) -> Self {
+        Self {
+            sdf,
+            bbox,
+            mat,
+            epsilon: 1e-4,
+            max_steps: 512,
+            step_scale: 1.0,
+        }
+    }
+
+    /// gradient of the field by the tetrahedron of central differences
+    fn normal(&self, p: Vec3) -> Vec3 {
+        let h = self.epsilon;
+        let gradient = [
+            Vec3::new(1.0, -1.0, -1.0),
+            Vec3::new(-1.0, -1.0, 1.0),
+            Vec3::new(-1.0, 1.0, -1.0),
+            Vec3::new(1.0, 1.0, 1.0),
+        ]
+        .iter()
+        .fold(Vec3::zero(), |g, &k| g + k * self.sdf.distance(p + h * k));
+        if gradient.near_zero() {
+            return Vec3::new(0.0, 1.0, 0.0);
+        }
+        gradient.normalize()
+    }
+}
+
+impl Hittable for SdfShape {
+    fn hit(&self, ray_: &Ray, int: Interval, rec: &mut HitRecord) -> bool {
+        let mut ray_t = int;
+        if !self.bbox.hit(ray_, &mut ray_t) {
+            return false;
+        }
+        let speed = ray_.dir.length();
+        let mut t = ray_t.min;
+        let mut steps = 0;
+        // rays that start on the surface, like those scattered off of it, first leave
+        // the epsilon band around it, which the distance alone cannot get them out of;
+        // grazing rays stay in it well past the start of the interval; a ray entering the
+        // box later than its interval starts is not leaving a surface
+        let leaving = ray_t.min <= int.min;
+        while leaving
+            && steps < self.max_steps
+            && self.sdf.distance(ray_.at(t)).abs() < self.epsilon
+        {
+            t += self.epsilon / speed;
+            if t > ray_t.max {
+                return false;
+            }
+            steps += 1;
+        }
+        // rays that start inside march towards the surface from within
+        let side = self.sdf.distance(ray_.at(t)).signum();
+        for _ in steps..self.max_steps {
+            let d = side * self.sdf.distance(ray_.at(t));
+            if d < self.epsilon {
+                if !int.surrounds(t) {
+                    return false;
+                }
+                rec.t = t;
+                rec.point = ray_.at(t);
+                let out_normal = self.normal(rec.point);
+                rec.set_face_normal(ray_, out_normal);
+                Sphere::get_sphere_uv(out_normal, &mut rec.u, &mut rec.v);
+                let frame = Onb::new(out_normal);
+                (rec.dpdu, rec.dpdv) = (frame.u, frame.v);
+                rec.mat_ptr = Some(self.mat.clone());
+                return true;
+            }
+            t += self.step_scale * d / speed;
+            if t > ray_t.max {
+                return false;
+            }
+        }
+        false
+    }
+
+    fn bounding_box(&self) -> AABB {
+        self.bbox
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::Lambertian;
+
+    #[test]
+    fn test_sphere_traced_like_sphere() {
+        let sdf = Sdf::Sphere { radius: 1.0 }.translate(Vec3::new(0.0, 0.0, -3.0));
+        let bbox = AABB::new_by_point(Vec3::new(-1.0, -1.0, -4.0), Vec3::new(1.0, 1.0, -2.0));
+        let shape = SdfShape::new(sdf, bbox, Arc::new(Lambertian::new(Vec3::ones())));
+        let mut rec = HitRecord::new(Vec3::zero(), Vec3::zero(), 0.0, false, None);
+        let ray_ = Ray::new(Vec3::zero(), Vec3::new(0.0, 0.0, -2.0), 0.0);
+        assert!(shape.hit(&ray_, Interval::new(0.001, f64::INFINITY), &mut rec));
+        assert!((rec.t - 1.0).abs() < 1e-3);
+        assert!((rec.normal - Vec3::new(0.0, 0.0, 1.0)).length() < 1e-3);
+        assert!(rec.front_face);
+    }
+
+    #[test]
+    fn test_ray_from_surface_finds_far_side() {
+        let bbox = AABB::new_by_point(Vec3::new(-1.0, -1.0, -1.0), Vec3::new(1.0, 1.0, 1.0));
+        let mat = Arc::new(Lambertian::new(Vec3::ones()));
+        let shape = SdfShape::new(Sdf::Sphere { radius: 1.0 }, bbox, mat);
+        let mut rec = HitRecord::new(Vec3::zero(), Vec3::zero(), 0.0, false, None);
+        let int = Interval::new(1e-5, f64::INFINITY);
+        let inwards = Ray::new(Vec3::new(1.0, 0.0, 0.0), Vec3::new(-1.0, 0.0, 0.0), 0.0);
+        assert!(shape.hit(&inwards, int, &mut rec));
+        assert!((rec.t - 2.0).abs() < 1e-3);
+        assert!(!rec.front_face);
+        let outwards = Ray::new(Vec3::new(1.0, 0.0, 0.0), Vec3::new(1.0, 1.0, 0.0), 0.0);
+        assert!(!shape.hit(&outwards, int, &mut rec));
+    }
+
+    #[test]
+    fn test_grazing_ray_from_surface() {
+        let bbox = AABB::new_by_point(Vec3::new(-2.0, -2.0, -2.0), Vec3::new(2.0, 2.0, 2.0));
+        let mat = Arc::new(Lambertian::new(Vec3::ones()));
+        let shape = SdfShape::new(Sdf::Sphere { radius: 1.0 }, bbox, mat);
+        let mut rec = HitRecord::new(Vec3::zero(), Vec3::zero(), 0.0, false, None);
+        let int = Interval::new(0.001, f64::INFINITY);
+        for s in [0.0, 0.02, 0.05, 0.08, 0.2] {
+            let outwards = Ray::new(Vec3::new(1.0, 0.0, 0.0), Vec3::new(s, 1.0, 0.0), 0.0);
+            assert!(!shape.hit(&outwards, int, &mut rec), "s = {s}");
+        }
+        // grazing inwards the ray crosses the chord to the far side
+        let inwards = Ray::new(Vec3::new(1.0, 0.0, 0.0), Vec3::new(-0.05, 1.0, 0.0), 0.0);
+        assert!(shape.hit(&inwards, int, &mut rec));
+        assert!(rec.t > 0.09);
+        assert!((rec.point.length() - 1.0).abs() <= shape.epsilon);
+        assert!(!rec.front_face);
+    }
+}
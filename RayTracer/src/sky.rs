@@ -0,0 +1,254 @@
+use crate::environment::{Environment, EnvironmentSample};
+use crate::onb::Onb;
+use crate::rt::random_f64;
+use crate::vec3::Vec3;
+use std::f64::consts::PI;
+
+/// angular radius of the sun disk in degrees
+const SUN_RADIUS: f64 = 0.27;
+/// scene radiance per kcd/m^2 of sky luminance
+const SKY_SCALE: f64 = 0.04;
+/// irradiance of the sun at the top of the atmosphere, in scene units
+const SUN_IRRADIANCE: f64 = 4.0;
+
+/// Perez et al. luminance distribution with the five coefficients A..E
+fn perez(coeffs: &[f64; 5], cos_theta: f64, gamma: f64) -> f64 {
+    let [a, b, c, d, e] = *coeffs;
+    let cos_gamma = gamma.cos();
+    (1.0 + a * (b / cos_theta.max(0.01)).exp())
+        * (1.0 + c * (d * gamma).exp() + e * cos_gamma * cos_gamma)
+}
+
+fn xyy_to_rgb(x: f64, y: f64, lum: f64) -> Vec3 {
+    if y <= 0.0 {
+        return Vec3::zero();
+    }
+    let cx = x / y * lum;
+    let cz = (1.0 - x - y) / y * lum;
+    // linear sRGB primaries, D65 white
+    Vec3::new(
+        3.2406 * cx - 1.5372 * lum - 0.4986 * cz,
+        -0.9689 * cx + 1.8758 * lum + 0.0415 * cz,
+        0.0557 * cx - 0.2040 * lum + 1.0570 * cz,
+    )
+}
+
+/// Preetham analytic daylight sky with a sun disk, +y is up.
+/// Directions below the horizon see a diffuse ground lit by the sky and the sun.
+pub struct Sky {
+    /// unit direction towards the sun
+    sun_dir: Vec3,
+    coeffs_lum: [f64; 5],
+    coeffs_x: [f64; 5],
+    coeffs_y: [f64; 5],
+    /// zenith values divided by the Perez function at the zenith
+    zenith: (f64, f64, f64),
+    sun_radiance: Vec3,
+    ground_radiance: Vec3,
+    cos_sun_radius: f64,
+    /// probability of sampling the sun disk instead of the whole sphere
+    sun_sample_prob: f64,
+    pub intensity: f64,
+}
+
+impl Sky {
+    /// `turbidity` ranges from 2 (very clear) to 10 (hazy)
+    pub fn new(sun_direction: Vec3, turbidity: f64, ground_albedo: Vec3) -> Self {
+        let sun_dir = sun_direction.normalize();
+        let t = turbidity.clamp(1.7, 10.0);
+        // the model only holds with the sun above the horizon
+        let theta_s = sun_dir.y.clamp(-1.0, 1.0).acos().min(0.5 * PI - 1e-3);
+        let (t2, th2, th3) = (t * t, theta_s * theta_s, theta_s * theta_s * theta_s);
+
+        let chi = (4.0 / 9.0 - t / 120.0) * (PI - 2.0 * theta_s);
+        let zenith_lum = ((4.0453 * t - 4.9710) * chi.tan() - 0.2155 * t + 2.4192).max(0.0);
+        let zenith_x = t2 * (0.00166 * th3 - 0.00375 * th2 + 0.00209 * theta_s)
+            + t * (-0.02903 * th3 + 0.06377 * th2 - 0.03202 * theta_s + 0.00394)
+            + (0.11693 * th3 - 0.21196 * th2 + 0.06052 * theta_s + 0.25886);
+        let zenith_y = t2 * (0.00275 * th3 - 0.00610 * th2 + 0.00317 * theta_s)
+            + t * (-0.04214 * th3 + 0.08970 * th2 - 0.04153 * theta_s + 0.00516)
+            + (0.15346 * th3 - 0.26756 * th2 + 0.06670 * theta_s + 0.26688);
+
+        let coeffs_lum = [
+            0.1787 * t - 1.4630,
+            -0.3554 * t + 0.4275,
+            -0.0227 * t + 5.3251,
+            0.1206 * t - 2.5771,
+            -0.0670 * t + 0.3703,
+        ];
+        let coeffs_x = [
+            -0.0193 * t - 0.2592,
+            -0.0665 * t + 0.0008,
+            -0.0004 * t + 0.2125,
+            -0.0641 * t - 0.8989,
+            -0.0033 * t + 0.0452,
+        ];
+        let coeffs_y = [
+            -0.0167 * t - 0.2608,
+            -0.0950 * t + 0.0092,
+            -0.0079 * t + 0.2102,
+            -0.0441 * t - 1.6537,
+            -0.0109 * t + 0.0529,
+        ];
+        let zenith = (
+            zenith_lum / perez(&coeffs_lum, 1.0, theta_s),
+            zenith_x / perez(&coeffs_x, 1.0, theta_s),
+            zenith_y / perez(&coeffs_y, 1.0, theta_s),
+        );
+
+        let cos_sun_radius = SUN_RADIUS.to_radians().cos();
+        let sun_solid_angle = 2.0 * PI * (1.0 - cos_sun_radius);
+        let sun_radiance = if sun_dir.y > 0.0 {
+            Sky::sun_transmittance(theta_s, t) * (SUN_IRRADIANCE / sun_solid_angle)
+        } else {
+            Vec3::zero()
+        };
+
+        let mut sky = Self {
+            sun_dir,
+            coeffs_lum,
+            coeffs_x,
+            coeffs_y,
+            zenith,
+            sun_radiance,
+            ground_radiance: Vec3::zero(),
+            cos_sun_radius,
+            sun_sample_prob: if sun_dir.y > 0.0 { 0.5 } else { 0.0 },
+            intensity: 1.0,
+        };
+        sky.ground_radiance = ground_albedo.cor_dot(sky.horizontal_irradiance()) / PI;
+        sky
+    }
+
+    /// sun position given as elevation above the horizon and azimuth from -z towards +x, in degrees
+    pub fn new_by_angles(
+        elevation: f64,
+        azimuth: f64,
+        turbidity: f64,
+        ground_albedo: Vec3,
+    ) -> Self {
+        let (elevation, azimuth) = (elevation.to_radians(), azimuth.to_radians());
+        let dir = Vec3::new(
+            elevation.cos() * azimuth.sin(),
+            elevation.sin(),
+            -elevation.cos() * azimuth.cos(),
+        );
+        Self::new(dir, turbidity, ground_albedo)
+    }
+
+    /// Rayleigh and aerosol extinction of sunlight along the optical air mass
+    fn sun_transmittance(theta_s: f64, turbidity: f64) -> Vec3 {
+        let deg = theta_s.to_degrees();
+        let air_mass = 1.0 / (theta_s.cos() + 0.15 * (93.885 - deg).powf(-1.253));
+        let beta = 0.04608 * turbidity - 0.04586;
+        let channel = |lambda: f64| {
+            let rayleigh = 0.008735 * lambda.powf(-4.08);
+            let aerosol = beta * lambda.powf(-1.3);
+            (-(rayleigh + aerosol) * air_mass).exp()
+        };
+        // representative wavelengths of the RGB primaries in micrometers
+        Vec3::new(channel(0.68), channel(0.55), channel(0.44))
+    }
+
+    fn sky_radiance(&self, dir: Vec3) -> Vec3 {
+        let cos_theta = dir.y.max(0.0);
+        let gamma = (dir * self.sun_dir).clamp(-1.0, 1.0).acos();
+        let lum = self.zenith.0 * perez(&self.coeffs_lum, cos_theta, gamma);
+        let x = self.zenith.1 * perez(&self.coeffs_x, cos_theta, gamma);
+        let y = self.zenith.2 * perez(&self.coeffs_y, cos_theta, gamma);
+        let rgb = SKY_SCALE * xyy_to_rgb(x, y, lum);
+        Vec3::new(rgb.x.max(0.0), rgb.y.max(0.0), rgb.z.max(0.0))
+    }
+
+    /// irradiance on an upward facing surface from the sky dome and the sun
+    fn horizontal_irradiance(&self) -> Vec3 {
+        let (n_theta, n_phi) = (32, 64);
+        let d_theta = 0.5 * PI / n_theta as f64;
+        let d_phi = 2.0 * PI / n_phi as f64;
+        let mut irradiance = Vec3::zero();
+        for i in 0..n_theta {
+            let theta = (i as f64 + 0.5) * d_theta;
+            for j in 0..n_phi {
+                let phi = (j as f64 + 0.5) * d_phi;
+                let dir = Vec3::new(
+                    theta.sin() * phi.cos(),
+                    theta.cos(),
+                    theta.sin() * phi.sin(),
+                );
+                irradiance +=
+                    self.sky_radiance(dir) * (theta.cos() * theta.sin() * d_theta * d_phi);
+            }
+        }
+        let sun_solid_angle = 2.0 * PI * (1.0 - self.cos_sun_radius);
+        irradiance + self.sun_radiance * (sun_solid_angle * self.sun_dir.y.max(0.0))
+    }
+
+    fn in_sun(&self, dir: Vec3) -> bool {
+        dir * self.sun_dir >= self.cos_sun_radius
+    }
+}
+
+impl Environment for Sky {
+    fn radiance(&self, dir: Vec3) -> Vec3 {
+        let dir = dir.normalize();
+        if dir.y < 0.0 {
+            return self.intensity * self.ground_radiance;
+        }
+        let mut radiance = self.sky_radiance(dir);
+        if self.in_sun(dir) {
+            radiance += self.sun_radiance;
+        }
+        self.intensity * radiance
+    }
+
+    fn sample(&self) -> Option<EnvironmentSample> {
+        let wi = if random_f64(0.0, 1.0) < self.sun_sample_prob {
+            // uniform in the cone of the sun disk
+            let cos_theta = 1.0 - random_f64(0.0, 1.0) * (1.0 - self.cos_sun_radius);
+            let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+            let phi = 2.0 * PI * random_f64(0.0, 1.0);
+            Onb::new(self.sun_dir).local(Vec3::new(
+                sin_theta * phi.cos(),
+                sin_theta * phi.sin(),
+                cos_theta,
+            ))
+        } else {
+            Vec3::random_unit_vector()
+        };
+        let pdf = self.pdf(wi);
+        if pdf == 0.0 {
+            return None;
+        }
+        Some(EnvironmentSample {
+            wi,
+            radiance: self.radiance(wi),
+            pdf,
+        })
+    }
+
+    fn pdf(&self, dir: Vec3) -> f64 {
+        let dir = dir.normalize();
+        let mut pdf = (1.0 - self.sun_sample_prob) / (4.0 * PI);
+        if self.in_sun(dir) {
+            pdf += self.sun_sample_prob / (2.0 * PI * (1.0 - self.cos_sun_radius));
+        }
+        pdf
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sky_brightest_near_sun() {
+        let sky = Sky::new_by_angles(30.0, 0.0, 3.0, Vec3::ones() * 0.3);
+        let towards_sun = Vec3::new(0.0, 0.6, -1.0);
+        let away_from_sun = Vec3::new(0.0, 0.6, 1.0);
+        assert!(sky.radiance(towards_sun).y > sky.radiance(away_from_sun).y);
+        // clear sky scatters more blue than red
+        let zenith = sky.radiance(Vec3::new(0.0, 1.0, 0.0));
+        assert!(zenith.z > zenith.x);
+        assert!(sky.radiance(sky.sun_dir).y > 100.0 * zenith.y);
+    }
+}
@@ -0,0 +1,183 @@
+use crate::vec3::Vec3;
+use std::sync::OnceLock;
+
+// spectral rendering traces three hero wavelengths (in nm) per camera sample,
+// stored in the components of a Vec3. RGB colors of the scene are upsampled
+// with Smits' basis spectra and the result goes back to sRGB through CIE XYZ.
+
+pub const LAMBDA_MIN: f64 = 380.0;
+pub const LAMBDA_MAX: f64 = 720.0;
+
+/// Smits (1999) basis spectra, ten equal bins over [LAMBDA_MIN, LAMBDA_MAX]
+const SMITS_WHITE: [f64; 10] = [
+    1.0000, 1.0000, 0.9999, 0.9993, 0.9992, 0.9998, 1.0000, 1.0000, 1.0000, 1.0000,
+];
+const SMITS_CYAN: [f64; 10] = [
+    0.9710, 0.9426, 1.0007, 1.0007, 1.0007, 1.0007, 0.1564, 0.0000, 0.0000, 0.0000,
+];
+const SMITS_MAGENTA: [f64; 10] = [
+    1.0000, 1.0000, 0.9685, 0.2229, 0.0000, 0.0458, 0.8369, 1.0000, 1.0000, 0.9959,
+];
+const SMITS_YELLOW: [f64; 10] = [
+    0.0001, 0.0000, 0.1088, 0.6651, 1.0000, 1.0000, 0.9996, 0.9586, 0.9685, 0.9840,
+];
+const SMITS_RED: [f64; 10] = [
+    0.1012, 0.0515, 0.0000, 0.0000, 0.0000, 0.0000, 0.8325, 1.0149, 1.0149, 1.0149,
+];
+const SMITS_GREEN: [f64; 10] = [
+    0.0000, 0.0000, 0.0273, 0.7937, 1.0000, 0.9418, 0.1719, 0.0000, 0.0000, 0.0025,
+];
+const SMITS_BLUE: [f64; 10] = [
+    1.0000, 1.0000, 0.8916, 0.3323, 0.0000, 0.0000, 0.0003, 0.0369, 0.0483, 0.0496,
+];
+
+/// stratified set of three wavelengths, the first one is the hero
+pub fn sample_wavelengths(u: f64) -> Vec3 {
+    let range = LAMBDA_MAX - LAMBDA_MIN;
+    let hero = LAMBDA_MIN + u * range;
+    let rotate = |k: f64| LAMBDA_MIN + (hero - LAMBDA_MIN + k * range / 3.0) % range;
+    Vec3::new(hero, rotate(1.0), rotate(2.0))
+}
+
+/// the three wavelengths after the secondary ones have been dropped, e.g. by dispersion
+pub fn terminate_secondary(lambda: Vec3) -> Vec3 {
+    Vec3::new(lambda.x, 0.0, 0.0)
+}
+
+pub fn is_terminated(lambda: Vec3) -> bool {
+    lambda.y == 0.0
+}
+
+fn lobe(lambda: f64, mu: f64, sigma_low: f64, sigma_high: f64) -> f64 {
+    let t = (lambda - mu) / if lambda < mu { sigma_low } else { sigma_high };
+    (-0.5 * t * t).exp()
+}
+
+/// CIE 1931 color matching functions, multi-lobe fit of Wyman et al. (2013)
+pub fn cie_xyz(lambda: f64) -> Vec3 {
+    Vec3::new(
+        1.056 * lobe(lambda, 599.8, 37.9, 31.0) + 0.362 * lobe(lambda, 442.0, 16.0, 26.7)
+            - 0.065 * lobe(lambda, 501.1, 20.4, 26.2),
+        0.821 * lobe(lambda, 568.8, 46.9, 40.5) + 0.286 * lobe(lambda, 530.9, 16.3, 31.1),
+        1.217 * lobe(lambda, 437.0, 11.8, 36.0) + 0.681 * lobe(lambda, 459.0, 26.0, 13.8),
+    )
+}
+
+fn xyz_to_linear_srgb(c: Vec3) -> Vec3 {
+    Vec3::new(
+        3.2406 * c.x - 1.5372 * c.y - 0.4986 * c.z,
+        -0.9689 * c.x + 1.8758 * c.y + 0.0415 * c.z,
+        0.0557 * c.x - 0.2040 * c.y + 1.0570 * c.z,
+    )
+}
+
+fn smits(table: &[f64; 10], lambda: f64) -> f64 {
+    if !(LAMBDA_MIN..=LAMBDA_MAX).contains(&lambda) {
+        return 0.0;
+    }
+    let bin = ((lambda - LAMBDA_MIN) / (LAMBDA_MAX - LAMBDA_MIN) * 10.0) as usize;
+    table[bin.min(9)]
+}
+
+/// value at `lambda` of a smooth spectrum whose color is `rgb`
+fn upsample(rgb: Vec3, lambda: f64) -> f64 {
+    let (r, g, b) = (rgb.x.max(0.0), rgb.y.max(0.0), rgb.z.max(0.0));
+    let s = |table: &[f64; 10]| smits(table, lambda);
+    if r <= g && r <= b {
+        r * s(&SMITS_WHITE)
+            + if g <= b {
+                (g - r) * s(&SMITS_CYAN) + (b - g) * s(&SMITS_BLUE)
+            } else {
+                (b - r) * s(&SMITS_CYAN) + (g - b) * s(&SMITS_GREEN)
+            }
+    } else if g <= r && g <= b {
+        g * s(&SMITS_WHITE)
+            + if r <= b {
+                (r - g) * s(&SMITS_MAGENTA) + (b - r) * s(&SMITS_BLUE)
+            } else {
+                (b - g) * s(&SMITS_MAGENTA) + (r - b) * s(&SMITS_RED)
+            }
+    } else {
+        b * s(&SMITS_WHITE)
+            + if r <= g {
+                (r - b) * s(&SMITS_YELLOW) + (g - r) * s(&SMITS_GREEN)
+            } else {
+                (g - b) * s(&SMITS_YELLOW) + (r - g) * s(&SMITS_RED)
+            }
+    }
+}
+
+/// values of the upsampled `rgb` at the three wavelengths
+pub fn rgb_to_spectrum(rgb: Vec3, lambda: Vec3) -> Vec3 {
+    Vec3::new(
+        upsample(rgb, lambda.x),
+        upsample(rgb, lambda.y),
+        upsample(rgb, lambda.z),
+    )
+}
+
+/// per channel scale that maps the upsampled white back to RGB (1, 1, 1)
+fn white_balance() -> Vec3 {
+    static WHITE: OnceLock<Vec3> = OnceLock::new();
+    *WHITE.get_or_init(|| {
+        let steps = 3400;
+        let d_lambda = (LAMBDA_MAX - LAMBDA_MIN) / steps as f64;
+        let mut xyz = Vec3::zero();
+        for i in 0..steps {
+            let lambda = LAMBDA_MIN + (i as f64 + 0.5) * d_lambda;
+            xyz += cie_xyz(lambda) * (smits(&SMITS_WHITE, lambda) * d_lambda);
+        }
+        let rgb = xyz_to_linear_srgb(xyz);
+        Vec3::new(1.0 / rgb.x, 1.0 / rgb.y, 1.0 / rgb.z)
+    })
+}
+
+/// linear sRGB estimate from radiance `values` carried at the wavelengths `lambda`
+pub fn spectrum_to_rgb(values: Vec3, lambda: Vec3) -> Vec3 {
+    // each wavelength is uniformly distributed on its own, pdf 1 / range
+    let range = LAMBDA_MAX - LAMBDA_MIN;
+    let mut xyz = Vec3::zero();
+    for (value, l) in [
+        (values.x, lambda.x),
+        (values.y, lambda.y),
+        (values.z, lambda.z),
+    ] {
+        if value != 0.0 {
+            xyz += cie_xyz(l) * value;
+        }
+    }
+    xyz = xyz * (range / 3.0);
+    xyz_to_linear_srgb(xyz).cor_dot(white_balance())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(rgb: Vec3) -> Vec3 {
+        let n = 3000;
+        let mut sum = Vec3::zero();
+        for i in 0..n {
+            let lambda = sample_wavelengths((i as f64 + 0.5) / n as f64);
+            sum += spectrum_to_rgb(rgb_to_spectrum(rgb, lambda), lambda);
+        }
+        sum / n as f64
+    }
+
+    #[test]
+    fn test_white_round_trip() {
+        let white = round_trip(Vec3::ones());
+        assert!((white - Vec3::ones()).length() < 1e-2);
+    }
+
+    #[test]
+    fn test_color_round_trip() {
+        for rgb in [
+            Vec3::new(0.8, 0.2, 0.1),
+            Vec3::new(0.1, 0.6, 0.3),
+            Vec3::new(0.2, 0.3, 0.9),
+        ] {
+            assert!((round_trip(rgb) - rgb).length() < 0.1);
+        }
+    }
+}
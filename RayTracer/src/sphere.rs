@@ -60,6 +60,19 @@ impl Sphere {
         *u = phi / (2.0 * PI);
         *v = theta / PI;
     }
+
+    /// partial derivatives of the surface point w.r.t. the uv of get_sphere_uv
+    pub fn get_sphere_tangents(p: Vec3, radius: f64) -> (Vec3, Vec3) {
+        let p = radius * p;
+        let dpdu = 2.0 * PI * Vec3::new(p.z, 0.0, -p.x);
+        let r_xz = (p.x * p.x + p.z * p.z).sqrt();
+        let dpdv = if r_xz > 1e-12 {
+            PI * Vec3::new(-p.x * p.y / r_xz, r_xz, -p.y * p.z / r_xz)
+        } else {
+            PI * Vec3::new(radius, 0.0, 0.0)
+        };
+        (dpdu, dpdv)
+    }
 }
 
 impl Hittable for Sphere {
@@ -87,9 +100,10 @@ impl Hittable for Sphere {
         }
         rec.t = root;
         rec.point = ray_.at(rec.t);
-        let out_normal = (rec.point - self.center) / self.radius;
+        let out_normal = (rec.point - center) / self.radius;
         rec.set_face_normal(ray_, out_normal);
         Sphere::get_sphere_uv(out_normal, &mut rec.u, &mut rec.v);
+        (rec.dpdu, rec.dpdv) = Sphere::get_sphere_tangents(out_normal, self.radius);
         rec.mat_ptr = match &self.mat_ptr {
             Some(mat) => Some(mat.clone()),
             None => None,
@@ -100,6 +114,29 @@ impl Hittable for Sphere {
     fn bounding_box(&self) -> AABB {
         self.bbox.clone()
     }
+
+    fn area(&self) -> f64 {
+        4.0 * PI * self.radius * self.radius
+    }
+
+    fn sample_surface(&self, time: f64) -> Option<HitRecord> {
+        let center = if self.is_moving {
+            self.sphere_center(time)
+        } else {
+            self.center
+        };
+        let out_normal = Vec3::random_unit_vector();
+        let mut rec = HitRecord::new(
+            center + self.radius * out_normal,
+            out_normal,
+            0.0,
+            true,
+            self.mat_ptr.clone(),
+        );
+        Sphere::get_sphere_uv(out_normal, &mut rec.u, &mut rec.v);
+        (rec.dpdu, rec.dpdv) = Sphere::get_sphere_tangents(out_normal, self.radius);
+        Some(rec)
+    }
 }
 
 unsafe impl Sync for Sphere {}
@@ -0,0 +1,434 @@
+use crate::camera::Camera;
+use crate::color::write_color;
+use crate::hit::{HitRecord, Hittable};
+use crate::interval::Interval;
+use crate::light::{distant_photon, Emitters};
+use crate::onb::Onb;
+use crate::ray::Ray;
+use crate::rt::random_f64;
+use crate::vec3::Vec3;
+use image::RgbImage;
+use indicatif::ProgressBar;
+use rayon::prelude::*;
+use std::collections::HashMap;
+use std::f64::consts::PI;
+
+// stochastic progressive photon mapping (Hachisuka and Jensen 2009, in the
+// formulation of pbrt): every pass follows one camera path per pixel through
+// specular bounces to its first other surface, the visible point, then shoots
+// photons and gathers those that land within the pixel's radius of it. The radii
+// shrink over the passes, so the blur of the density estimate vanishes.
+//
+// Emitters and the environment only light the scene through photons. The analytic
+// lights are also sampled directly, their photons count from the second bounce on.
+
+/// fraction of the photons of a pass that is kept when the radius shrinks
+const ALPHA: f64 = 2.0 / 3.0;
+
+struct VisiblePoint {
+    rec: HitRecord,
+    ray_in: Ray,
+    beta: Vec3,
+}
+
+/// what a pixel gathered over the passes
+struct SppmPixel {
+    radius: f64,
+    /// light reaching the camera without a diffuse bounce, summed over the passes
+    direct: Vec3,
+    visible: Option<VisiblePoint>,
+    /// flux arriving at the visible point in the current pass
+    phi: Vec3,
+    photons: usize,
+    /// photons kept in the estimate so far
+    n: f64,
+    tau: Vec3,
+}
+
+/// visible points sorted into cubic cells as large as the largest radius
+struct Grid {
+    cell: f64,
+    cells: HashMap<(i64, i64, i64), Vec<usize>>,
+}
+
+impl Grid {
+    fn new(pixels: &[SppmPixel]) -> Self {
+        let cell = pixels
+            .iter()
+            .filter(|p| p.visible.is_some())
+            .fold(0.0, |max: f64, p| max.max(p.radius));
+        let mut grid = Self {
+            cell,
+            cells: HashMap::new(),
+        };
+        if cell <= 0.0 {
+            return grid;
+        }
+        for (index, pixel) in pixels.iter().enumerate() {
+            let point = match &pixel.visible {
+                Some(vp) => vp.rec.point,
+                None => continue,
+            };
+            let r = Vec3::ones() * pixel.radius;
+            let (lo, hi) = (grid.key(point - r), grid.key(point + r));
+            for x in lo.0..=hi.0 {
+                for y in lo.1..=hi.1 {
+                    for z in lo.2..=hi.2 {
+                        grid.cells.entry((x, y, z)).or_default().push(index);
+                    }
+                }
+            }
+        }
+        grid
+    }
+
+    fn key(&self, p: Vec3) -> (i64, i64, i64) {
+        (
+            (p.x / self.cell).floor() as i64,
+            (p.y / self.cell).floor() as i64,
+            (p.z / self.cell).floor() as i64,
+        )
+    }
+
+    /// pixels whose visible point may be within their radius of `p`
+    fn candidates(&self, p: Vec3) -> &[usize] {
+        if self.cell <= 0.0 {
+            return &[];
+        }
+        self.cells.get(&self.key(p)).map_or(&[], |v| v.as_slice())
+    }
+}
+
+pub struct Sppm<'a> {
+    camera: &'a Camera,
+    emitters: Emitters<'a>,
+    photons_per_pass: usize,
+    initial_radius: f64,
+    /// sphere around the scene that distant lights shoot their photons into
+    scene_center: Vec3,
+    scene_radius: f64,
+}
+
+impl<'a> Sppm<'a> {
+    pub fn new<T: Hittable>(
+        camera: &'a Camera,
+        world: &T,
+        photons_per_pass: usize,
+        initial_radius: f64,
+    ) -> Self {
+        // planes reach infinitely far, distant light only has to cover the rest
+        let bbox = world.finite_bounding_box();
+        let lo = Vec3::new(bbox.x.min, bbox.y.min, bbox.z.min);
+        let hi = Vec3::new(bbox.x.max, bbox.y.max, bbox.z.max);
+        let (scene_center, scene_radius) = if bbox.x.min <= bbox.x.max {
+            (0.5 * (lo + hi), 0.5 * (hi - lo).length())
+        } else {
+            // nothing but unbounded objects, light the surroundings of the camera
+            (camera.lookfrom, camera.focus_dist)
+        };
+        Self {
+            camera,
+            emitters: Emitters::new(&camera.emitters),
+            photons_per_pass,
+            initial_radius,
+            scene_center,
+            scene_radius,
+        }
+    }
+
+    /// one pass per sample of the camera
+    pub fn render<T: Hittable>(&self, world: &T, img: &mut RgbImage, bar: &ProgressBar) {
+        let (width, height) = (self.camera.width, self.camera.height);
+        let passes = self.camera.samples_per_pixel;
+        let mut pixels: Vec<SppmPixel> = (0..width * height)
+            .map(|_| SppmPixel {
+                radius: self.initial_radius,
+                direct: Vec3::zero(),
+                visible: None,
+                phi: Vec3::zero(),
+                photons: 0,
+                n: 0.0,
+                tau: Vec3::zero(),
+            })
+            .collect();
+        bar.set_length(passes as u64);
+        for _ in 0..passes {
+            pixels
+                .par_iter_mut()
+                .enumerate()
+                .for_each(|(k, pixel)| self.trace_camera(world, k % width, k / width, pixel));
+            let grid = Grid::new(&pixels);
+            let hits: Vec<Vec<(usize, Vec3)>> = (0..self.photons_per_pass)
+                .into_par_iter()
+                .fold(Vec::new, |mut hits, _| {
+                    self.trace_photon(world, &grid, &pixels, &mut hits);
+                    hits
+                })
+                .collect();
+            for (index, flux) in hits.into_iter().flatten() {
+                pixels[index].phi += flux;
+                pixels[index].photons += 1;
+            }
+            for pixel in pixels.iter_mut() {
+                if let (Some(vp), true) = (&pixel.visible, pixel.photons > 0) {
+                    let m = pixel.photons as f64;
+                    let n = pixel.n + ALPHA * m;
+                    let radius = pixel.radius * (n / (pixel.n + m)).sqrt();
+                    let shrink = (radius / pixel.radius) * (radius / pixel.radius);
+                    pixel.tau = (pixel.tau + vp.beta.cor_dot(pixel.phi)) * shrink;
+                    pixel.n = n;
+                    pixel.radius = radius;
+                }
+                pixel.phi = Vec3::zero();
+                pixel.photons = 0;
+            }
+            bar.inc(1);
+        }
+        bar.finish();
+
+        let photons = (passes * self.photons_per_pass) as f64;
+        for (k, pixel) in pixels.iter().enumerate() {
+            let area = PI * pixel.radius * pixel.radius;
+            let color = pixel.direct / passes as f64 + pixel.tau / (photons * area);
+            write_color(color, img, k % width, k / width);
+        }
+    }
+
+    /// follows a camera ray through specular bounces to the visible point of the pixel
+    fn trace_camera<T: Hittable>(&self, world: &T, i: usize, j: usize, pixel: &mut SppmPixel) {
+        let cam = self.camera;
+        pixel.visible = None;
+        let mut ray_ = match cam.get_ray(i, j) {
+            Some(ray_) => ray_,
+            None => return,
+        };
+        // the photons carry RGB
+        ray_.lambda = Vec3::zero();
+        let mut beta = Vec3::ones();
+        for _ in 0..cam.max_depth {
+            let mut rec = HitRecord::new(Vec3::zero(), Vec3::zero(), 0.0, false, None);
+            if !world.hit(&ray_, Interval::new(0.001, f64::INFINITY), &mut rec) {
+                pixel.direct += beta.cor_dot(cam.escaped(&ray_, 0.0));
+                return;
+            }
+            rec.set_footprint(&ray_);
+            beta = beta.cor_dot(ray_.media.transmittance(rec.t * ray_.dir.length()));
+            let mat = rec.mat_ptr.clone().unwrap();
+            pixel.direct += beta.cor_dot(mat.emitted(rec.u, rec.v, rec.point));
+            let mut scattered = Ray::new(Vec3::zero(), Vec3::zero(), 0.0);
+            let mut attenuation = Vec3::zero();
+            if !mat.scatter(&ray_, &rec, &mut attenuation, &mut scattered) {
+                return;
+            }
+            if mat.scattering_pdf(&ray_, &rec, scattered.dir) > 0.0 {
+                pixel.direct += beta.cor_dot(cam.sample_analytic_lights(&ray_, &rec, world));
+                pixel.visible = Some(VisiblePoint {
+                    rec,
+                    ray_in: ray_,
+                    beta,
+                });
+                return;
+            }
+            scattered.media = mat.next_media(&ray_, &rec, &scattered);
+            scattered.follow_cone(&ray_, rec.t);
+            scattered.lambda = Vec3::zero();
+            beta = beta.cor_dot(attenuation);
+            ray_ = scattered;
+        }
+    }
+
+    /// a photon ray with its power, and whether it lights the visible points directly
+    fn emit(&self) -> Option<(Ray, Vec3, bool)> {
+        let cam = self.camera;
+        let has_emitters = self.emitters.pdf() > 0.0;
+        let has_sky = cam.environment.is_some() || !cam.background.near_zero();
+        let sources = has_emitters as usize + cam.lights.len() + has_sky as usize;
+        if sources == 0 {
+            return None;
+        }
+        // every source gets the same share of the photons
+        let scale = sources as f64;
+        let mut k = ((random_f64(0.0, 1.0) * scale) as usize).min(sources - 1);
+        // photons follow the shutter curve like the camera rays they meet
+        let time = cam.sample_time();
+        if has_emitters {
+            if k == 0 {
+                let rec = self.emitters.sample(time)?;
+                let emitted = rec.mat_ptr.as_ref()?.emitted(rec.u, rec.v, rec.point);
+                // two-sided, cosine weighted on the side picked
+                let side = if random_f64(0.0, 1.0) < 0.5 {
+                    1.0
+                } else {
+                    -1.0
+                };
+                let dir = Onb::new(side * rec.normal).local(Vec3::random_cosine_direction());
+                let power = 2.0 * PI * emitted / self.emitters.pdf();
+                return Some((Ray::new(rec.point, dir, time), scale * power, true));
+            }
+            k -= 1;
+        }
+        let (center, radius) = (self.scene_center, self.scene_radius);
+        if let Some(light) = cam.lights.get(k) {
+            let (mut ray_, power) = light.sample_photon(center, radius)?;
+            ray_.time = time;
+            return Some((ray_, scale * power, false));
+        }
+        let (wi, radiance, pdf) = match &cam.environment {
+            Some(env) => {
+                let sample = env.sample()?;
+                (sample.wi, sample.radiance, sample.pdf)
+            }
+            None => (Vec3::random_unit_vector(), cam.background, 0.25 / PI),
+        };
+        let mut ray_ = distant_photon(-wi, center, radius);
+        ray_.time = time;
+        let power = PI * radius * radius * radiance / pdf;
+        Some((ray_, scale * power, true))
+    }
+
+    fn trace_photon<T: Hittable>(
+        &self,
+        world: &T,
+        grid: &Grid,
+        pixels: &[SppmPixel],
+        hits: &mut Vec<(usize, Vec3)>,
+    ) {
+        let (mut ray_, mut beta, lights_directly) = match self.emit() {
+            Some(photon) => photon,
+            None => return,
+        };
+        for depth in 0..self.camera.max_depth {
+            let mut rec = HitRecord::new(Vec3::zero(), Vec3::zero(), 0.0, false, None);
+            if !world.hit(&ray_, Interval::new(0.001, f64::INFINITY), &mut rec) {
+                return;
+            }
+            beta = beta.cor_dot(ray_.media.transmittance(rec.t * ray_.dir.length()));
+            let mat = rec.mat_ptr.clone().unwrap();
+            let mut scattered = Ray::new(Vec3::zero(), Vec3::zero(), 0.0);
+            let mut attenuation = Vec3::zero();
+            if !mat.scatter(&ray_, &rec, &mut attenuation, &mut scattered) {
+                return;
+            }
+            let diffuse = mat.scattering_pdf(&ray_, &rec, scattered.dir) > 0.0;
+            if diffuse && (depth > 0 || lights_directly) {
+                let wi = -ray_.dir.normalize();
+                for &index in grid.candidates(rec.point) {
+                    let pixel = &pixels[index];
+                    let vp = pixel.visible.as_ref().unwrap();
+                    if (vp.rec.point - rec.point).squared_length() > pixel.radius * pixel.radius {
+                        continue;
+                    }
+                    // the BSDF alone, the density estimate accounts for the cosine
+                    let cos = (vp.rec.normal * wi).abs();
+                    if cos < 1e-8 {
+                        continue;
+                    }
+                    let vp_mat = vp.rec.mat_ptr.as_ref().unwrap();
+                    let f = vp_mat.eval(&vp.ray_in, &vp.rec, wi) / cos;
+                    if !f.near_zero() {
+                        hits.push((index, beta.cor_dot(f)));
+                    }
+                }
+            }
+            scattered.media = mat.next_media(&ray_, &rec, &scattered);
+            scattered.lambda = Vec3::zero();
+            beta = beta.cor_dot(attenuation);
+            ray_ = scattered;
+            if beta.near_zero() {
+                return;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bvh::Bvh_Node;
+    use crate::camera::ShutterCurve;
+    use crate::hit_list::HitList;
+    use crate::light::DirectionalLight;
+    use crate::material::Lambertian;
+    use crate::plane::Plane;
+    use crate::sphere::Sphere;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_distant_photons_skip_unbounded_objects() {
+        let gray = Arc::new(Lambertian::new(Vec3::new(0.5, 0.5, 0.5)));
+        let mut world = HitList::new(Vec::new());
+        world.add(Arc::new(Plane::new(
+            Vec3::zero(),
+            Vec3::new(0.0, 1.0, 0.0),
+            gray.clone(),
+        )));
+        world.add(Arc::new(Sphere::new(
+            Vec3::new(0.0, 1.0, 0.0),
+            1.0,
+            Some(gray),
+        )));
+        let mut camera = Camera::init(100, 1.0);
+        camera.lights.push(Arc::new(DirectionalLight::new(
+            Vec3::new(0.3, -1.0, 0.2),
+            Vec3::ones(),
+        )));
+        let sppm = Sppm::new(&camera, &world, 10, 0.1);
+        assert!((sppm.scene_center - Vec3::new(0.0, 1.0, 0.0)).length() < 1e-9);
+        assert!((sppm.scene_radius - 3.0_f64.sqrt()).abs() < 1e-9);
+        let bvh = Bvh_Node::new_by_list(&world);
+        let sppm = Sppm::new(&camera, &bvh, 10, 0.1);
+        assert!((sppm.scene_radius - 3.0_f64.sqrt()).abs() < 1e-9);
+        for _ in 0..100 {
+            let (ray_, power) = camera.lights[0]
+                .sample_photon(sppm.scene_center, sppm.scene_radius)
+                .unwrap();
+            assert!(ray_.orig.length() < 10.0 && ray_.dir.length().is_finite());
+            assert!(power.x.is_finite() && power.x > 0.0);
+        }
+    }
+
+    #[test]
+    fn test_photon_times_follow_shutter_curve() {
+        let gray = Arc::new(Lambertian::new(Vec3::new(0.5, 0.5, 0.5)));
+        let mut world = HitList::new(Vec::new());
+        world.add(Arc::new(Sphere::new(Vec3::zero(), 1.0, Some(gray))));
+        let mut camera = Camera::init(100, 1.0);
+        camera.lights.push(Arc::new(DirectionalLight::new(
+            Vec3::new(0.0, -1.0, 0.0),
+            Vec3::ones(),
+        )));
+        (camera.shutter_open, camera.shutter_close) = (2.0, 4.0);
+        camera.shutter_curve = ShutterCurve::Custom(vec![0.0, 0.0, 0.0, 1.0]);
+        camera.update();
+        let sppm = Sppm::new(&camera, &world, 10, 0.1);
+        for _ in 0..200 {
+            let (ray_, _, _) = sppm.emit().unwrap();
+            assert!((3.5..=4.0).contains(&ray_.time));
+        }
+    }
+
+    #[test]
+    fn test_grid_finds_points_in_radius() {
+        let pixels: Vec<SppmPixel> = [0.0, 0.25, 3.0]
+            .iter()
+            .map(|&x| SppmPixel {
+                radius: 0.5,
+                direct: Vec3::zero(),
+                visible: Some(VisiblePoint {
+                    rec: HitRecord::new(Vec3::new(x, 0.0, 0.0), Vec3::zero(), 0.0, true, None),
+                    ray_in: Ray::new(Vec3::zero(), Vec3::new(1.0, 0.0, 0.0), 0.0),
+                    beta: Vec3::ones(),
+                }),
+                phi: Vec3::zero(),
+                photons: 0,
+                n: 0.0,
+                tau: Vec3::zero(),
+            })
+            .collect();
+        let grid = Grid::new(&pixels);
+        let near = grid.candidates(Vec3::new(0.4, 0.1, -0.1));
+        assert!(near.contains(&0) && near.contains(&1));
+        assert!(!near.contains(&2));
+        assert!(grid.candidates(Vec3::new(-10.0, 0.0, 0.0)).is_empty());
+    }
+}
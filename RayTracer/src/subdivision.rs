@@ -0,0 +1,635 @@
+use crate::material::Material;
+use crate::mesh::{EdgeTarget, TriangleMesh};
+use crate::vec3::Vec3;
+use std::collections::HashMap;
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum SubdivisionScheme {
+    /// for quad meshes, other polygons turn into quads after the first level
+    CatmullClark,
+    /// for triangle meshes
+    Loop,
+}
+
+fn edge_key(a: usize, b: usize) -> (usize, usize) {
+    (a.min(b), a.max(b))
+}
+
+/// weight of each neighbour of a vertex of valence n in Loop's vertex rule
+fn loop_beta(n: usize) -> f64 {
+    let c = 3.0 / 8.0 + (2.0 * PI / n as f64).cos() / 4.0;
+    (5.0 / 8.0 - c * c) / n as f64
+}
+
+fn average(points: impl Iterator<Item = Vec3>) -> Vec3 {
+    let (sum, n) = points.fold((Vec3::zero(), 0), |(sum, n), p| (sum + p, n + 1));
+    sum / n.max(1) as f64
+}
+
+/// edges in the order the faces meet them, with the faces on each side
+struct Topology {
+    edges: Vec<(usize, usize)>,
+    edge_faces: Vec<Vec<usize>>,
+    edge_index: HashMap<(usize, usize), usize>,
+    neighbours: Vec<Vec<usize>>,
+    vertex_faces: Vec<Vec<usize>>,
+}
+
+impl Topology {
+    fn new(vertices: usize, faces: &[Vec<usize>]) -> Self {
+        let mut topology = Topology {
+            edges: Vec::new(),
+            edge_faces: Vec::new(),
+            edge_index: HashMap::new(),
+            neighbours: vec![Vec::new(); vertices],
+            vertex_faces: vec![Vec::new(); vertices],
+        };
+        for (f, face) in faces.iter().enumerate() {
+            for (k, &a) in face.iter().enumerate() {
+                let b = face[(k + 1) % face.len()];
+                topology.vertex_faces[a].push(f);
+                let key = edge_key(a, b);
+                let index = match topology.edge_index.get(&key) {
+                    Some(&index) => index,
+                    None => {
+                        topology.edge_index.insert(key, topology.edges.len());
+                        topology.edges.push(key);
+                        topology.edge_faces.push(Vec::new());
+                        topology.neighbours[a].push(b);
+                        topology.neighbours[b].push(a);
+                        topology.edges.len() - 1
+                    }
+                };
+                topology.edge_faces[index].push(f);
+            }
+        }
+        topology
+    }
+
+    fn edge(&self, a: usize, b: usize) -> usize {
+        self.edge_index[&edge_key(a, b)]
+    }
+
+    /// the neighbours of v along edges with a single face
+    fn boundary_neighbours(&self, v: usize) -> Vec<usize> {
+        self.neighbours[v]
+            .iter()
+            .copied()
+            .filter(|&w| self.edge_faces[self.edge(v, w)].len() == 1)
+            .collect()
+    }
+
+    /// position of a boundary vertex with weights (1, w, 1) on it and its boundary
+    /// neighbours, None for interior vertices. Corners where the boundary does not
+    /// simply pass through stay where they are.
+    fn boundary_rule(&self, positions: &[Vec3], v: usize, w: f64) -> Option<Vec3> {
+        let boundary = self.boundary_neighbours(v);
+        match boundary[..] {
+            [] if !self.neighbours[v].is_empty() => None,
+            [a, b] => Some((positions[a] + w * positions[v] + positions[b]) / (w + 2.0)),
+            _ => Some(positions[v]),
+        }
+    }
+}
+
+/// control mesh refined by Catmull–Clark or Loop subdivision and diced into a
+/// `TriangleMesh` on the limit surface. Boundaries follow cubic B-spline curves.
+pub struct SubdivisionSurface {
+    scheme: SubdivisionScheme,
+    positions: Vec<Vec3>,
+    faces: Vec<Vec<usize>>,
+    uvs: Option<Vec<(f64, f64)>>,
+    /// where the vertices of a patch lie on the face it was made for, NaN outside of it
+    params: Option<Vec<(f64, f64)>>,
+    /// most levels a face of the tessellation is refined to, each one quarters its
+    /// triangles
+    pub max_levels: usize,
+}
+
+impl SubdivisionSurface {
+    /// faces with fewer than three corners are skipped, those of Loop surfaces with
+    /// more than three are split into fans
+    pub fn new(scheme: SubdivisionScheme, positions: Vec<Vec3>, faces: Vec<Vec<usize>>) -> Self {
+        let faces = faces.into_iter().filter(|face| face.len() >= 3);
+        let faces = match scheme {
+            SubdivisionScheme::CatmullClark => faces.collect(),
+            SubdivisionScheme::Loop => faces
+                .flat_map(|face| {
+                    (1..face.len() - 1).map(move |k| vec![face[0], face[k], face[k + 1]])
+                })
+                .collect(),
+        };
+        Self {
+            scheme,
+            positions,
+            faces,
+            uvs: None,
+            params: None,
+            max_levels: 6,
+        }
+    }
+
+    /// per-vertex uvs, interpolated linearly over the refined faces
+    pub fn with_uvs(mut self, uvs: Vec<(f64, f64)>) -> Self {
+        self.uvs = Some(uvs);
+        self
+    }
+
+    fn catmull_clark(&self) -> Self {
+        let topology = Topology::new(self.positions.len(), &self.faces);
+        let p = &self.positions;
+        let (nv, nf) = (p.len(), self.faces.len());
+        let face_points: Vec<Vec3> = self
+            .faces
+            .iter()
+            .map(|face| average(face.iter().map(|&i| p[i])))
+            .collect();
+        // new vertices first, then one per face, then one per edge
+        let mut positions = Vec::with_capacity(nv + nf + topology.edges.len());
+        for v in 0..nv {
+            let point = topology.boundary_rule(p, v, 6.0).unwrap_or_else(|| {
+                let n = topology.neighbours[v].len() as f64;
+                let q = average(topology.vertex_faces[v].iter().map(|&f| face_points[f]));
+                let r = average(topology.neighbours[v].iter().map(|&w| (p[v] + p[w]) / 2.0));
+                (q + 2.0 * r + (n - 3.0) * p[v]) / n
+            });
+            positions.push(point);
+        }
+        positions.extend(face_points.iter().copied());
+        for (&(a, b), faces) in topology.edges.iter().zip(&topology.edge_faces) {
+            positions.push(match faces[..] {
+                [f, g] => (p[a] + p[b] + face_points[f] + face_points[g]) / 4.0,
+                _ => (p[a] + p[b]) / 2.0,
+            });
+        }
+        let edge_point = |a: usize, b: usize| nv + nf + topology.edge(a, b);
+        let mut faces = Vec::with_capacity(4 * nf);
+        for (f, face) in self.faces.iter().enumerate() {
+            let k = face.len();
+            for i in 0..k {
+                let (prev, v, next) = (face[(i + k - 1) % k], face[i], face[(i + 1) % k]);
+                faces.push(vec![v, edge_point(v, next), nv + f, edge_point(prev, v)]);
+            }
+        }
+        let uvs = self
+            .uvs
+            .as_ref()
+            .map(|uvs| self.refine_pairs(&topology, uvs));
+        let params = self
+            .params
+            .as_ref()
+            .map(|p| self.refine_pairs(&topology, p));
+        Self {
+            scheme: self.scheme,
+            positions,
+            faces,
+            uvs,
+            params,
+            max_levels: self.max_levels,
+        }
+    }
+
+    fn loop_subdivision(&self) -> Self {
+        let topology = Topology::new(self.positions.len(), &self.faces);
+        let p = &self.positions;
+        let nv = p.len();
+        // new vertices first, then one per edge
+        let mut positions = Vec::with_capacity(nv + topology.edges.len());
+        for v in 0..nv {
+            let point = topology.boundary_rule(p, v, 6.0).unwrap_or_else(|| {
+                let n = topology.neighbours[v].len();
+                let beta = loop_beta(n);
+                let sum = topology.neighbours[v]
+                    .iter()
+                    .fold(Vec3::zero(), |sum, &w| sum + p[w]);
+                (1.0 - n as f64 * beta) * p[v] + beta * sum
+            });
+            positions.push(point);
+        }
+        let opposite = |f: usize, a: usize, b: usize| {
+            let face = &self.faces[f];
+            face.iter()
+                .copied()
+                .find(|&c| c != a && c != b)
+                .unwrap_or(a)
+        };
+        for (&(a, b), faces) in topology.edges.iter().zip(&topology.edge_faces) {
+            positions.push(match faces[..] {
+                [f, g] => {
+                    let (c, d) = (opposite(f, a, b), opposite(g, a, b));
+                    3.0 / 8.0 * (p[a] + p[b]) + 1.0 / 8.0 * (p[c] + p[d])
+                }
+                _ => (p[a] + p[b]) / 2.0,
+            });
+        }
+        let edge_point = |a: usize, b: usize| nv + topology.edge(a, b);
+        let mut faces = Vec::with_capacity(4 * self.faces.len());
+        for face in &self.faces {
+            let (a, b, c) = (face[0], face[1], face[2]);
+            let (ab, bc, ca) = (edge_point(a, b), edge_point(b, c), edge_point(c, a));
+            faces.push(vec![a, ab, ca]);
+            faces.push(vec![ab, b, bc]);
+            faces.push(vec![ca, bc, c]);
+            faces.push(vec![ab, bc, ca]);
+        }
+        let uvs = self
+            .uvs
+            .as_ref()
+            .map(|uvs| self.refine_pairs(&topology, uvs));
+        let params = self
+            .params
+            .as_ref()
+            .map(|p| self.refine_pairs(&topology, p));
+        Self {
+            scheme: self.scheme,
+            positions,
+            faces,
+            uvs,
+            params,
+            max_levels: self.max_levels,
+        }
+    }
+
+    /// per-vertex pairs like uvs for the vertices of the next level, averaged over the
+    /// faces and edges the new vertices stand for
+    fn refine_pairs(&self, topology: &Topology, pairs: &[(f64, f64)]) -> Vec<(f64, f64)> {
+        let mut refined = pairs.to_vec();
+        if self.scheme == SubdivisionScheme::CatmullClark {
+            refined.extend(self.faces.iter().map(|face| {
+                let n = face.len() as f64;
+                face.iter().fold((0.0, 0.0), |(u, v), &i| {
+                    (u + pairs[i].0 / n, v + pairs[i].1 / n)
+                })
+            }));
+        }
+        refined.extend(topology.edges.iter().map(|&(a, b)| {
+            (
+                (pairs[a].0 + pairs[b].0) / 2.0,
+                (pairs[a].1 + pairs[b].1) / 2.0,
+            )
+        }));
+        refined
+    }
+
+    /// one level of refinement
+    pub fn subdivide(&self) -> Self {
+        match self.scheme {
+            SubdivisionScheme::CatmullClark => self.catmull_clark(),
+            SubdivisionScheme::Loop => self.loop_subdivision(),
+        }
+    }
+
+    /// where the vertices end up after infinitely many levels, for a mesh that has
+    /// been refined at least once
+    fn limit_positions(&self) -> Vec<Vec3> {
+        let topology = Topology::new(self.positions.len(), &self.faces);
+        let p = &self.positions;
+        (0..p.len())
+            .map(|v| {
+                if let Some(point) = topology.boundary_rule(p, v, 4.0) {
+                    return point;
+                }
+                let n = topology.neighbours[v].len();
+                let edges = topology.neighbours[v]
+                    .iter()
+                    .fold(Vec3::zero(), |sum, &w| sum + p[w]);
+                match self.scheme {
+                    SubdivisionScheme::CatmullClark => {
+                        // the corner across each quad around the vertex
+                        let corners =
+                            topology.vertex_faces[v]
+                                .iter()
+                                .fold(Vec3::zero(), |sum, &f| {
+                                    let face = &self.faces[f];
+                                    let k = face.iter().position(|&c| c == v).unwrap_or(0);
+                                    sum + p[face[(k + 2) % face.len()]]
+                                });
+                        let n = n as f64;
+                        (n * n * p[v] + 4.0 * edges + corners) / (n * (n + 5.0))
+                    }
+                    SubdivisionScheme::Loop => {
+                        let gamma = 1.0 / (3.0 / (8.0 * loop_beta(n)) + n as f64);
+                        (1.0 - n as f64 * gamma) * p[v] + gamma * edges
+                    }
+                }
+            })
+            .collect()
+    }
+
+    /// the surface over just `faces`, without the vertices they no longer use
+    fn restricted(&self, faces: Vec<Vec<usize>>) -> Self {
+        let mut index = HashMap::new();
+        let mut used = Vec::new();
+        let faces = faces
+            .into_iter()
+            .map(|face| {
+                face.into_iter()
+                    .map(|v| {
+                        *index.entry(v).or_insert_with(|| {
+                            used.push(v);
+                            used.len() - 1
+                        })
+                    })
+                    .collect()
+            })
+            .collect();
+        let pick = |pairs: &Vec<(f64, f64)>| used.iter().map(|&v| pairs[v]).collect();
+        Self {
+            scheme: self.scheme,
+            positions: used.iter().map(|&v| self.positions[v]).collect(),
+            faces,
+            uvs: self.uvs.as_ref().map(pick),
+            params: self.params.as_ref().map(pick),
+            max_levels: self.max_levels,
+        }
+    }
+
+    /// face f with the faces that share a vertex with it, which settle the limit surface
+    /// over f at every level. The corners of f get `corners` as their parameters.
+    fn patch(&self, topology: &Topology, f: usize, corners: &[(f64, f64)]) -> Self {
+        let mut ring: Vec<usize> = self.faces[f]
+            .iter()
+            .flat_map(|&v| topology.vertex_faces[v].iter().copied())
+            .filter(|&g| g != f)
+            .collect();
+        ring.sort_unstable();
+        ring.dedup();
+        // f comes first, so its corners are the first vertices of the patch
+        let faces = std::iter::once(f)
+            .chain(ring)
+            .map(|g| self.faces[g].clone())
+            .collect();
+        let mut patch = self.restricted(faces);
+        let mut params = vec![(f64::NAN, f64::NAN); patch.positions.len()];
+        params[..corners.len()].copy_from_slice(corners);
+        patch.params = Some(params);
+        patch
+    }
+
+    /// one level of refinement of a patch, dropping the faces that no longer share a
+    /// vertex with the face it was made for
+    fn refine_patch(&self) -> Self {
+        let refined = self.subdivide();
+        let params = refined.params.as_deref().unwrap_or_default();
+        let ring = refined
+            .faces
+            .iter()
+            .filter(|face| face.iter().any(|&v| !params[v].0.is_nan()))
+            .cloned()
+            .collect();
+        refined.restricted(ring)
+    }
+
+    /// refines each face of the surface on its own until its edges meet the target,
+    /// then dices it into triangles with smooth normals. Every edge gets a power of two
+    /// segments, like the edges of Bézier patches, and faces refined further snap their
+    /// border onto them, so neighbours refined to different levels share the vertices
+    /// along their edge and the mesh stays watertight.
+    pub fn tessellate(&self, target: &EdgeTarget, mat: Arc<dyn Material>) -> TriangleMesh {
+        // after one level the faces of either scheme all have the same shape
+        let base = self.subdivide();
+        let topology = Topology::new(base.positions.len(), &base.faces);
+        let most = 1 << self.max_levels;
+        let rates: Vec<usize> = topology
+            .edges
+            .iter()
+            .map(|&(a, b)| {
+                target
+                    .segments(&[base.positions[a], base.positions[b]])
+                    .min(most)
+            })
+            .collect();
+        let mut positions = Vec::new();
+        let mut uvs = base.uvs.as_ref().map(|_| Vec::new());
+        let mut indices = Vec::new();
+        // vertices on the corners and edges of the faces, shared with their neighbours
+        let mut shared: HashMap<Site, usize> = HashMap::new();
+        for (f, face) in base.faces.iter().enumerate() {
+            let quad = face.len() == 4;
+            let corners: &[(f64, f64)] = if quad {
+                &[(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)]
+            } else {
+                &[(0.0, 0.0), (1.0, 0.0), (0.0, 1.0)]
+            };
+            let sides: Vec<usize> = (0..face.len())
+                .map(|k| topology.edge(face[k], face[(k + 1) % face.len()]))
+                .collect();
+            let n = sides.iter().map(|&e| rates[e]).max().unwrap_or(1);
+            let mut patch = base.patch(&topology, f, corners);
+            for _ in 0..n.trailing_zeros() {
+                patch = patch.refine_patch();
+            }
+            let limit = patch.limit_positions();
+            let steps = |x: f64| (x * n as f64).round() as usize;
+            let grid: HashMap<(usize, usize), usize> = patch
+                .params
+                .iter()
+                .flatten()
+                .enumerate()
+                .filter(|(_, p)| !p.0.is_nan())
+                .map(|(v, &(s, t))| ((steps(s), steps(t)), v))
+                .collect();
+            // the side of the face (i, j) lies on and how many steps along it
+            let side = |i: usize, j: usize| {
+                let sides = if quad {
+                    vec![(j == 0, i), (i == n, j), (j == n, n - i), (i == 0, n - j)]
+                } else {
+                    vec![(j == 0, i), (i + j == n, j), (i == 0, n - j)]
+                };
+                let k = sides.iter().position(|&(on, _)| on)?;
+                Some((k, sides[k].1))
+            };
+            let mut vertex = |i: usize, j: usize| {
+                // points on the border snap to the nearest vertex of their edge
+                let (site, (i, j)) = match side(i, j) {
+                    Some((k, step)) => {
+                        let m = rates[sides[k]];
+                        let r = (2 * step * m + n) / (2 * n);
+                        let (a, b) = (face[k], face[(k + 1) % face.len()]);
+                        let site = match r {
+                            0 => Site::Corner(a),
+                            r if r == m => Site::Corner(b),
+                            r if a < b => Site::Edge(sides[k], r),
+                            r => Site::Edge(sides[k], m - r),
+                        };
+                        let (from, to) = (corners[k], corners[(k + 1) % corners.len()]);
+                        let along = |x: f64, y: f64| steps(x + (y - x) * r as f64 / m as f64);
+                        (Some(site), (along(from.0, to.0), along(from.1, to.1)))
+                    }
+                    None => (None, (i, j)),
+                };
+                let v = grid[&(i, j)];
+                let mut push = || {
+                    positions.push(limit[v]);
+                    if let (Some(uvs), Some(patch_uvs)) = (uvs.as_mut(), patch.uvs.as_ref()) {
+                        uvs.push(patch_uvs[v]);
+                    }
+                    positions.len() - 1
+                };
+                match site {
+                    Some(site) => *shared.entry(site).or_insert_with(push),
+                    None => push(),
+                }
+            };
+            let mut index = HashMap::new();
+            for j in 0..=n {
+                for i in (0..=n).filter(|&i| quad || i + j <= n) {
+                    index.insert((i, j), vertex(i, j));
+                }
+            }
+            // triangles squashed flat by the snapping are left out
+            let mut triangle = |a, b, c| {
+                let [a, b, c] = [a, b, c].map(|corner| index[&corner]);
+                if a != b && b != c && c != a {
+                    indices.push([a, b, c]);
+                }
+            };
+            for j in 0..n {
+                for i in 0..n {
+                    if quad {
+                        triangle((i, j), (i + 1, j), (i + 1, j + 1));
+                        triangle((i, j), (i + 1, j + 1), (i, j + 1));
+                    } else if i + j < n {
+                        triangle((i, j), (i + 1, j), (i, j + 1));
+                        if i + j + 1 < n {
+                            triangle((i + 1, j), (i + 1, j + 1), (i, j + 1));
+                        }
+                    }
+                }
+            }
+        }
+        let mut mesh = TriangleMesh::new(positions, indices, mat);
+        if let Some(uvs) = uvs {
+            mesh = mesh.with_uvs(uvs);
+        }
+        mesh.compute_normals();
+        mesh
+    }
+}
+
+/// a vertex of a tessellation that faces share: a corner of the once refined surface,
+/// or the k-th vertex along an edge from its lower numbered end
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+enum Site {
+    Corner(usize),
+    Edge(usize, usize),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::camera::Camera;
+    use crate::material::Lambertian;
+
+    fn closed(mesh: &TriangleMesh) -> bool {
+        let mut edges: HashMap<(usize, usize), usize> = HashMap::new();
+        for &[a, b, c] in &mesh.indices {
+            for (x, y) in [(a, b), (b, c), (c, a)] {
+                *edges.entry(edge_key(x, y)).or_insert(0) += 1;
+            }
+        }
+        edges.values().all(|&n| n == 2)
+    }
+
+    #[test]
+    fn test_cube_and_tetrahedron_limit_surfaces() {
+        let mut camera = Camera::init(200, 1.0);
+        camera.lookfrom = Vec3::new(0.0, 0.0, 10.0);
+        let target = EdgeTarget::new(&camera, 4.0);
+        let mat = Arc::new(Lambertian::new(Vec3::ones()));
+
+        let mesh =
+            cube(QUADS.iter().map(|q| q.to_vec()).collect()).tessellate(&target, mat.clone());
+        assert!(closed(&mesh));
+        // the limit of the corners is the same for each of them, inside the cube
+        let radii: Vec<f64> = mesh
+            .positions
+            .iter()
+            .filter(|p| {
+                (p.x.abs() - p.y.abs()).abs() < 1e-12 && (p.y.abs() - p.z.abs()).abs() < 1e-12
+            })
+            .map(|p| p.length())
+            .collect();
+        assert_eq!(radii.len(), 8);
+        assert!(radii.iter().all(|r| (r - radii[0]).abs() < 1e-12));
+        assert!(mesh
+            .positions
+            .iter()
+            .all(|p| p.x.abs() < 1.0 && p.y.abs() < 1.0 && p.z.abs() < 1.0));
+
+        let tetrahedron = SubdivisionSurface::new(
+            SubdivisionScheme::Loop,
+            vec![
+                Vec3::new(1.0, 1.0, 1.0),
+                Vec3::new(1.0, -1.0, -1.0),
+                Vec3::new(-1.0, 1.0, -1.0),
+                Vec3::new(-1.0, -1.0, 1.0),
+            ],
+            vec![vec![0, 1, 2], vec![0, 3, 1], vec![0, 2, 3], vec![1, 3, 2]],
+        );
+        let mesh = tetrahedron.tessellate(&target, mat);
+        assert!(closed(&mesh));
+        let centroid = average(mesh.positions.iter().copied());
+        assert!(centroid.length() < 1e-12);
+        // smooth normals point away from the center
+        let normals = mesh.normals.as_ref().unwrap();
+        assert!(mesh
+            .positions
+            .iter()
+            .zip(normals)
+            .all(|(&p, &n)| p * n > 0.0));
+    }
+
+    const QUADS: [[usize; 4]; 6] = [
+        [0, 2, 3, 1],
+        [4, 5, 7, 6],
+        [0, 1, 5, 4],
+        [2, 6, 7, 3],
+        [0, 4, 6, 2],
+        [1, 3, 7, 5],
+    ];
+
+    /// the corners of the cube from -1 to 1
+    fn cube(faces: Vec<Vec<usize>>) -> SubdivisionSurface {
+        let corners = (0..8)
+            .map(|k| {
+                let coord = |bit: usize| if k & bit != 0 { 1.0 } else { -1.0 };
+                Vec3::new(coord(1), coord(2), coord(4))
+            })
+            .collect();
+        SubdivisionSurface::new(SubdivisionScheme::CatmullClark, corners, faces)
+    }
+
+    #[test]
+    fn test_faces_refined_to_their_own_distance() {
+        // degenerate faces are skipped
+        let mut faces: Vec<Vec<usize>> = QUADS.iter().map(|q| q.to_vec()).collect();
+        faces.push(Vec::new());
+        faces.push(vec![0, 1]);
+        let cube = cube(faces);
+        let mut camera = Camera::init(200, 1.0);
+        camera.lookfrom = Vec3::new(0.0, 0.0, 2.0);
+        let target = EdgeTarget::new(&camera, 2.0);
+        let mesh = cube.tessellate(&target, Arc::new(Lambertian::new(Vec3::ones())));
+        // faces refined to different levels still share the vertices along their edges
+        assert!(closed(&mesh));
+        let centroid = |t: &[usize; 3]| {
+            (mesh.positions[t[0]] + mesh.positions[t[1]] + mesh.positions[t[2]]) / 3.0
+        };
+        let near = mesh.indices.iter().filter(|t| centroid(t).z > 0.5).count();
+        let far = mesh.indices.iter().filter(|t| centroid(t).z < -0.5).count();
+        assert!(near >= 4 * far);
+        // the whole surface at the level of its nearest face has many more triangles
+        let uniform = 6 * 4 * 2 * (1usize << cube.max_levels).pow(2);
+        assert!(2 * mesh.indices.len() < uniform);
+        // and the near face meets the target
+        for t in mesh.indices.iter().filter(|t| centroid(t).z > 0.9) {
+            for k in 0..3 {
+                let (a, b) = (mesh.positions[t[k]], mesh.positions[t[(k + 1) % 3]]);
+                assert!((b - a).length() <= target.max_length(a).max(target.max_length(b)));
+            }
+        }
+    }
+}
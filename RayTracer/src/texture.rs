@@ -1,11 +1,26 @@
-use crate::interval::Interval;
+use crate::color::srgb_to_linear;
+use crate::hit::HitRecord;
 use crate::perlin::Perlin;
 use crate::vec3::Vec3;
-use image::{DynamicImage, GenericImageView, ImageError};
+use image::codecs::hdr::HdrDecoder;
+use image::{DynamicImage, ImageError};
+use std::fs::{self, File};
+use std::io::{self, BufReader};
+use std::ops::{Add, AddAssign, Mul};
 use std::path::Path;
 use std::sync::Arc;
-pub trait Texture {
+pub trait Texture: Send + Sync {
     fn value(&self, u: f64, v: f64, p: Vec3) -> Vec3;
+
+    /// value at a hit, textures that filter read the footprint of the hit from it
+    fn value_at(&self, rec: &HitRecord) -> Vec3 {
+        self.value(rec.u, rec.v, rec.point)
+    }
+
+    /// coverage at a hit, 1 where the surface is solid
+    fn alpha_at(&self, _rec: &HitRecord) -> f64 {
+        1.0
+    }
 }
 pub struct SolidColor {
     albedo: Vec3,
@@ -66,32 +81,463 @@ impl Texture for CheckerTexture {
     }
 }
 
+/// how `ImageTexture` reconstructs the image between and across texels
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TextureFilter {
+    Nearest,
+    Bilinear,
+    /// Catmull–Rom over the 4 by 4 texels around the lookup
+    Bicubic,
+    /// bilinear in the two mip levels around the longer axis of the footprint, blended
+    Trilinear,
+    /// elliptically weighted average over the footprint, sharp along its shorter axis
+    Ewa,
+}
+
+/// what lookups outside [0, 1] see along one axis
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum WrapMode {
+    Repeat,
+    Clamp,
+    Mirror,
+}
+
+impl WrapMode {
+    /// texel index for x on an axis of n texels
+    fn apply(self, x: i64, n: usize) -> usize {
+        let n = n as i64;
+        let x = match self {
+            WrapMode::Repeat => x.rem_euclid(n),
+            WrapMode::Clamp => x.clamp(0, n - 1),
+            WrapMode::Mirror => {
+                let m = x.rem_euclid(2 * n);
+                if m < n {
+                    m
+                } else {
+                    2 * n - 1 - m
+                }
+            }
+        };
+        x as usize
+    }
+}
+
+/// how the stored values of an image turn into the linear values rendering works with
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ColorSpace {
+    /// the sRGB transfer curve, the default for 8 and 16 bit images
+    Srgb,
+    /// values as stored, the default for float images and right for data like normal,
+    /// height or roughness maps
+    Linear,
+}
+
+/// filtered color and coverage
+#[derive(Clone, Copy, Default)]
+struct Texel {
+    color: Vec3,
+    alpha: f64,
+}
+
+impl Add for Texel {
+    type Output = Texel;
+
+    fn add(self, other: Texel) -> Texel {
+        Texel {
+            color: self.color + other.color,
+            alpha: self.alpha + other.alpha,
+        }
+    }
+}
+
+impl AddAssign for Texel {
+    fn add_assign(&mut self, other: Texel) {
+        *self = *self + other;
+    }
+}
+
+impl From<[f32; 4]> for Texel {
+    fn from([r, g, b, a]: [f32; 4]) -> Texel {
+        Texel {
+            color: Vec3::new(r as f64, g as f64, b as f64),
+            alpha: a as f64,
+        }
+    }
+}
+
+impl Texel {
+    /// the stored form, linear RGBA
+    fn packed(&self) -> [f32; 4] {
+        let c = self.color;
+        [c.x as f32, c.y as f32, c.z as f32, self.alpha as f32]
+    }
+}
+
+impl Mul<Texel> for f64 {
+    type Output = Texel;
+
+    fn mul(self, texel: Texel) -> Texel {
+        Texel {
+            color: self * texel.color,
+            alpha: self * texel.alpha,
+        }
+    }
+}
+
+/// one level of the pyramid. Texels are stored in single precision to halve the
+/// memory, filters widen them to `Texel` to sum them up.
+#[derive(Clone)]
+struct MipLevel {
+    width: usize,
+    height: usize,
+    texels: Vec<[f32; 4]>,
+}
+
+impl MipLevel {
+    /// half the size rounded up, each texel the mean of the 2 by 2 texels under it
+    fn downsample(&self) -> Self {
+        let (width, height) = (self.width.div_ceil(2), self.height.div_ceil(2));
+        let at = |x: usize, y: usize| {
+            Texel::from(self.texels[y.min(self.height - 1) * self.width + x.min(self.width - 1)])
+        };
+        let texels = (0..width * height)
+            .map(|k| {
+                let (x, y) = (2 * (k % width), 2 * (k / width));
+                (0.25 * (at(x, y) + at(x + 1, y) + at(x, y + 1) + at(x + 1, y + 1))).packed()
+            })
+            .collect();
+        Self {
+            width,
+            height,
+            texels,
+        }
+    }
+}
+
+/// Catmull–Rom weights of the four texels around a lookup f of the way past the second
+fn catmull_rom(f: f64) -> [f64; 4] {
+    [
+        f * (-0.5 + f * (1.0 - 0.5 * f)),
+        1.0 + f * f * (-2.5 + 1.5 * f),
+        f * (0.5 + f * (2.0 - 1.5 * f)),
+        f * f * (-0.5 + 0.5 * f),
+    ]
+}
+
+/// width, height and RGBA rows from the top of a portable float map, color or gray
+fn read_pfm(path: &Path) -> io::Result<(usize, usize, Vec<[f32; 4]>)> {
+    let data = fs::read(path)?;
+    let invalid = || io::Error::new(io::ErrorKind::InvalidData, "malformed PFM file");
+    // the header is four whitespace separated words and a single whitespace byte
+    let mut words = Vec::new();
+    let mut at = 0;
+    while words.len() < 4 {
+        while at < data.len() && data[at].is_ascii_whitespace() {
+            at += 1;
+        }
+        let start = at;
+        while at < data.len() && !data[at].is_ascii_whitespace() {
+            at += 1;
+        }
+        if start == at {
+            return Err(invalid());
+        }
+        words.push(std::str::from_utf8(&data[start..at]).map_err(|_| invalid())?);
+    }
+    at += 1;
+    let channels = match words[0] {
+        "PF" => 3,
+        "Pf" => 1,
+        _ => return Err(invalid()),
+    };
+    let number = |word: &str| word.parse::<f64>().map_err(|_| invalid());
+    let (width, height) = (number(words[1])? as usize, number(words[2])? as usize);
+    // a negative scale marks little endian floats
+    let little_endian = number(words[3])? < 0.0;
+    let body = data.get(at.min(data.len())..).ok_or_else(invalid)?;
+    if body.len() < 4 * channels * width * height {
+        return Err(invalid());
+    }
+    let floats: Vec<f32> = body
+        .chunks_exact(4)
+        .take(channels * width * height)
+        .map(|b| {
+            let b = [b[0], b[1], b[2], b[3]];
+            if little_endian {
+                f32::from_le_bytes(b)
+            } else {
+                f32::from_be_bytes(b)
+            }
+        })
+        .collect();
+    // rows are stored from the bottom up
+    let mut rgba = Vec::with_capacity(width * height);
+    for row in floats.chunks_exact(channels * width).rev() {
+        rgba.extend(row.chunks_exact(channels).map(|c| match c {
+            [r, g, b] => [*r, *g, *b, 1.0],
+            _ => [c[0], c[0], c[0], 1.0],
+        }));
+    }
+    Ok((width, height, rgba))
+}
+
+#[derive(Clone)]
 pub struct ImageTexture {
-    image: DynamicImage,
+    /// the image and its mip pyramid, halving down to a single texel
+    levels: Vec<MipLevel>,
+    pub filter: TextureFilter,
+    /// wrap modes along u and v, by default around a sphere: repeating along the
+    /// equator and clamped at the poles, which must not blend into each other
+    pub wrap: [WrapMode; 2],
+    /// longest ratio of the axes of the EWA footprint, longer ones are widened to it
+    pub max_anisotropy: f64,
 }
 
 impl ImageTexture {
+    /// 8 and 16 bit images are read as sRGB colors. Radiance .hdr, .pfm and float
+    /// images like OpenEXR keep their full range and are taken as linear.
     pub fn new(filename: &str) -> Result<Self, ImageError> {
-        let image = image::open(Path::new(filename))?;
-        Ok(Self { image })
+        ImageTexture::load(filename, None)
+    }
+
+    pub fn new_by_color_space(filename: &str, space: ColorSpace) -> Result<Self, ImageError> {
+        ImageTexture::load(filename, Some(space))
+    }
+
+    fn load(filename: &str, space: Option<ColorSpace>) -> Result<Self, ImageError> {
+        let path = Path::new(filename);
+        let extension = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .unwrap_or("")
+            .to_ascii_lowercase();
+        let space_or_linear = space.unwrap_or(ColorSpace::Linear);
+        match extension.as_str() {
+            // image::open would squeeze these into 8 bits
+            "hdr" => {
+                let decoder = HdrDecoder::new(BufReader::new(File::open(path)?))?;
+                let meta = decoder.metadata();
+                let rgba = decoder
+                    .read_image_hdr()?
+                    .iter()
+                    .map(|p| [p[0], p[1], p[2], 1.0])
+                    .collect();
+                let (width, height) = (meta.width as usize, meta.height as usize);
+                Ok(Self::from_rgba(width, height, rgba, space_or_linear))
+            }
+            "pfm" => {
+                let (width, height, rgba) = read_pfm(path)?;
+                Ok(Self::from_rgba(width, height, rgba, space_or_linear))
+            }
+            _ => {
+                let image = image::open(path)?;
+                let is_float = matches!(
+                    image,
+                    DynamicImage::ImageRgb32F(_) | DynamicImage::ImageRgba32F(_)
+                );
+                let space = match space {
+                    Some(space) => space,
+                    None if is_float => ColorSpace::Linear,
+                    None => ColorSpace::Srgb,
+                };
+                Ok(Self::from_image(&image, space))
+            }
+        }
+    }
+
+    /// texture from an image in memory, its integer channels scaled to [0, 1]
+    pub fn from_image(image: &DynamicImage, space: ColorSpace) -> Self {
+        let rgba = image.to_rgba32f();
+        let (width, height) = (rgba.width() as usize, rgba.height() as usize);
+        let texels = rgba.pixels().map(|p| p.0).collect();
+        Self::from_rgba(width, height, texels, space)
+    }
+
+    fn from_rgba(width: usize, height: usize, rgba: Vec<[f32; 4]>, space: ColorSpace) -> Self {
+        let decode = |c: f32| match space {
+            ColorSpace::Srgb => srgb_to_linear(c as f64) as f32,
+            ColorSpace::Linear => c,
+        };
+        // alpha is linear coverage in either space
+        let texels = rgba
+            .iter()
+            .map(|&[r, g, b, a]| [decode(r), decode(g), decode(b), a])
+            .collect();
+        let mut levels = Vec::new();
+        if width > 0 && height > 0 {
+            levels.push(MipLevel {
+                width,
+                height,
+                texels,
+            });
+            while let Some(last) = levels.last().filter(|l| l.width > 1 || l.height > 1) {
+                let next = last.downsample();
+                levels.push(next);
+            }
+        }
+        Self {
+            levels,
+            filter: TextureFilter::Ewa,
+            wrap: [WrapMode::Repeat, WrapMode::Clamp],
+            max_anisotropy: 8.0,
+        }
+    }
+
+    fn texel(&self, level: usize, x: i64, y: i64) -> Texel {
+        let l = &self.levels[level];
+        let (x, y) = (
+            self.wrap[0].apply(x, l.width),
+            self.wrap[1].apply(y, l.height),
+        );
+        Texel::from(l.texels[y * l.width + x])
+    }
+
+    /// continuous texel coordinates of (u, v) in a level, texel centers on the integers
+    fn texel_coords(&self, level: usize, u: f64, v: f64) -> (f64, f64) {
+        let l = &self.levels[level];
+        (u * l.width as f64 - 0.5, (1.0 - v) * l.height as f64 - 0.5)
+    }
+
+    fn bilinear(&self, level: usize, u: f64, v: f64) -> Texel {
+        let (s, t) = self.texel_coords(level, u, v);
+        let (x, y) = (s.floor(), t.floor());
+        let (fx, fy) = (s - x, t - y);
+        let (x, y) = (x as i64, y as i64);
+        (1.0 - fy) * ((1.0 - fx) * self.texel(level, x, y) + fx * self.texel(level, x + 1, y))
+            + fy * ((1.0 - fx) * self.texel(level, x, y + 1) + fx * self.texel(level, x + 1, y + 1))
+    }
+
+    fn bicubic(&self, u: f64, v: f64) -> Texel {
+        let (s, t) = self.texel_coords(0, u, v);
+        let (x, y) = (s.floor(), t.floor());
+        let (wx, wy) = (catmull_rom(s - x), catmull_rom(t - y));
+        let (x, y) = (x as i64, y as i64);
+        let mut sum = Texel::default();
+        for (j, wy) in wy.iter().enumerate() {
+            for (i, wx) in wx.iter().enumerate() {
+                sum += wx * wy * self.texel(0, x + i as i64 - 1, y + j as i64 - 1);
+            }
+        }
+        // the negative lobes may overshoot out of range
+        let c = sum.color;
+        Texel {
+            color: Vec3::new(c.x.max(0.0), c.y.max(0.0), c.z.max(0.0)),
+            alpha: sum.alpha.clamp(0.0, 1.0),
+        }
+    }
+
+    /// the footprint axes in texels of the full size image
+    fn texel_axes(&self, footprint: [(f64, f64); 2]) -> [(f64, f64); 2] {
+        let l = &self.levels[0];
+        footprint.map(|(du, dv)| (du * l.width as f64, dv * l.height as f64))
+    }
+
+    /// blend of `lookup` at the two levels around the fractional level `lod`
+    fn between_levels(&self, lod: f64, lookup: impl Fn(usize) -> Texel) -> Texel {
+        let lod = lod.clamp(0.0, (self.levels.len() - 1) as f64);
+        let level = lod.floor() as usize;
+        let f = lod - level as f64;
+        if f == 0.0 {
+            return lookup(level);
+        }
+        (1.0 - f) * lookup(level) + f * lookup(level + 1)
+    }
+
+    fn trilinear(&self, u: f64, v: f64, footprint: [(f64, f64); 2]) -> Texel {
+        let [a, b] = self.texel_axes(footprint);
+        let radius = a.0.hypot(a.1).max(b.0.hypot(b.1));
+        self.between_levels((2.0 * radius).log2(), |level| self.bilinear(level, u, v))
+    }
+
+    fn ewa(&self, u: f64, v: f64, footprint: [(f64, f64); 2]) -> Texel {
+        let [mut major, mut minor] = self.texel_axes(footprint);
+        if major.0.hypot(major.1) < minor.0.hypot(minor.1) {
+            (major, minor) = (minor, major);
+        }
+        let (major_length, minor_length) = (major.0.hypot(major.1), minor.0.hypot(minor.1));
+        if minor_length == 0.0 {
+            return self.trilinear(u, v, footprint);
+        }
+        // too long ellipses cover too many texels, blur them across instead
+        if minor_length * self.max_anisotropy < major_length {
+            let scale = major_length / (minor_length * self.max_anisotropy);
+            minor = (scale * minor.0, scale * minor.1);
+        }
+        let lod = minor.0.hypot(minor.1).log2();
+        self.between_levels(lod, |level| self.ewa_level(level, u, v, [major, minor]))
+    }
+
+    /// Gaussian weighted average over the ellipse with the semi-axes `axes`, in texels of
+    /// the full size image, widened by a texel for the reconstruction
+    fn ewa_level(&self, level: usize, u: f64, v: f64, axes: [(f64, f64); 2]) -> Texel {
+        let (full, l) = (&self.levels[0], &self.levels[level]);
+        let (sx, sy) = (
+            l.width as f64 / full.width as f64,
+            l.height as f64 / full.height as f64,
+        );
+        let [(du0, dv0), (du1, dv1)] = axes.map(|(du, dv)| (sx * du, sy * dv));
+        // implicit ellipse a s² + b s t + c t² < 1 around the lookup
+        let mut a = dv0 * dv0 + dv1 * dv1 + 1.0;
+        let mut b = -2.0 * (du0 * dv0 + du1 * dv1);
+        let mut c = du0 * du0 + du1 * du1 + 1.0;
+        let inv_f = 1.0 / (a * c - 0.25 * b * b);
+        (a, b, c) = (a * inv_f, b * inv_f, c * inv_f);
+        let det = 4.0 * a * c - b * b;
+        let (s_half, t_half) = (2.0 * (c / det).sqrt(), 2.0 * (a / det).sqrt());
+        let (s, t) = self.texel_coords(level, u, v);
+        let (mut sum, mut total) = (Texel::default(), 0.0);
+        for y in (t - t_half).ceil() as i64..=(t + t_half).floor() as i64 {
+            let dt = y as f64 - t;
+            for x in (s - s_half).ceil() as i64..=(s + s_half).floor() as i64 {
+                let ds = x as f64 - s;
+                let r2 = a * ds * ds + b * ds * dt + c * dt * dt;
+                if r2 < 1.0 {
+                    let weight = (-2.0 * r2).exp() - (-2.0_f64).exp();
+                    sum += weight * self.texel(level, x, y);
+                    total += weight;
+                }
+            }
+        }
+        if total > 0.0 {
+            (1.0 / total) * sum
+        } else {
+            self.bilinear(level, u, v)
+        }
+    }
+
+    /// the image at (u, v) filtered over the uv ellipse with the semi-axes `footprint`,
+    /// and its alpha. Without a footprint the mipmapped filters fall back to bilinear.
+    pub fn lookup(&self, u: f64, v: f64, footprint: [(f64, f64); 2]) -> (Vec3, f64) {
+        if self.levels.is_empty() {
+            return (Vec3::new(0.0, 1.0, 0.0), 1.0);
+        }
+        let texel = match self.filter {
+            TextureFilter::Nearest => {
+                let l = &self.levels[0];
+                let x = (u * l.width as f64).floor() as i64;
+                let y = ((1.0 - v) * l.height as f64).floor() as i64;
+                self.texel(0, x, y)
+            }
+            TextureFilter::Bilinear => self.bilinear(0, u, v),
+            TextureFilter::Bicubic => self.bicubic(u, v),
+            TextureFilter::Trilinear => self.trilinear(u, v, footprint),
+            TextureFilter::Ewa => self.ewa(u, v, footprint),
+        };
+        (texel.color, texel.alpha)
     }
 }
 
 impl Texture for ImageTexture {
     fn value(&self, u: f64, v: f64, _p: Vec3) -> Vec3 {
-        if self.image.height() <= 0 {
-            return Vec3::new(0.0, 1.0, 0.0);
-        }
-        let u_ = (Interval::new(0.0, 1.0).clamp(u) * (self.image.width() - 1) as f64) as u32;
-        let v_ =
-            ((1.0 - Interval::new(0.0, 1.0).clamp(v)) * (self.image.height() - 1) as f64) as u32;
-        let pixel = self.image.get_pixel(u_, v_);
-        let color_scale = 1.0 / 255.0;
-        Vec3::new(
-            color_scale * pixel[0] as f64,
-            color_scale * pixel[1] as f64,
-            color_scale * pixel[2] as f64,
-        )
+        self.lookup(u, v, [(0.0, 0.0); 2]).0
+    }
+
+    fn value_at(&self, rec: &HitRecord) -> Vec3 {
+        self.lookup(rec.u, rec.v, rec.footprint).0
+    }
+
+    fn alpha_at(&self, rec: &HitRecord) -> f64 {
+        self.lookup(rec.u, rec.v, rec.footprint).1
     }
 }
 
@@ -114,6 +560,14 @@ impl NoiseTexture {
             scale,
         }
     }
+
+    /// the same marble for the same seed, on every run
+    pub fn new_seeded(scale: f64, seed: u64) -> Self {
+        Self {
+            noise: Perlin::new_seeded(seed),
+            scale,
+        }
+    }
 }
 
 impl Texture for NoiseTexture {
@@ -122,3 +576,90 @@ impl Texture for NoiseTexture {
             * (1.0 + f64::sin(self.noise.turb(_p, 7) * 10.0 + self.scale * _p.z))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ray::Ray;
+    use image::{ImageBuffer, LumaA, Rgb};
+
+    #[test]
+    fn test_wrap_filters_and_footprint() {
+        assert_eq!(WrapMode::Repeat.apply(-1, 4), 3);
+        assert_eq!(WrapMode::Clamp.apply(9, 4), 3);
+        assert_eq!(WrapMode::Mirror.apply(-1, 4), 0);
+        assert_eq!(WrapMode::Mirror.apply(5, 4), 2);
+
+        // 4 by 4 checkerboard of single black and white texels
+        let image = ImageBuffer::from_fn(4, 4, |x, y| {
+            Rgb([if (x + y) % 2 == 0 { 255u8 } else { 0 }; 3])
+        });
+        let mut texture =
+            ImageTexture::from_image(&DynamicImage::ImageRgb8(image), ColorSpace::Linear);
+        assert_eq!(texture.levels.len(), 3);
+        let gray = 0.5 * Vec3::ones();
+        assert_eq!(texture.levels[1].texels[0], [0.5, 0.5, 0.5, 1.0]);
+        // v runs up the image, so (0.1, 0.9) is in the top left texel
+        texture.filter = TextureFilter::Nearest;
+        assert_eq!(texture.value(0.1, 0.9, Vec3::zero()), Vec3::ones());
+        texture.filter = TextureFilter::Bilinear;
+        assert!((texture.value(0.25, 0.75, Vec3::zero()) - gray).length() < 1e-12);
+        // the top edge clamps by default, repeating would blend in the bottom row
+        assert!((texture.value(0.125, 1.0, Vec3::zero()) - Vec3::ones()).length() < 1e-12);
+        texture.wrap = [WrapMode::Repeat; 2];
+        assert!((texture.value(0.125, 1.0, Vec3::zero()) - gray).length() < 1e-12);
+        texture.filter = TextureFilter::Bicubic;
+        let texel = texture.value(0.125, 0.875, Vec3::zero());
+        assert!((texel - Vec3::ones()).length() < 1e-12);
+        // a footprint of many texels averages the board out to gray
+        let wide = [(0.6, 0.0), (0.0, 0.6)];
+        for filter in [TextureFilter::Trilinear, TextureFilter::Ewa] {
+            texture.filter = filter;
+            assert!((texture.lookup(0.3, 0.6, wide).0 - gray).length() < 1e-9);
+        }
+
+        // a cone 1 wide where it meets a surface with uv 2 units across, head on
+        let mut rec = HitRecord::new(Vec3::zero(), Vec3::new(0.0, 0.0, 1.0), 10.0, true, None);
+        (rec.dpdu, rec.dpdv) = (Vec3::new(2.0, 0.0, 0.0), Vec3::new(0.0, 2.0, 0.0));
+        let mut ray_ = Ray::new(Vec3::new(0.0, 0.0, 10.0), Vec3::new(0.0, 0.0, -1.0), 0.0);
+        ray_.cone_spread = 0.1;
+        rec.set_footprint(&ray_);
+        for (du, dv) in rec.footprint {
+            assert!((du.hypot(dv) - 0.25).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_color_spaces_float_maps_and_alpha() {
+        // mid gray in sRGB is about a fifth of white in linear terms, alpha stays linear
+        let image = ImageBuffer::from_pixel(1, 1, LumaA([128u8, 128]));
+        let image = DynamicImage::ImageLumaA8(image);
+        let srgb = ImageTexture::from_image(&image, ColorSpace::Srgb);
+        let mut rec = HitRecord::new(Vec3::zero(), Vec3::zero(), 0.0, true, None);
+        (rec.u, rec.v) = (0.5, 0.5);
+        assert!((srgb.value_at(&rec).x - 0.2158605).abs() < 1e-6);
+        assert!((srgb.alpha_at(&rec) - 128.0 / 255.0).abs() < 1e-6);
+        let linear = ImageTexture::from_image(&image, ColorSpace::Linear);
+        assert!((linear.value_at(&rec).x - 128.0 / 255.0).abs() < 1e-6);
+
+        // a 2 by 1 little endian color PFM keeps values above one
+        let path = std::env::temp_dir().join(format!("ray_tracer_test_{}.pfm", std::process::id()));
+        let mut data = b"PF\n2 1\n-1.0\n".to_vec();
+        for value in [4.0f32, 0.5, 0.25, 0.0, 1.0, 16.0] {
+            data.extend(value.to_le_bytes());
+        }
+        fs::write(&path, data).unwrap();
+        let loaded = ImageTexture::new(path.to_str().unwrap());
+        fs::remove_file(&path).unwrap();
+        let mut pfm = loaded.unwrap();
+        pfm.filter = TextureFilter::Nearest;
+        assert_eq!(
+            pfm.value(0.25, 0.5, Vec3::zero()),
+            Vec3::new(4.0, 0.5, 0.25)
+        );
+        assert_eq!(
+            pfm.value(0.75, 0.5, Vec3::zero()),
+            Vec3::new(0.0, 1.0, 16.0)
+        );
+    }
+}
@@ -0,0 +1,254 @@
+use crate::aabb::AABB;
+use crate::hit::{HitRecord, Hittable};
+use crate::interval::Interval;
+use crate::material::Material;
+use crate::onb::Onb;
+use crate::ray::Ray;
+use crate::rt::random_f64;
+use crate::vec3::Vec3;
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+/// largest real root of x^3 + a x^2 + b x + c
+fn largest_cubic_root(a: f64, b: f64, c: f64) -> f64 {
+    let q = (a * a - 3.0 * b) / 9.0;
+    let r = (2.0 * a * a * a - 9.0 * a * b + 27.0 * c) / 54.0;
+    let mut x = if r * r < q * q * q {
+        // three real roots
+        let theta = (r / (q * q * q).sqrt()).clamp(-1.0, 1.0).acos();
+        let sqrt_q = q.sqrt();
+        [0.0, 2.0 * PI, -2.0 * PI]
+            .iter()
+            .map(|shift| -2.0 * sqrt_q * ((theta + shift) / 3.0).cos() - a / 3.0)
+            .fold(f64::NEG_INFINITY, f64::max)
+    } else {
+        let big_a = -r.signum() * (r.abs() + (r * r - q * q * q).sqrt()).cbrt();
+        let big_b = if big_a != 0.0 { q / big_a } else { 0.0 };
+        big_a + big_b - a / 3.0
+    };
+    // the closed forms lose digits when roots are close, newton polishes them
+    for _ in 0..2 {
+        let f = ((x + a) * x + b) * x + c;
+        let df = (3.0 * x + 2.0 * a) * x + b;
+        if df != 0.0 {
+            x -= f / df;
+        }
+    }
+    x
+}
+
+/// real roots of x^2 + b x + c
+fn monic_quadratic_roots(b: f64, c: f64, roots: &mut Vec<f64>) {
+    let disc = b * b - 4.0 * c;
+    if disc < 0.0 {
+        return;
+    }
+    let sqrtd = disc.sqrt();
+    // avoids cancellation by computing the larger root first
+    let q = -0.5 * (b + b.signum() * sqrtd);
+    if q == 0.0 {
+        roots.push(0.0);
+        return;
+    }
+    roots.push(q);
+    roots.push(c / q);
+}
+
+/// real roots of c4 x^4 + c3 x^3 + c2 x^2 + c1 x + c0 in increasing order, solved by
+/// Ferrari's method and polished with newton steps on the original polynomial
+fn solve_quartic(c4: f64, c3: f64, c2: f64, c1: f64, c0: f64) -> Vec<f64> {
+    let (a, b, c, d) = (c3 / c4, c2 / c4, c1 / c4, c0 / c4);
+    // depressed quartic y^4 + p y^2 + q y + r with x = y - a / 4
+    let a2 = a * a;
+    let p = b - 3.0 * a2 / 8.0;
+    let q = c - a * b / 2.0 + a2 * a / 8.0;
+    let r = d - a * c / 4.0 + a2 * b / 16.0 - 3.0 * a2 * a2 / 256.0;
+    let mut ys = Vec::with_capacity(4);
+    if q.abs() < 1e-12 {
+        // biquadratic
+        let mut zs = Vec::with_capacity(2);
+        monic_quadratic_roots(p, r, &mut zs);
+        for z in zs.into_iter().filter(|&z| z >= 0.0) {
+            ys.push(z.sqrt());
+            ys.push(-z.sqrt());
+        }
+    } else {
+        // a positive root m of the resolvent cubic splits the quartic into two quadratics
+        let m = largest_cubic_root(p, p * p / 4.0 - r, -q * q / 8.0);
+        if m > 0.0 {
+            let s = (2.0 * m).sqrt();
+            monic_quadratic_roots(-s, p / 2.0 + m + q / (2.0 * s), &mut ys);
+            monic_quadratic_roots(s, p / 2.0 + m - q / (2.0 * s), &mut ys);
+        }
+    }
+    let mut xs: Vec<f64> = ys
+        .into_iter()
+        .map(|y| {
+            let mut x = y - a / 4.0;
+            for _ in 0..2 {
+                let f = (((x + a) * x + b) * x + c) * x + d;
+                let df = ((4.0 * x + 3.0 * a) * x + 2.0 * b) * x + c;
+                if df != 0.0 {
+                    x -= f / df;
+                }
+            }
+            x
+        })
+        .collect();
+    xs.sort_by(f64::total_cmp);
+    xs
+}
+
+/// torus around `axis` through `center`, the tube of radius `minor` follows a circle
+/// of radius `major`. u goes around the axis and v around the tube.
+pub struct Torus {
+    center: Vec3,
+    frame: Onb,
+    major: f64,
+    minor: f64,
+    mat: Arc<dyn Material>,
+    bbox: AABB,
+}
+
+impl Torus {
+    pub fn new(center: Vec3, axis: Vec3, major: f64, minor: f64, mat: Arc<dyn Material>) -> Self {
+        let frame = Onb::new(axis);
+        // a disk of radius major + minor, thickened by the tube along the axis
+        let mut extent = Vec3::zero();
+        for i in 0..3 {
+            let n = frame.w[i].abs();
+            extent[i] = (major + minor) * (1.0 - n * n).max(0.0).sqrt() + minor * n;
+        }
+        Self {
+            center,
+            frame,
+            major,
+            minor,
+            mat,
+            bbox: AABB::new_by_point(center - extent, center + extent),
+        }
+    }
+
+    /// record at the local point p
+    fn record(&self, p: Vec3, t: f64) -> HitRecord {
+        let phi = p.y.atan2(p.x);
+        let ring = Vec3::new(phi.cos(), phi.sin(), 0.0);
+        let radial = (p.x * p.x + p.y * p.y).sqrt() - self.major;
+        let theta = p.z.atan2(radial);
+        let out_normal = self
+            .frame
+            .local(ring * theta.cos() + Vec3::new(0.0, 0.0, theta.sin()));
+        let mut rec = HitRecord::new(
+            self.center + self.frame.local(p),
+            out_normal,
+            t,
+            true,
+            Some(self.mat.clone()),
+        );
+        rec.u = phi.rem_euclid(2.0 * PI) / (2.0 * PI);
+        rec.v = theta.rem_euclid(2.0 * PI) / (2.0 * PI);
+        rec.dpdu = 2.0 * PI * self.frame.local(Vec3::new(-p.y, p.x, 0.0));
+        rec.dpdv = 2.0
+            * PI
+            * self.minor
+            * self
+                .frame
+                .local(-theta.sin() * ring + Vec3::new(0.0, 0.0, theta.cos()));
+        rec
+    }
+}
+
+impl Hittable for Torus {
+    fn hit(&self, ray_: &Ray, ray_t: Interval, rec: &mut HitRecord) -> bool {
+        let o = self.frame.to_local(ray_.orig - self.center);
+        let d = self.frame.to_local(ray_.dir);
+        // the quartic is solved from where the ray enters the bounding sphere,
+        // which keeps its coefficients small for far away rays
+        let bound = self.major + self.minor;
+        let g = d * d;
+        let h = o * d;
+        let disc = h * h - g * (o * o - bound * bound);
+        if disc < 0.0 {
+            return false;
+        }
+        let shift = (-h - disc.sqrt()) / g;
+        let o = o + shift * d;
+        // (|p|^2 + R^2 - r^2)^2 = 4 R^2 (x^2 + y^2) along p = o + t d
+        let (r2, big_r2) = (self.minor * self.minor, self.major * self.major);
+        let h = 2.0 * (o * d);
+        let k = o * o + big_r2 - r2;
+        let roots = solve_quartic(
+            g * g,
+            2.0 * g * h,
+            h * h + 2.0 * g * k - 4.0 * big_r2 * (d.x * d.x + d.y * d.y),
+            2.0 * h * k - 8.0 * big_r2 * (o.x * d.x + o.y * d.y),
+            k * k - 4.0 * big_r2 * (o.x * o.x + o.y * o.y),
+        );
+        let t = match roots.into_iter().find(|t| ray_t.surrounds(t + shift)) {
+            Some(t) => t,
+            None => return false,
+        };
+        let hit = self.record(o + t * d, t + shift);
+        let out_normal = hit.normal;
+        *rec = hit;
+        rec.set_face_normal(ray_, out_normal);
+        true
+    }
+
+    fn bounding_box(&self) -> AABB {
+        self.bbox
+    }
+
+    fn area(&self) -> f64 {
+        4.0 * PI * PI * self.major * self.minor
+    }
+
+    fn sample_surface(&self, _time: f64) -> Option<HitRecord> {
+        // the outside of the tube is larger than its inside by (R + r cos(theta)) / R
+        let theta = loop {
+            let theta = random_f64(0.0, 2.0 * PI);
+            let weight = (self.major + self.minor * theta.cos()) / (self.major + self.minor);
+            if random_f64(0.0, 1.0) < weight {
+                break theta;
+            }
+        };
+        let phi = random_f64(0.0, 2.0 * PI);
+        let radial = self.major + self.minor * theta.cos();
+        let p = Vec3::new(
+            radial * phi.cos(),
+            radial * phi.sin(),
+            self.minor * theta.sin(),
+        );
+        Some(self.record(p, 0.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::Lambertian;
+
+    #[test]
+    fn test_quartic_roots() {
+        // (x - 1) (x - 2) (x + 3) (x - 0.5)
+        let roots = solve_quartic(1.0, -0.5, -7.0, 9.5, -3.0);
+        let expected = [-3.0, 0.5, 1.0, 2.0];
+        assert_eq!(roots.len(), 4);
+        for (root, e) in roots.iter().zip(expected) {
+            assert!((root - e).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_torus_hit_through_hole() {
+        let mat = Arc::new(Lambertian::new(Vec3::ones()));
+        let torus = Torus::new(Vec3::zero(), Vec3::new(0.0, 1.0, 0.0), 2.0, 0.5, mat);
+        let mut rec = HitRecord::new(Vec3::zero(), Vec3::zero(), 0.0, false, None);
+        let along_axis = Ray::new(Vec3::new(0.0, 10.0, 0.0), Vec3::new(0.0, -1.0, 0.0), 0.0);
+        assert!(!torus.hit(&along_axis, Interval::new(0.001, f64::INFINITY), &mut rec));
+        let across = Ray::new(Vec3::new(-100.0, 0.0, 0.0), Vec3::new(1.0, 0.0, 0.0), 0.0);
+        assert!(torus.hit(&across, Interval::new(0.001, f64::INFINITY), &mut rec));
+        assert!((rec.t - 97.5).abs() < 1e-6);
+        assert!((rec.normal - Vec3::new(-1.0, 0.0, 0.0)).length() < 1e-6);
+    }
+}
@@ -0,0 +1,362 @@
+use crate::aabb::AABB;
+use crate::hit::{HitRecord, Hittable};
+use crate::interval::Interval;
+use crate::ray::Ray;
+use crate::vec3::Vec3;
+use std::sync::Arc;
+
+/// unit quaternion for rotations
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Quat {
+    pub w: f64,
+    pub v: Vec3,
+}
+
+impl Quat {
+    pub fn identity() -> Self {
+        Self {
+            w: 1.0,
+            v: Vec3::zero(),
+        }
+    }
+
+    /// rotation by `angle` degrees around `axis`, counterclockwise looking down the axis
+    pub fn from_axis_angle(axis: Vec3, angle: f64) -> Self {
+        let half = 0.5 * angle.to_radians();
+        Self {
+            w: half.cos(),
+            v: half.sin() * axis.normalize(),
+        }
+    }
+
+    /// rotation taking the orthonormal frame `from` onto `to`, axis by axis
+    pub fn between_frames(from: [Vec3; 3], to: [Vec3; 3]) -> Self {
+        // m = sum of to_k from_k^T
+        let m = |i: usize, j: usize| (0..3).map(|k| to[k][i] * from[k][j]).sum::<f64>();
+        let trace = m(0, 0) + m(1, 1) + m(2, 2);
+        let (w, x, y, z) = if trace > 0.0 {
+            let s = 2.0 * (trace + 1.0).sqrt();
+            (
+                0.25 * s,
+                (m(2, 1) - m(1, 2)) / s,
+                (m(0, 2) - m(2, 0)) / s,
+                (m(1, 0) - m(0, 1)) / s,
+            )
+        } else if m(0, 0) > m(1, 1) && m(0, 0) > m(2, 2) {
+            let s = 2.0 * (1.0 + m(0, 0) - m(1, 1) - m(2, 2)).sqrt();
+            (
+                (m(2, 1) - m(1, 2)) / s,
+                0.25 * s,
+                (m(0, 1) + m(1, 0)) / s,
+                (m(0, 2) + m(2, 0)) / s,
+            )
+        } else if m(1, 1) > m(2, 2) {
+            let s = 2.0 * (1.0 + m(1, 1) - m(0, 0) - m(2, 2)).sqrt();
+            (
+                (m(0, 2) - m(2, 0)) / s,
+                (m(0, 1) + m(1, 0)) / s,
+                0.25 * s,
+                (m(1, 2) + m(2, 1)) / s,
+            )
+        } else {
+            let s = 2.0 * (1.0 + m(2, 2) - m(0, 0) - m(1, 1)).sqrt();
+            (
+                (m(1, 0) - m(0, 1)) / s,
+                (m(0, 2) + m(2, 0)) / s,
+                (m(1, 2) + m(2, 1)) / s,
+                0.25 * s,
+            )
+        };
+        Self {
+            w,
+            v: Vec3::new(x, y, z),
+        }
+        .normalize()
+    }
+
+    pub fn conjugate(&self) -> Self {
+        Self {
+            w: self.w,
+            v: -self.v,
+        }
+    }
+
+    pub fn rotate(&self, a: Vec3) -> Vec3 {
+        let t = 2.0 * self.v.cross(a);
+        a + self.w * t + self.v.cross(t)
+    }
+
+    fn dot(&self, other: &Quat) -> f64 {
+        self.w * other.w + self.v * other.v
+    }
+
+    fn normalize(&self) -> Self {
+        let len = self.dot(self).sqrt();
+        Self {
+            w: self.w / len,
+            v: self.v / len,
+        }
+    }
+
+    /// spherical interpolation along the shorter arc
+    pub fn slerp(a: &Quat, b: &Quat, t: f64) -> Quat {
+        let mut cos = a.dot(b);
+        let mut b = *b;
+        if cos < 0.0 {
+            cos = -cos;
+            b = Quat { w: -b.w, v: -b.v };
+        }
+        let (wa, wb) = if cos > 0.9995 {
+            // nearly parallel, a normalized lerp is accurate enough
+            (1.0 - t, t)
+        } else {
+            let theta = cos.acos();
+            let sin = theta.sin();
+            (((1.0 - t) * theta).sin() / sin, (t * theta).sin() / sin)
+        };
+        Quat {
+            w: wa * a.w + wb * b.w,
+            v: wa * a.v + wb * b.v,
+        }
+        .normalize()
+    }
+}
+
+/// scale, then rotate, then translate
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Transform {
+    pub translation: Vec3,
+    pub rotation: Quat,
+    pub scale: Vec3,
+}
+
+impl Transform {
+    pub fn new(translation: Vec3, rotation: Quat, scale: Vec3) -> Self {
+        Self {
+            translation,
+            rotation,
+            scale,
+        }
+    }
+
+    pub fn identity() -> Self {
+        Self::new(Vec3::zero(), Quat::identity(), Vec3::ones())
+    }
+
+    pub fn apply_point(&self, p: Vec3) -> Vec3 {
+        self.translation + self.apply_vector(p)
+    }
+
+    pub fn apply_vector(&self, a: Vec3) -> Vec3 {
+        self.rotation.rotate(a.cor_dot(self.scale))
+    }
+
+    /// normals go through the inverse transpose
+    pub fn apply_normal(&self, n: Vec3) -> Vec3 {
+        let s = self.scale;
+        self.rotation
+            .rotate(Vec3::new(n.x / s.x, n.y / s.y, n.z / s.z))
+            .normalize()
+    }
+
+    pub fn inverse_point(&self, p: Vec3) -> Vec3 {
+        self.inverse_vector(p - self.translation)
+    }
+
+    pub fn inverse_vector(&self, a: Vec3) -> Vec3 {
+        let r = self.rotation.conjugate().rotate(a);
+        Vec3::new(r.x / self.scale.x, r.y / self.scale.y, r.z / self.scale.z)
+    }
+
+    pub fn apply_aabb(&self, bbox: &AABB) -> AABB {
+        let mut min = Vec3::new(f64::INFINITY, f64::INFINITY, f64::INFINITY);
+        let mut max = -min;
+        for i in 0..8 {
+            let corner = Vec3::new(
+                if i & 1 == 0 { bbox.x.min } else { bbox.x.max },
+                if i & 2 == 0 { bbox.y.min } else { bbox.y.max },
+                if i & 4 == 0 { bbox.z.min } else { bbox.z.max },
+            );
+            let p = self.apply_point(corner);
+            min = Vec3::new(min.x.min(p.x), min.y.min(p.y), min.z.min(p.z));
+            max = Vec3::new(max.x.max(p.x), max.y.max(p.y), max.z.max(p.z));
+        }
+        AABB::new_by_point(min, max)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Keyframe {
+    pub time: f64,
+    pub transform: Transform,
+}
+
+impl Keyframe {
+    pub fn new(time: f64, translation: Vec3, rotation: Quat, scale: Vec3) -> Self {
+        Self {
+            time,
+            transform: Transform::new(translation, rotation, scale),
+        }
+    }
+}
+
+/// transform interpolated between keyframes: translation and scale linearly,
+/// rotation with slerp. Times outside the keys hold the first or last key.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AnimatedTransform {
+    keys: Vec<Keyframe>,
+}
+
+impl AnimatedTransform {
+    pub fn new(mut keys: Vec<Keyframe>) -> Self {
+        if keys.is_empty() {
+            keys.push(Keyframe {
+                time: 0.0,
+                transform: Transform::identity(),
+            });
+        }
+        keys.sort_by(|a, b| a.time.total_cmp(&b.time));
+        Self { keys }
+    }
+
+    pub fn at(&self, time: f64) -> Transform {
+        let next = self.keys.partition_point(|k| k.time <= time);
+        if next == 0 {
+            return self.keys[0].transform;
+        }
+        if next == self.keys.len() {
+            return self.keys[next - 1].transform;
+        }
+        let (a, b) = (&self.keys[next - 1], &self.keys[next]);
+        let t = (time - a.time) / (b.time - a.time);
+        let (ta, tb) = (&a.transform, &b.transform);
+        Transform {
+            translation: (1.0 - t) * ta.translation + t * tb.translation,
+            rotation: Quat::slerp(&ta.rotation, &tb.rotation, t),
+            scale: (1.0 - t) * ta.scale + t * tb.scale,
+        }
+    }
+
+    /// box around `bbox` over the whole animation
+    pub fn motion_bounds(&self, bbox: &AABB) -> AABB {
+        // rotations sweep arcs between the samples, steps are small enough for a tiny pad
+        let steps = 64;
+        let mut bounds = self.keys[0].transform.apply_aabb(bbox);
+        for pair in self.keys.windows(2) {
+            for i in 1..=steps {
+                let time = pair[0].time + (pair[1].time - pair[0].time) * i as f64 / steps as f64;
+                bounds = AABB::new_by_aabb(&bounds, &self.at(time).apply_aabb(bbox));
+            }
+        }
+        let pad = 1e-3
+            * (bounds.x.size() * bounds.x.size()
+                + bounds.y.size() * bounds.y.size()
+                + bounds.z.size() * bounds.z.size())
+            .sqrt();
+        AABB::new(
+            bounds.x.expand(pad),
+            bounds.y.expand(pad),
+            bounds.z.expand(pad),
+        )
+    }
+}
+
+/// any hittable moved by keyframes, sampled at the time of each ray
+pub struct Animated {
+    object: Arc<dyn Hittable>,
+    motion: AnimatedTransform,
+    bbox: AABB,
+}
+
+impl Animated {
+    pub fn new(object: Arc<dyn Hittable>, motion: AnimatedTransform) -> Self {
+        let bbox = motion.motion_bounds(&object.bounding_box());
+        Self {
+            object,
+            motion,
+            bbox,
+        }
+    }
+}
+
+impl Hittable for Animated {
+    fn hit(&self, ray_: &Ray, int: Interval, rec: &mut HitRecord) -> bool {
+        let xf = self.motion.at(ray_.time);
+        // the ray parameter t is the same in both spaces since the direction is not normalized
+        let local = Ray {
+            orig: xf.inverse_point(ray_.orig),
+            dir: xf.inverse_vector(ray_.dir),
+            ..*ray_
+        };
+        if !self.object.hit(&local, int, rec) {
+            return false;
+        }
+        rec.point = ray_.at(rec.t);
+        rec.normal = xf.apply_normal(rec.normal);
+        rec.dpdu = xf.apply_vector(rec.dpdu);
+        rec.dpdv = xf.apply_vector(rec.dpdv);
+        true
+    }
+
+    fn bounding_box(&self) -> AABB {
+        self.bbox
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_transform_round_trip() {
+        let xf = Transform::new(
+            Vec3::new(1.0, -2.0, 3.0),
+            Quat::from_axis_angle(Vec3::new(1.0, 1.0, 0.0), 70.0),
+            Vec3::new(2.0, 0.5, 1.5),
+        );
+        let p = Vec3::new(0.3, 0.7, -1.1);
+        assert!((xf.inverse_point(xf.apply_point(p)) - p).length() < 1e-12);
+        let quarter = Quat::from_axis_angle(Vec3::new(0.0, 1.0, 0.0), 90.0);
+        let x = quarter.rotate(Vec3::new(1.0, 0.0, 0.0));
+        assert!((x - Vec3::new(0.0, 0.0, -1.0)).length() < 1e-12);
+    }
+
+    #[test]
+    fn test_rotation_between_frames() {
+        let axes = [
+            Vec3::new(1.0, 0.0, 0.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            Vec3::new(0.0, 0.0, 1.0),
+        ];
+        for (axis, angle) in [
+            (Vec3::new(0.0, 1.0, 0.0), 30.0),
+            (Vec3::new(1.0, 2.0, -1.0), 170.0),
+            (Vec3::new(0.0, 0.0, 1.0), 180.0),
+        ] {
+            let q = Quat::from_axis_angle(axis, angle);
+            let from =
+                axes.map(|a| Quat::from_axis_angle(Vec3::new(1.0, 1.0, 1.0), 25.0).rotate(a));
+            let found = Quat::between_frames(from, from.map(|a| q.rotate(a)));
+            let p = Vec3::new(0.3, -0.4, 0.8);
+            assert!((found.rotate(p) - q.rotate(p)).length() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_slerp_halfway() {
+        let motion = AnimatedTransform::new(vec![
+            Keyframe::new(0.0, Vec3::zero(), Quat::identity(), Vec3::ones()),
+            Keyframe::new(
+                1.0,
+                Vec3::new(2.0, 0.0, 0.0),
+                Quat::from_axis_angle(Vec3::new(0.0, 0.0, 1.0), 120.0),
+                Vec3::ones(),
+            ),
+        ]);
+        let half = motion.at(0.5);
+        assert!((half.translation - Vec3::new(1.0, 0.0, 0.0)).length() < 1e-12);
+        let x = half.rotation.rotate(Vec3::new(1.0, 0.0, 0.0));
+        let expected = Vec3::new(60f64.to_radians().cos(), 60f64.to_radians().sin(), 0.0);
+        assert!((x - expected).length() < 1e-9);
+    }
+}
@@ -1,4 +1,4 @@
-use rand::Rng;
+use crate::rt::random_f64;
 use std::iter::Sum;
 use std::ops::{Add, AddAssign, Div, Index, IndexMut, Mul, Neg, Sub};
 #[derive(Clone, Debug, PartialEq, Copy)]
@@ -34,17 +34,15 @@ impl Vec3 {
     }
 
     pub fn sample_square() -> Vec3 {
-        let mut rng = rand::thread_rng();
-        Vec3::new(rng.gen_range(-0.5..0.5), rng.gen_range(-0.5..0.5), 0.0)
+        Vec3::new(random_f64(-0.5, 0.5), random_f64(-0.5, 0.5), 0.0)
     }
 
     pub fn random_in_unit_sphere() -> Vec3 {
-        let mut rng = rand::thread_rng();
         loop {
             let p = Vec3::new(
-                rng.gen_range(-1.0..1.0),
-                rng.gen_range(-1.0..1.0),
-                rng.gen_range(-1.0..1.0),
+                random_f64(-1.0, 1.0),
+                random_f64(-1.0, 1.0),
+                random_f64(-1.0, 1.0),
             );
             if p.squared_length() < 1.0 {
                 return p;
@@ -65,10 +63,18 @@ impl Vec3 {
         }
     }
 
+    /// cosine weighted direction around +z
+    pub fn random_cosine_direction() -> Vec3 {
+        let r1: f64 = random_f64(0.0, 1.0);
+        let r2: f64 = random_f64(0.0, 1.0);
+        let phi = 2.0 * std::f64::consts::PI * r1;
+        let z = (1.0 - r2).sqrt();
+        Vec3::new(phi.cos() * r2.sqrt(), phi.sin() * r2.sqrt(), z)
+    }
+
     pub fn random_in_unit_disk() -> Vec3 {
-        let mut rng = rand::thread_rng();
         loop {
-            let p = Vec3::new(rng.gen_range(-1.0..1.0), rng.gen_range(-1.0..1.0), 0.0);
+            let p = Vec3::new(random_f64(-1.0, 1.0), random_f64(-1.0, 1.0), 0.0);
             if p.squared_length() < 1.0 {
                 return p;
             }
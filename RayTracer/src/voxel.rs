@@ -0,0 +1,401 @@
+use crate::aabb::AABB;
+use crate::hit::{HitRecord, Hittable};
+use crate::interval::Interval;
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::vec3::Vec3;
+use std::fs;
+use std::io;
+use std::sync::Arc;
+
+/// voxels along each side of a brick
+const BRICK: usize = 8;
+
+/// Amanatides–Woo walk over the cells of a box of the grid, `lo` to `hi` exclusive
+struct Dda {
+    cell: [isize; 3],
+    step: [isize; 3],
+    next: [f64; 3],
+    delta: [f64; 3],
+    lo: [isize; 3],
+    hi: [isize; 3],
+    /// where the ray enters the current cell
+    t: f64,
+}
+
+impl Dda {
+    /// starts in the cell around `ray.at(t)`, in grid space with cells of `size`
+    fn new(orig: Vec3, dir: Vec3, size: f64, lo: [isize; 3], hi: [isize; 3], t: f64) -> Self {
+        let p = orig + t * dir;
+        let mut dda = Dda {
+            cell: [0; 3],
+            step: [0; 3],
+            next: [f64::INFINITY; 3],
+            delta: [f64::INFINITY; 3],
+            lo,
+            hi,
+            t,
+        };
+        for i in 0..3 {
+            dda.cell[i] = ((p[i] / size).floor() as isize).clamp(lo[i], hi[i] - 1);
+            if dir[i] > 0.0 {
+                dda.step[i] = 1;
+                dda.next[i] = ((dda.cell[i] + 1) as f64 * size - orig[i]) / dir[i];
+                dda.delta[i] = size / dir[i];
+            } else if dir[i] < 0.0 {
+                dda.step[i] = -1;
+                dda.next[i] = (dda.cell[i] as f64 * size - orig[i]) / dir[i];
+                dda.delta[i] = -size / dir[i];
+            }
+        }
+        dda
+    }
+
+    /// where the ray leaves the current cell
+    fn exit(&self) -> f64 {
+        self.next[0].min(self.next[1]).min(self.next[2])
+    }
+
+    /// steps into the next cell, false once the walk leaves its box
+    fn advance(&mut self) -> bool {
+        let axis = if self.next[0] < self.next[1] {
+            if self.next[0] < self.next[2] {
+                0
+            } else {
+                2
+            }
+        } else if self.next[1] < self.next[2] {
+            1
+        } else {
+            2
+        };
+        self.cell[axis] += self.step[axis];
+        self.t = self.next[axis];
+        self.next[axis] += self.delta[axis];
+        self.lo[axis] <= self.cell[axis] && self.cell[axis] < self.hi[axis]
+    }
+}
+
+/// grid of voxels from `corner`, each a cube of `voxel_size`. A voxel holds 0 when it
+/// is empty and k for the material `materials[k - 1]` otherwise. The grid is stored as
+/// bricks of 8^3 voxels, empty bricks take no memory and are skipped by the rays.
+pub struct VoxelGrid {
+    dims: [usize; 3],
+    bricks: Vec<Option<Box<[u8; BRICK * BRICK * BRICK]>>>,
+    brick_dims: [usize; 3],
+    corner: Vec3,
+    voxel_size: f64,
+    materials: Vec<Arc<dyn Material>>,
+    bbox: AABB,
+}
+
+impl VoxelGrid {
+    /// empty grid of nx by ny by nz voxels
+    pub fn new(
+        dims: [usize; 3],
+        corner: Vec3,
+        voxel_size: f64,
+        materials: Vec<Arc<dyn Material>>,
+    ) -> Self {
+        let brick_dims = dims.map(|n| n.div_ceil(BRICK));
+        let extent = voxel_size * Vec3::new(dims[0] as f64, dims[1] as f64, dims[2] as f64);
+        Self {
+            dims,
+            bricks: vec![None; brick_dims.iter().product()],
+            brick_dims,
+            corner,
+            voxel_size,
+            materials,
+            bbox: AABB::new_by_point(corner, corner + extent),
+        }
+    }
+
+    /// grid of a raw array of material indices, x fastest, then y, then z
+    pub fn from_raw(
+        dims: [usize; 3],
+        values: &[u8],
+        corner: Vec3,
+        voxel_size: f64,
+        materials: Vec<Arc<dyn Material>>,
+    ) -> Self {
+        let mut grid = VoxelGrid::new(dims, corner, voxel_size, materials);
+        for (k, &value) in values.iter().enumerate().filter(|(_, &v)| v != 0) {
+            let (x, y, z) = (k % dims[0], k / dims[0] % dims[1], k / (dims[0] * dims[1]));
+            grid.set([x, y, z], value);
+        }
+        grid
+    }
+
+    /// the first model of a MagicaVoxel `.vox` file, with z up turned into y up.
+    /// `material` makes the material of each of the 255 palette colors, files without
+    /// a palette come out white.
+    pub fn from_vox(
+        filename: &str,
+        corner: Vec3,
+        voxel_size: f64,
+        material: impl Fn(Vec3) -> Arc<dyn Material>,
+    ) -> io::Result<Self> {
+        let bytes = fs::read(filename)?;
+        let invalid = |msg: &str| io::Error::new(io::ErrorKind::InvalidData, msg.to_string());
+        let int = |at: usize| -> io::Result<usize> {
+            bytes
+                .get(at..at + 4)
+                .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as usize)
+                .ok_or_else(|| invalid("truncated .vox file"))
+        };
+        if bytes.get(0..4) != Some(b"VOX ".as_slice()) {
+            return Err(invalid("not a .vox file"));
+        }
+        let (mut size, mut voxels, mut palette) = (None, None, None);
+        // the chunks nest inside MAIN, but walking them in file order visits them all
+        let mut offset = 20;
+        while offset + 12 <= bytes.len() {
+            let id = &bytes[offset..offset + 4];
+            let content = offset + 12;
+            let content_size = int(offset + 4)?;
+            let end = content + content_size;
+            if end > bytes.len() {
+                return Err(invalid("truncated .vox file"));
+            }
+            match id {
+                b"SIZE" if size.is_none() => {
+                    size = Some([int(content)?, int(content + 4)?, int(content + 8)?]);
+                }
+                b"XYZI" if voxels.is_none() => {
+                    let n = int(content)?;
+                    let data = bytes
+                        .get(content + 4..content + 4 + 4 * n)
+                        .ok_or_else(|| invalid("truncated .vox file"))?;
+                    voxels = Some(data.to_vec());
+                }
+                b"RGBA" => palette = Some(bytes[content..end].to_vec()),
+                _ => {}
+            }
+            offset = end;
+        }
+        let (size, voxels) = match (size, voxels) {
+            (Some(size), Some(voxels)) => (size, voxels),
+            _ => return Err(invalid("no model in .vox file")),
+        };
+        let materials = (0..255)
+            .map(|i| {
+                let color = match &palette {
+                    Some(rgba) if rgba.len() >= 4 * (i + 1) => {
+                        Vec3::new(
+                            rgba[4 * i] as f64,
+                            rgba[4 * i + 1] as f64,
+                            rgba[4 * i + 2] as f64,
+                        ) / 255.0
+                    }
+                    _ => Vec3::ones(),
+                };
+                material(color)
+            })
+            .collect();
+        let dims = [size[0], size[2], size[1]];
+        let mut grid = VoxelGrid::new(dims, corner, voxel_size, materials);
+        for v in voxels.chunks_exact(4) {
+            let (x, y, z) = (v[0] as usize, v[1] as usize, v[2] as usize);
+            if x < size[0] && y < size[1] && z < size[2] {
+                // y of the file runs against our z to keep the model from being mirrored
+                grid.set([x, z, size[1] - 1 - y], v[3]);
+            }
+        }
+        Ok(grid)
+    }
+
+    fn brick_index(&self, brick: [usize; 3]) -> usize {
+        (brick[2] * self.brick_dims[1] + brick[1]) * self.brick_dims[0] + brick[0]
+    }
+
+    fn in_brick(voxel: [usize; 3]) -> usize {
+        ((voxel[2] % BRICK) * BRICK + voxel[1] % BRICK) * BRICK + voxel[0] % BRICK
+    }
+
+    pub fn set(&mut self, voxel: [usize; 3], value: u8) {
+        if (0..3).any(|i| voxel[i] >= self.dims[i]) {
+            return;
+        }
+        let brick = self.brick_index(voxel.map(|v| v / BRICK));
+        if value == 0 && self.bricks[brick].is_none() {
+            return;
+        }
+        let cells = self.bricks[brick].get_or_insert_with(|| Box::new([0; BRICK * BRICK * BRICK]));
+        cells[VoxelGrid::in_brick(voxel)] = value;
+    }
+
+    pub fn get(&self, voxel: [usize; 3]) -> u8 {
+        if (0..3).any(|i| voxel[i] >= self.dims[i]) {
+            return 0;
+        }
+        match &self.bricks[self.brick_index(voxel.map(|v| v / BRICK))] {
+            Some(cells) => cells[VoxelGrid::in_brick(voxel)],
+            None => 0,
+        }
+    }
+
+    /// fills in the record for the surface of the voxel `cell` at t
+    fn record(&self, ray_: &Ray, t: f64, cell: [isize; 3], value: u8, rec: &mut HitRecord) {
+        rec.t = t;
+        rec.point = ray_.at(t);
+        // the face is on the axis where the point is farthest out of the voxel center
+        let local = (rec.point - self.corner) / self.voxel_size;
+        let offset = Vec3::new(
+            local.x - cell[0] as f64 - 0.5,
+            local.y - cell[1] as f64 - 0.5,
+            local.z - cell[2] as f64 - 0.5,
+        );
+        let axis = if offset.x.abs() > offset.y.abs() && offset.x.abs() > offset.z.abs() {
+            0
+        } else if offset.y.abs() > offset.z.abs() {
+            1
+        } else {
+            2
+        };
+        let mut out_normal = Vec3::zero();
+        out_normal[axis] = offset[axis].signum();
+        // each face shows the whole texture, on the two axes along it
+        let (a, b) = [(2, 1), (0, 2), (0, 1)][axis];
+        rec.u = offset[a] + 0.5;
+        rec.v = offset[b] + 0.5;
+        rec.dpdu = Vec3::zero();
+        rec.dpdv = Vec3::zero();
+        rec.dpdu[a] = self.voxel_size;
+        rec.dpdv[b] = self.voxel_size;
+        let index = (value as usize - 1).min(self.materials.len() - 1);
+        rec.mat_ptr = Some(self.materials[index].clone());
+        rec.set_face_normal(ray_, out_normal);
+    }
+}
+
+impl Hittable for VoxelGrid {
+    fn hit(&self, ray_: &Ray, int: Interval, rec: &mut HitRecord) -> bool {
+        let mut ray_t = int;
+        if self.materials.is_empty() || !self.bbox.hit(ray_, &mut ray_t) {
+            return false;
+        }
+        let orig = ray_.orig - self.corner;
+        let dims = self.dims.map(|n| n as isize);
+        let brick_dims = self.brick_dims.map(|n| n as isize);
+        let brick_size = self.voxel_size * BRICK as f64;
+        // surfaces are where the value changes, which also finds the way out of
+        // voxels the ray starts in
+        let mut bricks = Dda::new(orig, ray_.dir, brick_size, [0; 3], brick_dims, ray_t.min);
+        let start = Dda::new(orig, ray_.dir, self.voxel_size, [0; 3], dims, ray_t.min);
+        let starts_inside = (0..3).all(|i| {
+            let p = orig[i] + int.min * ray_.dir[i];
+            0.0 < p && p < self.voxel_size * dims[i] as f64
+        });
+        let mut current = if starts_inside {
+            self.get(start.cell.map(|c| c as usize))
+        } else {
+            0
+        };
+        let mut last_cell = start.cell;
+        loop {
+            let brick = bricks.cell.map(|c| c as usize);
+            let t_exit = bricks.exit().min(ray_t.max);
+            if self.bricks[self.brick_index(brick)].is_none() {
+                if current != 0 {
+                    // leaves solid voxels into an empty brick
+                    if int.surrounds(bricks.t) {
+                        self.record(ray_, bricks.t, last_cell, current, rec);
+                        return true;
+                    }
+                    current = 0;
+                }
+            } else {
+                let lo = bricks.cell.map(|c| c * BRICK as isize);
+                let hi = [0, 1, 2].map(|i| (lo[i] + BRICK as isize).min(dims[i]));
+                let mut voxels = Dda::new(orig, ray_.dir, self.voxel_size, lo, hi, bricks.t);
+                loop {
+                    if voxels.t > ray_t.max {
+                        return false;
+                    }
+                    let value = self.get(voxels.cell.map(|c| c as usize));
+                    if value != current {
+                        let (cell, shown) = if value != 0 {
+                            (voxels.cell, value)
+                        } else {
+                            (last_cell, current)
+                        };
+                        if int.surrounds(voxels.t) {
+                            self.record(ray_, voxels.t, cell, shown, rec);
+                            return true;
+                        }
+                        current = value;
+                    }
+                    last_cell = voxels.cell;
+                    if !voxels.advance() {
+                        break;
+                    }
+                }
+            }
+            if t_exit >= ray_t.max {
+                return false;
+            }
+            if !bricks.advance() {
+                // leaves the grid out of a solid voxel on its border
+                if current != 0 && int.surrounds(bricks.t) {
+                    self.record(ray_, bricks.t, last_cell, current, rec);
+                    return true;
+                }
+                return false;
+            }
+        }
+    }
+
+    fn bounding_box(&self) -> AABB {
+        self.bbox
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::Lambertian;
+
+    fn chunk(id: &[u8], content: &[u8]) -> Vec<u8> {
+        let mut bytes = id.to_vec();
+        bytes.extend((content.len() as u32).to_le_bytes());
+        bytes.extend(0u32.to_le_bytes());
+        bytes.extend(content);
+        bytes
+    }
+
+    #[test]
+    fn test_vox_import_and_hit() {
+        // a 20^3 model with a single voxel, so the ray crosses empty bricks first
+        let mut size = Vec::new();
+        for n in [20u32, 20, 20] {
+            size.extend(n.to_le_bytes());
+        }
+        let mut xyzi = 1u32.to_le_bytes().to_vec();
+        xyzi.extend([17, 2, 10, 3]);
+        let children = [chunk(b"SIZE", &size), chunk(b"XYZI", &xyzi)].concat();
+        let mut file = b"VOX ".to_vec();
+        file.extend(150u32.to_le_bytes());
+        file.extend(b"MAIN");
+        file.extend(0u32.to_le_bytes());
+        file.extend((children.len() as u32).to_le_bytes());
+        file.extend(children);
+        let name = format!("ray_tracer_test_{}.vox", std::process::id());
+        let path = std::env::temp_dir().join(name);
+        fs::write(&path, file).unwrap();
+
+        let grid = VoxelGrid::from_vox(path.to_str().unwrap(), Vec3::zero(), 1.0, |c| {
+            Arc::new(Lambertian::new(c))
+        });
+        // removed before the asserts, so a failing test leaves nothing behind
+        fs::remove_file(&path).unwrap();
+        let grid = grid.unwrap();
+        // file (17, 2, 10) is (17, 10, 20 - 1 - 2) with y up
+        assert_eq!(grid.get([17, 10, 17]), 3);
+        let mut rec = HitRecord::new(Vec3::zero(), Vec3::zero(), 0.0, false, None);
+        let ray_ = Ray::new(Vec3::new(-5.0, 10.5, 17.25), Vec3::new(1.0, 0.0, 0.0), 0.0);
+        assert!(grid.hit(&ray_, Interval::new(0.001, f64::INFINITY), &mut rec));
+        assert!((rec.t - 22.0).abs() < 1e-9);
+        assert!((rec.normal - Vec3::new(-1.0, 0.0, 0.0)).length() < 1e-9);
+        assert!((rec.u - 0.25).abs() < 1e-9 && (rec.v - 0.5).abs() < 1e-9);
+    }
+}